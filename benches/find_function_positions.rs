@@ -0,0 +1,59 @@
+//! Benchmarks function extraction on a generated synthetic file set
+
+use std::fs;
+use std::hint::black_box;
+use std::path::PathBuf;
+use criterion::{criterion_group, criterion_main, Criterion};
+use docwen::c_parse::find_function_positions;
+
+/// Number of header/source pairs in the synthetic file set
+const FILE_PAIRS: usize = 50;
+
+/// Number of functions per file
+const FUNCTIONS_PER_FILE: usize = 200;
+
+/// Writes FILE_PAIRS header/source pairs with FUNCTIONS_PER_FILE documented functions each
+/// to the given directory and returns their paths.
+fn generate_files(dir: &tempfile::TempDir) -> Vec<PathBuf>
+{
+    let mut paths = Vec::with_capacity(FILE_PAIRS * 2);
+    for i in 0..FILE_PAIRS
+    {
+        let mut header = format!("#pragma once\n#include <stddef.h>\n\nnamespace module_{i}\n{{\n");
+        let mut source = format!("#include \"module_{i}.h\"\n\nnamespace module_{i}\n{{\n");
+        for f in 0..FUNCTIONS_PER_FILE
+        {
+            let doc = format!("    /**\n     * Computes value {f} of module {i}\n     * @param a The input\n     */\n");
+            header.push_str(&format!("{doc}    int func_{f}(int a, const char *name);\n\n"));
+            source.push_str(&format!("{doc}    int func_{f}(int a, const char *name)\n    {{\n        return a + {f};\n    }}\n\n"));
+        }
+        header.push_str("}\n");
+        source.push_str("}\n");
+
+        for (name, content) in [(format!("module_{i}.h"), header), (format!("module_{i}.cpp"), source)]
+        {
+            let path = dir.path().join(name);
+            fs::write(&path, content).expect("Failed to write synthetic file");
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+fn bench_find_function_positions(c: &mut Criterion)
+{
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let paths = generate_files(&dir);
+
+    c.bench_function("find_function_positions", |b| {
+        b.iter(|| find_function_positions(black_box(paths.iter().cloned()), true).expect("Extraction failed"))
+    });
+}
+
+criterion_group!
+{
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_find_function_positions
+}
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+//! Handles looking up the authors of doc lines with 'git blame' (for 'check --blame')
+
+use std::path::Path;
+use std::process::Command;
+
+/// Returns the author of the last change to the given (0-based) row of the file at the given path
+/// according to 'git blame'. Returns None if git is not available, the file is not inside a git
+/// repository or the row does not exist.
+pub fn last_author(path: &Path, row: usize) -> Option<String>
+{
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let line = row + 1;
+    let output = Command::new("git")
+        .arg("-C").arg(dir)
+        .args(["blame", "--porcelain", "-L", &format!("{line},{line}"), "--"])
+        .arg(path.file_name()?)
+        .output()
+        .ok()?;
+    if !output.status.success() { return None; }
+
+    String::from_utf8_lossy(&output.stdout).lines()
+        .find_map(|l| l.strip_prefix("author "))
+        .map(str::to_string)
+}
@@ -1,70 +1,901 @@
 //! Handles parsing c/c++ code
 
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use tree_sitter::{Parser, Node};
-use std::{collections::HashMap, fs, iter};
+use std::{collections::{HashMap, HashSet, VecDeque}, fs, iter};
 use anyhow::Context;
+use crate::cache::ExtractionCache;
+use crate::docfig::Settings;
+use crate::docfig::Mode::{MatchEnumDocs, MatchFunctionDocsUnqualified, MatchMacroDocs, MatchStructDocs};
 use crate::docwen_check::{FilePosition, FunctionID};
 
+/// Extracts all functions from the source text of a single file.
+/// Implementations are registered per file extension in an ExtractorRegistry.
+pub trait Extractor
+{
+    /// Extracts all functions from the given source text of the file at the given path.
+    fn extract(&mut self, source: &str, path: &Path) -> anyhow::Result<Vec<(FunctionID, FilePosition)>>;
+
+    /// Returns the text the docs of the extracted positions are read from: the given source with everything
+    /// the extractor ignores blanked out (keeping rows and columns), so docs and positions come from the same
+    /// lines. Extracting from the returned text yields the same positions. By default, this is the source itself.
+    fn doc_source<'a>(&self, source: &'a str) -> Cow<'a, str>
+    {
+        Cow::Borrowed(source)
+    }
+
+    /// Parses the given source like 'extract' and returns the (row, column) of its first syntax error
+    /// or None if it parses cleanly. Extractors that do not use a parser never report errors.
+    fn first_parse_error(&mut self, _source: &str) -> anyhow::Result<Option<(usize, usize)>>
+    {
+        Ok(None)
+    }
+}
+
+/// Options that influence which functions are extracted and how they are identified
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions
+{
+    pub use_qualifiers: bool, // Whether qualifiers are used to differentiate functions
+    pub max_scope_depth: Option<usize>, // Functions nested in more scopes than this are skipped
+    pub macros: bool, // Whether '#define' macros are extracted instead of functions
+    pub structs: bool, // Whether struct, class and union definitions are extracted instead of functions
+    pub enums: bool, // Whether enum definitions and their enumerators are extracted instead of functions
+    pub canonicalize_builtin_types: bool, // Whether equivalent builtin type spellings in params are unified
+    pub ignore_macro_declarations: bool, // Whether macro invocations that look like declarations are skipped
+    pub only_public: bool, // Whether functions with internal linkage are skipped
+    pub platform_defines: Option<Vec<String>> // Defines that select the active '#if' branches (None: all branches are kept)
+}
+
+impl ExtractOptions
+{
+    /// Returns the ExtractOptions defined by the given settings.
+    /// With several modes, the first one is used.
+    pub fn from_settings(settings: &Settings) -> Self
+    {
+        let mode = settings.mode.primary();
+        ExtractOptions
+        {
+            use_qualifiers: mode != MatchFunctionDocsUnqualified,
+            max_scope_depth: settings.max_scope_depth,
+            macros: mode == MatchMacroDocs,
+            structs: mode == MatchStructDocs,
+            enums: mode == MatchEnumDocs,
+            canonicalize_builtin_types: settings.canonicalize_builtin_types,
+            ignore_macro_declarations: settings.ignore_macro_declarations,
+            only_public: settings.only_public,
+            platform_defines: settings.platform_defines.clone()
+        }
+    }
+}
+
+/// Grammar used to parse C/C++ source files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language
+{
+    C,
+    Cpp
+}
+
+impl Language
+{
+    /// Returns the tree-sitter grammar of the language.
+    pub fn grammar(&self) -> tree_sitter::Language
+    {
+        match self
+        {
+            Language::C => tree_sitter_c::LANGUAGE.into(),
+            Language::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+        }
+    }
+}
+
+impl std::str::FromStr for Language
+{
+    type Err = anyhow::Error;
+
+    /// Parses "c" or "cpp" (case-insensitive).
+    fn from_str(s: &str) -> anyhow::Result<Self>
+    {
+        match s.to_ascii_lowercase().as_str()
+        {
+            "c" => Ok(Language::C),
+            "cpp" | "c++" => Ok(Language::Cpp),
+            _ => Err(anyhow::anyhow!("Unknown language '{s}' (expected one of: c, cpp)")),
+        }
+    }
+}
+
+/// Extracts functions from C/C++ source files via tree-sitter.
+pub struct CppExtractor
+{
+    parser: Parser,
+    options: ExtractOptions
+}
+
+impl CppExtractor
+{
+    /// Creates a new CppExtractor.
+    /// 'use_qualifiers' specifies whether qualifiers should be used to differentiate functions.
+    pub fn new(use_qualifiers: bool) -> anyhow::Result<Self>
+    {
+        Self::with_options(ExtractOptions{use_qualifiers, ..Default::default()})
+    }
+
+    /// Creates a new CppExtractor with the given ExtractOptions that parses files as C++.
+    pub fn with_options(options: ExtractOptions) -> anyhow::Result<Self>
+    {
+        Self::with_language(options, Language::Cpp)
+    }
+
+    /// Creates a new CppExtractor with the given ExtractOptions that parses files as the given Language.
+    pub fn with_language(options: ExtractOptions, language: Language) -> anyhow::Result<Self>
+    {
+        let mut parser = Parser::new();
+        parser.set_language(&language.grammar())?;
+        Ok(CppExtractor{parser, options})
+    }
+}
+
+impl Extractor for CppExtractor
+{
+    fn extract(&mut self, source: &str, path: &Path) -> anyhow::Result<Vec<(FunctionID, FilePosition)>>
+    {
+        let filtered: String = mask_preprocessor(&self.doc_source(source));
+        let tree = self.parser.parse(&filtered, None).with_context(|| "Failed to parse tree")?;
+
+        if self.options.structs
+        {
+            return Ok(extract_structs(tree.root_node(), &filtered, path, &self.options));
+        }
+        if self.options.enums
+        {
+            return Ok(extract_enums(tree.root_node(), &filtered, path, &self.options));
+        }
+        Ok(extract_functions(tree.root_node(), &filtered, path, &self.options))
+    }
+
+    fn first_parse_error(&mut self, source: &str) -> anyhow::Result<Option<(usize, usize)>>
+    {
+        let filtered: String = mask_preprocessor(&self.doc_source(source));
+        let tree = self.parser.parse(&filtered, None).with_context(|| "Failed to parse tree")?;
+        if !tree.root_node().has_error() { return Ok(None); }
+
+        let mut first = None;
+        visit_all_nodes(tree.root_node(), &mut |node| {
+            if first.is_none() && (node.is_error() || node.is_missing())
+            {
+                first = Some((node.start_position().row, node.start_position().column));
+            }
+        });
+        Ok(first)
+    }
+
+    /// Blanks out the inactive '#if' branches if 'platform_defines' are given. Preprocessor directives are kept,
+    /// since they end doc blocks.
+    fn doc_source<'a>(&self, source: &'a str) -> Cow<'a, str>
+    {
+        match &self.options.platform_defines
+        {
+            Some(defines) => Cow::Owned(mask_inactive_branches(source, defines)),
+            None => Cow::Borrowed(source),
+        }
+    }
+}
+
+/// Extracts functions from assembly source files by looking for global labels.
+/// Labels carry no signature, so their FunctionIDs have empty params and are matched
+/// against every function of the same name (see find_function_positions_with).
+pub struct AsmExtractor;
+
+impl Extractor for AsmExtractor
+{
+    fn extract(&mut self, source: &str, path: &Path) -> anyhow::Result<Vec<(FunctionID, FilePosition)>>
+    {
+        let mut functions = Vec::new();
+        for (row, line) in source.lines().enumerate()
+        {
+            let body = line.trim_start();
+            let label_len = body
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(body.len());
+            let label = &body[..label_len];
+
+            // Only 'name:' at the start of a line, no local ('.L1:') or numeric ('1:') labels
+            let is_label = !label.is_empty()
+                && !label.starts_with(|c: char| c.is_ascii_digit())
+                && body[label_len..].starts_with(':');
+
+            if is_label
+            {
+                let id = FunctionID{name: label.to_string(), params: String::new()};
+                let pos = FilePosition{
+                    path: path.to_path_buf(),
+                    row,
+                    column: line.len() - body.len(),
+                    is_definition: true, // A label is the implementation
+                    needs_declaration: false
+                };
+                functions.push((id, pos));
+            }
+        }
+        Ok(functions)
+    }
+}
+
+/// Extracts '#define' macros from C/C++ source files.
+/// Only the macro name and params are captured (from the first line of the definition), the body, including
+/// any continuation lines, is ignored. Function-like macros are keyed with their params (e.g. '(a, b)' for
+/// 'MAX(a,b)'), object-like macros have empty params.
+pub struct MacroExtractor;
+
+impl Extractor for MacroExtractor
+{
+    fn extract(&mut self, source: &str, path: &Path) -> anyhow::Result<Vec<(FunctionID, FilePosition)>>
+    {
+        let mut functions = Vec::new();
+        let mut continued = false;
+        for (row, line) in source.lines().enumerate()
+        {
+            let is_continuation = continued;
+            continued = line.trim_end().ends_with('\\');
+            if is_continuation { continue; } // Part of the previous directive's body
+
+            let body = line.trim_start();
+            let Some(definition) = body.strip_prefix('#')
+                .map(str::trim_start)
+                .and_then(|d| d.strip_prefix("define"))
+                .filter(|d| d.starts_with(char::is_whitespace))
+                .map(str::trim_start)
+            else { continue; };
+            let (name, rest) = definition.split_at(
+                definition.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(definition.len()));
+            if name.is_empty() { continue; }
+
+            // Function-like macros have their '(' directly after the name, object-like ones have no params
+            let params = match rest.starts_with('(')
+            {
+                true => normalize_params(rest.find(')').map_or(rest.trim_end_matches('\\'), |end| &rest[..=end])),
+                false => String::new(),
+            };
+
+            let id = FunctionID{name: name.to_string(), params};
+            let pos = FilePosition{
+                path: path.to_path_buf(),
+                row,
+                column: line.len() - body.len(),
+                is_definition: true, // A macro is always defined where it is declared
+                needs_declaration: false
+            };
+            functions.push((id, pos));
+        }
+        Ok(functions)
+    }
+}
+
+/// Extracts function sections from markdown files (e.g. a hand-written API reference).
+/// Every header whose text is a function name (e.g. '### add', '## `ns::add()`') starts a section.
+/// Headers carry no signature, so their params are empty. The section text is read by
+/// LineSource::markdown_section.
+pub struct MarkdownExtractor;
+
+impl Extractor for MarkdownExtractor
+{
+    fn extract(&mut self, source: &str, path: &Path) -> anyhow::Result<Vec<(FunctionID, FilePosition)>>
+    {
+        let mut functions = Vec::new();
+        let mut in_fence = false;
+        for (row, line) in source.lines().enumerate()
+        {
+            let body = line.trim_start();
+            if body.starts_with("```") { in_fence = !in_fence; }
+            if in_fence { continue; }
+
+            let Some(name) = markdown_header_name(body) else { continue; };
+            let id = FunctionID{name: name.to_string(), params: String::new()};
+            let pos = FilePosition{
+                path: path.to_path_buf(),
+                row,
+                column: line.len() - body.len(),
+                is_definition: false, // The reference only describes the function
+                needs_declaration: false
+            };
+            functions.push((id, pos));
+        }
+        Ok(functions)
+    }
+}
+
+/// Returns the function name of the given trimmed markdown line if it is a header naming a function.
+fn markdown_header_name(line: &str) -> Option<&str>
+{
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) { return None; }
+
+    let text = line[level..].strip_prefix(' ')?.trim().trim_end_matches('#').trim();
+    let text = text.strip_prefix('`').and_then(|t| t.strip_suffix('`')).unwrap_or(text);
+    let name = text.split_once('(').map_or(text, |(name, _)| name).trim_end();
+
+    let is_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == ':' || c == '~')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':' || c == '~');
+    is_name.then_some(name)
+}
+
+/// Returns whether the file at the given path is a markdown file.
+pub fn is_markdown(path: &Path) -> bool
+{
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("md"))
+}
+
+/// Maps (lowercase) file extensions to the Extractor used for files with that extension.
+/// Files with an unregistered extension are handled by the C/C++ extractor.
+pub struct ExtractorRegistry
+{
+    extractors: HashMap<String, Box<dyn Extractor>>,
+    fallback: Box<dyn Extractor>
+}
+
+impl ExtractorRegistry
+{
+    /// Creates a registry with the built-in extractors ("s" and "asm" for assembly, "md" for markdown,
+    /// C/C++ for everything else).
+    /// 'use_qualifiers' specifies whether qualifiers should be used to differentiate functions.
+    pub fn new(use_qualifiers: bool) -> anyhow::Result<Self>
+    {
+        Self::with_options(&ExtractOptions{use_qualifiers, ..Default::default()})
+    }
+
+    /// Creates a registry with the built-in extractors like ExtractorRegistry::new,
+    /// using the given ExtractOptions. If the options select macros, all files are handled by
+    /// the MacroExtractor.
+    pub fn with_options(options: &ExtractOptions) -> anyhow::Result<Self>
+    {
+        if options.macros
+        {
+            return Ok(ExtractorRegistry{extractors: HashMap::new(), fallback: Box::new(MacroExtractor)});
+        }
+
+        let mut registry = ExtractorRegistry{
+            extractors: HashMap::new(),
+            fallback: Box::new(CppExtractor::with_options(options.clone())?)
+        };
+        registry.register("s", Box::new(AsmExtractor));
+        registry.register("asm", Box::new(AsmExtractor));
+        registry.register("md", Box::new(MarkdownExtractor));
+        Ok(registry)
+    }
+
+    /// Creates a registry with one default extractor registered for each of the given extensions.
+    pub fn for_extensions<I, S>(extensions: I, options: &ExtractOptions) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut registry = Self::with_options(options)?;
+        for ext in extensions
+        {
+            let extractor = default_extractor(ext.as_ref(), options)?;
+            registry.register(ext.as_ref(), extractor);
+        }
+        Ok(registry)
+    }
+
+    /// Creates a registry with one default extractor for each extension of the given settings.
+    pub fn from_settings(settings: &Settings) -> anyhow::Result<Self>
+    {
+        Self::for_extensions(&settings.match_extensions, &ExtractOptions::from_settings(settings))
+    }
+
+    /// Registers the given extractor for files with the given extension (case-insensitive).
+    /// Replaces any extractor previously registered for that extension.
+    pub fn register(&mut self, extension: &str, extractor: Box<dyn Extractor>)
+    {
+        self.extractors.insert(extension.to_ascii_lowercase(), extractor);
+    }
+
+    /// Replaces the extractor used for files whose extension has no registered extractor.
+    pub fn set_fallback(&mut self, extractor: Box<dyn Extractor>)
+    {
+        self.fallback = extractor;
+    }
+
+    /// Returns the extractor responsible for the file at the given path.
+    pub fn extractor_for(&mut self, path: &Path) -> &mut dyn Extractor
+    {
+        let ext = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        match self.extractors.get_mut(&ext)
+        {
+            Some(extractor) => extractor.as_mut(),
+            None => self.fallback.as_mut(),
+        }
+    }
+}
+
+/// Returns the default extractor for files of the given extension.
+pub fn default_extractor(extension: &str, options: &ExtractOptions) -> anyhow::Result<Box<dyn Extractor>>
+{
+    match extension.to_ascii_lowercase().as_str()
+    {
+        _ if options.macros => Ok(Box::new(MacroExtractor)),
+        "s" | "asm" => Ok(Box::new(AsmExtractor)),
+        "md" => Ok(Box::new(MarkdownExtractor)),
+        _ => Ok(Box::new(CppExtractor::with_options(options.clone())?)),
+    }
+}
+
+/// Extracts all functions of the file at the given path with the extractor for its extension.
+/// 'use_qualifiers' specifies whether qualifiers are part of the returned FunctionIDs
+/// (see FunctionID for the identity rules).
+pub fn parse_file_functions(path: impl AsRef<Path>, use_qualifiers: bool)
+    -> anyhow::Result<Vec<(FunctionID, FilePosition)>>
+{
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut registry = ExtractorRegistry::new(use_qualifiers)?;
+    registry.extractor_for(path).extract(&source, path)
+}
+
+/// Positions of all functions by FunctionID
+pub type FunctionPositions = HashMap<FunctionID, Vec<FilePosition>>;
+
 /// Finds all function matches (based on qualifiers, name and parameters)
 /// in the given list of files. Maps them by FunctionID -> Vec<FilePosition>.
-/// 'use_qualifiers' specifies whether qualifiers should be used to differentiate as well
+/// 'use_qualifiers' specifies whether qualifiers should be used to differentiate as well.
+/// Paths that resolve to the same file (e.g. a symlink and its target) are only parsed once.
 pub fn find_function_positions<I>(paths: I, use_qualifiers: bool)
     -> anyhow::Result<HashMap<FunctionID, Vec<FilePosition>>>
 where
     I: IntoIterator<Item = PathBuf>,
+{
+    Ok(find_function_positions_with_sources(paths, use_qualifiers)?.0)
+}
+
+/// Finds all function matches like find_function_positions.
+/// Additionally returns the doc source (see Extractor::doc_source) of each file, which the docs of
+/// the positions have to be read from.
+pub fn find_function_positions_with_sources<I>(paths: I, use_qualifiers: bool)
+    -> anyhow::Result<(FunctionPositions, HashMap<PathBuf, String>)>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    let mut seen = HashSet::new();
+    let mut registry = ExtractorRegistry::new(use_qualifiers)?;
+    let sources = paths.into_iter()
+        .filter(|p| seen.insert(fs::canonicalize(p).unwrap_or_else(|_| p.clone())))
+        .map(|p| {
+            let src = fs::read_to_string(&p)?;
+            let src = registry.extractor_for(&p).doc_source(&src).into_owned();
+            Ok((p, src))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let positions = find_function_positions_with(sources.iter().map(|(p, src)| (p.as_path(), src.as_str())),
+                                                 &mut registry, None)?;
+    Ok((positions, sources.into_iter().collect()))
+}
+
+/// Finds all function matches in the given (path, source text) pairs like find_function_positions,
+/// but dispatches each file to the extractor the given registry holds for its extension.
+/// If a cache is given, unchanged files reuse their cached extraction instead of being reparsed.
+/// Functions without a signature (empty params) are matched with all functions of the same name.
+pub fn find_function_positions_with<'a, I>(sources: I, registry: &mut ExtractorRegistry,
+                                           cache: Option<&mut ExtractionCache>)
+    -> anyhow::Result<HashMap<FunctionID, Vec<FilePosition>>>
+where
+    I: IntoIterator<Item = (&'a Path, &'a str)>,
+{
+    let mut functions = collect_function_positions(sources, registry, cache)?;
+    functions.retain(|_, vec| vec.len() > 1 );
+    Ok(functions)
+}
+
+/// Collects the positions of all functions in the given (path, source text) pairs like
+/// find_function_positions_with, including the functions that only occur once.
+pub fn collect_function_positions<'a, I>(sources: I, registry: &mut ExtractorRegistry,
+                                         mut cache: Option<&mut ExtractionCache>)
+    -> anyhow::Result<HashMap<FunctionID, Vec<FilePosition>>>
+where
+    I: IntoIterator<Item = (&'a Path, &'a str)>,
+{
+    let mut functions: HashMap<FunctionID, Vec<FilePosition>> = HashMap::new();
+    for (path, source) in sources
+    {
+        let extractor = registry.extractor_for(path);
+        let extracted = match cache.as_deref_mut()
+        {
+            Some(cache) => cache.extract(path, source, extractor)?,
+            None => extractor.extract(source, path)?,
+        };
+
+        for (id, pos) in extracted
+        {
+            functions.entry(id).or_default().push(pos);
+        }
+    }
+
+    // MERGE FUNCTIONS WITHOUT SIGNATURE INTO ALL SIGNED FUNCTIONS OF THE SAME NAME
+    let unsigned: Vec<FunctionID> = functions.keys().filter(|id| id.params.is_empty()).cloned().collect();
+    for id in unsigned
+    {
+        let matches: Vec<FunctionID> = functions.keys()
+            .filter(|other| !other.params.is_empty() && other.name == id.name)
+            .cloned()
+            .collect();
+
+        if matches.is_empty() { continue; }
+        let positions = functions.remove(&id).unwrap_or_default();
+        for m in matches
+        {
+            functions.entry(m).or_default().extend(positions.iter().cloned());
+        }
+    }
+
+    Ok(functions)
+}
+
+/// Pairs every 'override' (or 'final') marked method in the given (path, source text) pairs with the
+/// declaration of the same name and params in its nearest base class.
+/// Returns the FunctionID of the overriding method (with qualifiers) together with the positions
+/// of the base declaration and the override.
+pub fn find_override_pairs<'a, I>(sources: I) -> anyhow::Result<Vec<(FunctionID, Vec<FilePosition>)>>
+where
+    I: IntoIterator<Item = (&'a Path, &'a str)>,
 {
     let mut parser = Parser::new();
     parser.set_language(&tree_sitter_cpp::LANGUAGE.into())?;
 
-    let mut functions: HashMap<FunctionID, Vec<FilePosition>> = HashMap::new();
-    for path in paths
+    let mut classes: HashMap<String, Vec<String>> = HashMap::new();
+    let mut methods: Vec<ClassMethod> = Vec::new();
+    for (path, source) in sources
     {
-        let source = fs::read_to_string(&path)?;
+        let filtered = mask_preprocessor(source);
+        let tree = parser.parse(&filtered, None).with_context(|| "Failed to parse tree")?;
+        extract_class_methods(tree.root_node(), &filtered, path, &mut classes, &mut methods);
+    }
 
-        let filtered: String = mask_preprocessor(&source);
+    let mut pairs = Vec::new();
+    for method in methods.iter().filter(|m| m.is_override)
+    {
+        // BREADTH-FIRST SEARCH THROUGH THE BASE CLASSES FOR THE NEAREST DECLARATION
+        let mut queue: VecDeque<&String> = classes.get(&method.class).into_iter().flatten().collect();
+        let mut visited: HashSet<&String> = HashSet::new();
+        while let Some(base) = queue.pop_front()
+        {
+            let Some(base_class) = resolve_class(base, &classes) else { continue; };
+            if !visited.insert(base_class) { continue; }
+
+            let base_method = methods.iter()
+                .find(|m| &m.class == base_class && m.id == method.id);
+            if let Some(base_method) = base_method
+            {
+                let id = FunctionID{name: format!("{}::{}", method.class, method.id.name), params: method.id.params.clone()};
+                pairs.push((id, vec![base_method.position.clone(), method.position.clone()]));
+                break;
+            }
+            queue.extend(classes.get(base_class).into_iter().flatten());
+        }
+    }
+    Ok(pairs)
+}
+
+/// Pairs every explicit function template specialization (e.g. 'template<> void f<int>(int);') in the given
+/// (path, source text) pairs with the first primary function template of the same unqualified name
+/// (e.g. 'template<class T> void f(T);'). Params are not compared, since they differ by definition.
+/// Returns the FunctionID of the specialization (e.g. 'f<int>') together with the positions of the
+/// primary template and the specialization.
+pub fn find_specialization_pairs<'a, I>(sources: I) -> anyhow::Result<Vec<(FunctionID, Vec<FilePosition>)>>
+where
+    I: IntoIterator<Item = (&'a Path, &'a str)>,
+{
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_cpp::LANGUAGE.into())?;
+
+    let mut primaries: HashMap<String, FilePosition> = HashMap::new();
+    let mut specializations: Vec<(FunctionID, FilePosition)> = Vec::new();
+    for (path, source) in sources
+    {
+        let filtered = mask_preprocessor(source);
         let tree = parser.parse(&filtered, None).with_context(|| "Failed to parse tree")?;
 
-        let root = tree.root_node();
-        extract_functions(root, &filtered, path, &mut functions, use_qualifiers);
+        visit_all_nodes(tree.root_node(), &mut |node| {
+            if !matches!(node.kind(), "function_definition" | "function_declarator") || has_definition_ancestor(node)
+            {
+                return;
+            }
+            let Some(template) = enclosing_template(node) else { return; };
+            let Some(declarator) = find_declarator(node) else { return; };
+            let Some(Ok(name)) = declarator.child_by_field_name("declarator")
+                .map(|n| n.utf8_text(filtered.as_bytes())) else { return; };
+
+            let id = FunctionID{
+                name: normalize_name(name),
+                params: get_name_and_params(declarator, &filtered).1.unwrap_or_default()
+            };
+            let pos = FilePosition{
+                path: path.to_path_buf(),
+                row: node.start_position().row,
+                column: node.start_position().column,
+                is_definition: node.kind() == "function_definition",
+                needs_declaration: false
+            };
+
+            // 'template<>' has no parameters
+            let is_specialization = template.child_by_field_name("parameters")
+                .is_some_and(|p| p.named_child_count() == 0);
+            if is_specialization { specializations.push((id, pos)); }
+            else { primaries.entry(unqualified_template_name(&id.name).to_string()).or_insert(pos); }
+        });
     }
 
-    functions.retain(|_, vec| vec.len() > 1 );
-    Ok(functions)
+    Ok(specializations.into_iter()
+        .filter_map(|(id, pos)| {
+            let primary = primaries.get(unqualified_template_name(&id.name))?;
+            Some((id, vec![primary.clone(), pos]))
+        })
+        .collect())
+}
+
+/// Returns the template_declaration the function at the given node is directly declared in, if any
+/// (e.g. not for methods of class templates).
+fn enclosing_template(node: Node) -> Option<Node>
+{
+    let mut current = node;
+    while let Some(parent) = current.parent()
+    {
+        match parent.kind()
+        {
+            "template_declaration" => return Some(parent),
+            "declaration_list" | "field_declaration_list" | "translation_unit" | "compound_statement" => return None,
+            _ => current = parent,
+        }
+    }
+    None
+}
+
+/// Returns the given function name without its qualifiers and template arguments
+/// (e.g. 'ns::f<std::pair<int, int>>' -> 'f').
+fn unqualified_template_name(name: &str) -> &str
+{
+    let mut base = name;
+    if name.ends_with('>')
+    {
+        // FIND THE '<' MATCHING THE FINAL '>'
+        let mut depth = 0;
+        for (i, c) in name.char_indices().rev()
+        {
+            match c
+            {
+                '>' => depth += 1,
+                '<' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 { base = &name[..i]; break; }
+        }
+    }
+    base.rsplit("::").next().unwrap_or(base)
+}
+
+/// Finds the files among the given (path, source text) pairs that are '#include'd by another one of them
+/// inside class, struct, union or namespace scopes (e.g. a '.inl' included inside the namespace of its '.h').
+/// Returns the enclosing scope names (outermost first) of the first such include per included file.
+/// Quoted includes are resolved relative to the including file, or else matched against the end of the paths.
+pub fn find_include_scopes<'a, I>(sources: I) -> anyhow::Result<HashMap<PathBuf, Vec<String>>>
+where
+    I: IntoIterator<Item = (&'a Path, &'a str)>,
+{
+    let sources: Vec<(&Path, &str)> = sources.into_iter().collect();
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_cpp::LANGUAGE.into())?;
+
+    let mut scopes: HashMap<PathBuf, Vec<String>> = HashMap::new();
+    for (path, source) in &sources
+    {
+        let includes: Vec<(usize, &str)> = source.lines().enumerate()
+            .filter_map(|(row, line)| {
+                let rest = line.trim_start().strip_prefix('#')?.trim_start().strip_prefix("include")?;
+                let (included, _) = rest.trim_start().strip_prefix('"')?.split_once('"')?;
+                Some((row, included))
+            })
+            .collect();
+        if includes.is_empty() { continue; }
+
+        let filtered = mask_preprocessor(source);
+        let tree = parser.parse(&filtered, None).with_context(|| "Failed to parse tree")?;
+        for (row, included) in includes
+        {
+            // RESOLVE THE INCLUDED FILE WITHIN THE GIVEN SOURCES
+            let relative = crate::toml_manager::normalize_path(path.parent().unwrap_or(Path::new("")).join(included));
+            let Some((target, _)) = sources.iter()
+                .find(|(p, _)| *p == relative)
+                .or_else(|| sources.iter().find(|(p, _)| p.ends_with(included)))
+            else { continue; };
+            if target == path || scopes.contains_key(*target) { continue; }
+
+            // THE MASKED INCLUDE LINE IS WHITESPACE INSIDE THE BODY OF ITS ENCLOSING SCOPE
+            let point = tree_sitter::Point{row, column: 0};
+            let Some(node) = tree.root_node().descendant_for_point_range(point, point) else { continue; };
+            let qualifiers = get_scope_names(node, &filtered,
+                                             &["class_specifier", "struct_specifier", "union_specifier", "namespace_definition"]);
+            if !qualifiers.is_empty()
+            {
+                scopes.insert(target.to_path_buf(), qualifiers);
+            }
+        }
+    }
+    Ok(scopes)
 }
 
 /// Extracts all functions from the tree spanned by the given root node.
-/// Uses the given source text and file path to insert the functions into the given map.
-/// 'use_qualifiers' defines whether qualifiers are used to differentiate functions instead of
-/// basic name and param matching.
-pub fn extract_functions(root: Node, source: &str, file: PathBuf,
-                     map: &mut HashMap<FunctionID, Vec<FilePosition>>, use_qualifiers: bool)
+/// Uses the given source text and file path to build the (FunctionID, FilePosition) pairs.
+/// The given ExtractOptions define whether qualifiers are used to differentiate functions instead of
+/// basic name and param matching and up to which scope depth functions are extracted.
+pub fn extract_functions(root: Node, source: &str, file: &Path, options: &ExtractOptions)
+    -> Vec<(FunctionID, FilePosition)>
 {
+    let mut functions = Vec::new();
+
     // Recursively visit all nodes and apply the function
     visit_all_nodes(root, &mut |node| {
         match node.kind()
         {
-            "function_definition" | "function_declarator" if !has_definition_ancestor(node) =>
+            "function_definition" | "function_declarator"
+                if !has_definition_ancestor(node) && !is_returned_function_pointer(node) =>
                 {
-                    if let Some(id) = get_function_id(node, source, use_qualifiers)
+                    if options.max_scope_depth.is_some_and(|max| get_scope_depth(node, source) > max)
+                    {
+                        return;
+                    }
+
+                    if options.ignore_macro_declarations && is_macro_invocation(node, source)
+                    {
+                        return;
+                    }
+
+                    if options.only_public && is_internal_function(node, source)
+                    {
+                        return;
+                    }
+
+                    if let Some(id) = get_function_id_with(node, source, options)
                     {
                         let pos = FilePosition{
-                            path: file.clone(),
+                            path: file.to_path_buf(),
                             row: node.start_position().row,
-                            column: node.start_position().column
+                            column: node.start_position().column,
+                            is_definition: node.kind() == "function_definition",
+                            needs_declaration: node.kind() == "function_definition"
+                                && !has_internal_linkage(node, source) && !is_in_class_body(node)
                         };
 
-                        let entry = map.entry(id).or_insert(Vec::new());
-                        entry.push(pos);
+                        functions.push((id, pos));
                     }
                 },
 
                 _ => {}
         }
     });
+
+    functions
+}
+
+/// Extracts the definitions (with a body) of all named structs, classes and unions from the given tree
+/// with their qualified type name as name and empty params. Forward declarations (e.g. 'struct Foo;') and
+/// types used in other declarations (e.g. 'struct Foo *p') have no body and are skipped, so they are never
+/// compared with the definition. Unnamed types get the name of their typedef ('typedef struct { ... } Foo;').
+/// Types defined inside functions are skipped. The position of a type is the start of its template or typedef.
+pub fn extract_structs(root: Node, source: &str, file: &Path, options: &ExtractOptions)
+    -> Vec<(FunctionID, FilePosition)>
+{
+    let mut structs = Vec::new();
+    visit_all_nodes(root, &mut |node| {
+        if !matches!(node.kind(), "struct_specifier" | "class_specifier" | "union_specifier")
+            || node.child_by_field_name("body").is_none()
+            || has_definition_ancestor(node)
+        {
+            return;
+        }
+
+        let parent = node.parent().filter(|p| matches!(p.kind(), "template_declaration" | "type_definition"));
+        let name_node = node.child_by_field_name("name").or_else(|| {
+            parent.filter(|p| p.kind() == "type_definition").and_then(|p| p.child_by_field_name("declarator"))
+        });
+        let Some(Ok(name)) = name_node.map(|n| n.utf8_text(source.as_bytes())) else { return; };
+
+        let qualifiers = get_qualifiers(node, source);
+        if options.max_scope_depth.is_some_and(|max| qualifiers.len() > max) { return; }
+
+        let anchor = parent.unwrap_or(node);
+        let id = FunctionID{
+            name: normalize_name(&qualifiers.iter().map(String::as_str).chain([name]).collect::<Vec<_>>().join("::")),
+            params: String::new()
+        };
+        let pos = FilePosition{
+            path: file.to_path_buf(),
+            row: anchor.start_position().row,
+            column: anchor.start_position().column,
+            is_definition: true,
+            needs_declaration: false
+        };
+        structs.push((id, pos));
+    });
+    structs
+}
+
+/// Extracts the definitions (with a body) of all named enums (including 'enum class') and their enumerators
+/// from the given tree. Enums are named like in extract_structs, enumerators are named after their enum
+/// (e.g. 'Color::Red'), so they are matched by name regardless of their values. All params are empty.
+/// The position of an enumerator is the start of its name, so the docs above it and the comment after it
+/// on its line belong to it.
+pub fn extract_enums(root: Node, source: &str, file: &Path, options: &ExtractOptions)
+    -> Vec<(FunctionID, FilePosition)>
+{
+    let mut enums = Vec::new();
+    visit_all_nodes(root, &mut |node| {
+        let Some(body) = node.child_by_field_name("body") else { return; };
+        if node.kind() != "enum_specifier" || has_definition_ancestor(node) { return; }
+
+        let parent = node.parent().filter(|p| matches!(p.kind(), "template_declaration" | "type_definition"));
+        let name_node = node.child_by_field_name("name").or_else(|| {
+            parent.filter(|p| p.kind() == "type_definition").and_then(|p| p.child_by_field_name("declarator"))
+        });
+        let Some(Ok(name)) = name_node.map(|n| n.utf8_text(source.as_bytes())) else { return; };
+
+        let qualifiers = get_qualifiers(node, source);
+        if options.max_scope_depth.is_some_and(|max| qualifiers.len() > max) { return; }
+
+        let name = normalize_name(&qualifiers.iter().map(String::as_str).chain([name]).collect::<Vec<_>>().join("::"));
+        let position = |n: Node| FilePosition{
+            path: file.to_path_buf(),
+            row: n.start_position().row,
+            column: n.start_position().column,
+            is_definition: true,
+            needs_declaration: false
+        };
+        enums.push((FunctionID{name: name.clone(), params: String::new()}, position(parent.unwrap_or(node))));
+
+        let mut cursor = body.walk();
+        for enumerator in body.named_children(&mut cursor).filter(|n| n.kind() == "enumerator")
+        {
+            let Some(Ok(enumerator_name)) = enumerator.child_by_field_name("name").map(|n| n.utf8_text(source.as_bytes()))
+                else { continue; };
+            enums.push((FunctionID{name: format!("{name}::{enumerator_name}"), params: String::new()}, position(enumerator)));
+        }
+    });
+    enums
+}
+
+/// Returns whether the given function_definition or function_declarator node is most likely the
+/// invocation of a declaration generating macro (e.g. 'DECLARE_API(foo);'):
+/// Its name is all-caps and no return type precedes it.
+pub fn is_macro_invocation(node: Node, source: &str) -> bool
+{
+    let Some(name) = find_declarator(node).and_then(|d| get_name_and_params(d, source).0) else { return false; };
+    let name = name.rsplit("::").next().unwrap_or(&name);
+    let is_all_caps = name.chars().any(|c| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    if !is_all_caps { return false; }
+
+    // STATEMENT POSITION: NO RETURN TYPE
+    if node.kind() == "function_definition" { return node.child_by_field_name("type").is_none(); }
+    match node.parent()
+    {
+        Some(parent) if matches!(parent.kind(), "declaration" | "field_declaration") =>
+            parent.child_by_field_name("type").is_none(),
+        Some(_) => node.prev_sibling().is_none(), // E.g. the first part of an ERROR node
+        None => true,
+    }
 }
 
 /// Returns the full (optionally: qualified) function signature as a FunctionID.
+/// Only the declarator is used, so specifiers like 'inline', 'static', 'constexpr', 'virtual' and
+/// 'explicit' (siblings of the declarator) do not affect the identity.
 /// If no FunctionID can be derived from the given node, None is returned.
 pub fn get_function_id(node: Node, source: &str, with_qualifiers: bool) -> Option<FunctionID>
 {
@@ -86,6 +917,17 @@ pub fn get_function_id(node: Node, source: &str, with_qualifiers: bool) -> Optio
     }
 }
 
+/// Returns the FunctionID of the given node like get_function_id, using the given ExtractOptions.
+pub fn get_function_id_with(node: Node, source: &str, options: &ExtractOptions) -> Option<FunctionID>
+{
+    let mut id = get_function_id(node, source, options.use_qualifiers)?;
+    if options.canonicalize_builtin_types
+    {
+        id.params = canonicalize_builtin_types(&id.params);
+    }
+    Some(id)
+}
+
 /// Returns whether the given node has a 'function_definition' as an ancestor.
 /// This way you can avoid tracking a function twice.
 pub fn has_definition_ancestor(mut n: Node) -> bool
@@ -99,12 +941,19 @@ pub fn has_definition_ancestor(mut n: Node) -> bool
 }
 
 /// Walks from the given node until the function_declarator is found.
+/// For functions returning a function pointer (e.g. 'void (*get_handler(int))(int)') this is the
+/// inner declarator holding the name and params of the function, not the one of the returned pointer.
 /// Returns None if it could not be found.
 pub fn find_declarator(n: Node) -> Option<Node>
 {
     if n.kind() == "function_declarator"
     {
-        return Some(n);
+        let mut declarator = n;
+        while let Some(inner) = returning_declarator(declarator)
+        {
+            declarator = inner;
+        }
+        return Some(declarator);
     }
     let mut cur = n.walk();
     for child in n.children(&mut cur)
@@ -117,13 +966,44 @@ pub fn find_declarator(n: Node) -> Option<Node>
     None
 }
 
+/// Returns the function_declarator wrapped (through parentheses, pointers and references) by the
+/// 'declarator' field of the given function_declarator, i.e. the declarator of the function that returns
+/// what the given declarator declares (e.g. 'get_handler(int)' in 'void (*get_handler(int))(int)').
+fn returning_declarator(declarator: Node) -> Option<Node>
+{
+    let mut node = declarator.child_by_field_name("declarator")?;
+    loop
+    {
+        match node.kind()
+        {
+            "function_declarator" => return Some(node),
+            "parenthesized_declarator" | "pointer_declarator" | "reference_declarator" =>
+                {
+                    let mut cur = node.walk();
+                    let inner = node.named_children(&mut cur).find(|c| c.kind().ends_with("declarator"))?;
+                    node = inner;
+                },
+            _ => return None,
+        }
+    }
+}
+
+/// Returns whether the given node is the declarator of a function pointer returned by a function
+/// (e.g. '(*get_handler(int))(int)'). The function itself is tracked by its inner declarator.
+pub fn is_returned_function_pointer(node: Node) -> bool
+{
+    node.kind() == "function_declarator" && returning_declarator(node).is_some()
+}
+
 /// Gets ((optional) Name, (optional) Params) of the given declarator node based on the given
-/// source text.
+/// source text. The trailing cv- and ref-qualifiers of member functions are part of the params
+/// (e.g. '() const &'), so that overloads that only differ in them are distinguished.
 pub fn get_name_and_params(declarator: Node, source: &str) -> (Option<String>, Option<String>)
 {
     let mut cur = declarator.walk();
     let mut name: Option<String>   = None;
     let mut params: Option<String> = None;
+    let mut qualifiers: Vec<&str> = Vec::new();
 
     // WALK THROUGH DECLARATOR TO FIND NAME AND PARAMS
     for child in declarator.children(&mut cur)
@@ -135,7 +1015,7 @@ pub fn get_name_and_params(declarator: Node, source: &str) -> (Option<String>, O
                 {
                     if let Ok(txt) = child.utf8_text(source.as_bytes())
                     {
-                        name = Some(txt.to_string())
+                        name = Some(normalize_name(txt))
                     }
                 },
 
@@ -143,46 +1023,473 @@ pub fn get_name_and_params(declarator: Node, source: &str) -> (Option<String>, O
                 {
                     if let Ok(txt) = child.utf8_text(source.as_bytes())
                     {
-                        params = Some(txt.to_string())
+                        params = Some(normalize_params(txt))
+                    }
+                },
+
+            "type_qualifier" | "ref_qualifier" =>
+                {
+                    if let Ok(txt) = child.utf8_text(source.as_bytes())
+                    {
+                        qualifiers.push(txt.trim())
                     }
                 },
             _ => {}
         }
     }
+
+    let params = match qualifiers.is_empty()
+    {
+        true => params,
+        false => params.map(|p| format!("{p} {}", qualifiers.join(" "))),
+    };
     (name, params)
 }
 
+/// Normalizes the spelling of the given (optionally qualified) function name so that differently
+/// spaced names match (e.g. 'operator ==' -> 'operator==', 'A :: operator ()' -> 'A::operator()').
+/// Whitespace is removed, except for a single space between two words (e.g. 'operator new').
+pub fn normalize_name(name: &str) -> String
+{
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut normalized = String::with_capacity(name.len());
+    let mut pending_space = false;
+    for c in name.chars()
+    {
+        if c.is_whitespace()
+        {
+            pending_space = true;
+            continue;
+        }
+
+        if pending_space && is_word_char(c) && normalized.ends_with(is_word_char) { normalized.push(' '); }
+        pending_space = false;
+        normalized.push(c);
+    }
+    normalized
+}
+
+/// Normalizes the given parameter_list text so that differently formatted lists match:
+/// Comments are stripped and the tokens are joined with canonical spacing: a single space between
+/// two words (so 'const int' stays intact), after each comma and around each '=' of a default argument,
+/// a space before (but not after) each pointer or reference declarator, a space between the closing ')' and
+/// trailing qualifiers (e.g. '() const') and no space around any other punctuation.
+/// E.g. '( int  x,char* p , const std::vector<int> & v )' -> '(int x, char *p, const std::vector<int> &v)'.
+pub fn normalize_params(params: &str) -> String
+{
+    // STRIP COMMENTS
+    let mut stripped = String::with_capacity(params.len());
+    let mut rest = params;
+    while let Some(start) = rest.find("/")
+    {
+        let (before, after) = rest.split_at(start);
+        stripped.push_str(before);
+        if let Some(comment) = after.strip_prefix("//")
+        {
+            rest = comment.find('\n').map_or("", |end| &comment[end..]);
+        }
+        else if let Some(comment) = after.strip_prefix("/*")
+        {
+            stripped.push(' ');
+            rest = comment.find("*/").map_or("", |end| &comment[end + 2..]);
+        }
+        else
+        {
+            stripped.push('/');
+            rest = &after[1..];
+        }
+    }
+    stripped.push_str(rest);
+
+    // JOIN TOKENS WITH CANONICAL SPACING
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let is_declarator = |c: char| c == '*' || c == '&';
+    let mut normalized = String::with_capacity(stripped.len());
+    let mut pending_space = false;
+    for c in stripped.chars()
+    {
+        if c.is_whitespace()
+        {
+            pending_space = true;
+            continue;
+        }
+
+        let previous = normalized.chars().next_back();
+        let space = match previous
+        {
+            Some(',') => c != ')',
+            Some('=') => c != '=',
+            Some(p) if c == '=' => p != '=' && p != '(',
+            Some(p) if is_word_char(p) && is_word_char(c) => pending_space,
+            Some(')') => is_word_char(c) || is_declarator(c),
+            Some(p) if is_declarator(c) => is_word_char(p) || matches!(p, ')' | '>' | ']'),
+            _ => false,
+        };
+        if space { normalized.push(' '); }
+        normalized.push(c);
+        pending_space = false;
+    }
+    normalized
+}
+
+/// Returns the number of parameters of the given (normalized) params, e.g. 2 for '(int a,std::map<int, int> b)'.
+/// '()' and '(void)' have no parameters. Trailing qualifiers (e.g. '() const') are ignored.
+pub fn param_count(params: &str) -> usize
+{
+    let inner = params.trim().strip_prefix('(').and_then(|p| p.rfind(')').map(|end| &p[..end])).unwrap_or(params).trim();
+    if inner.is_empty() || inner == "void" { return 0; }
+
+    let mut depth = 0usize;
+    let mut count = 1;
+    for c in inner.chars()
+    {
+        match c
+        {
+            '(' | '<' | '[' | '{' => depth += 1,
+            ')' | '>' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => count += 1,
+            _ => {},
+        }
+    }
+    count
+}
+
+/// Keywords that form the spelling of builtin arithmetic types
+const BUILTIN_TYPE_WORDS: [&str; 7] = ["signed", "unsigned", "short", "long", "int", "char", "double"];
+
+/// Rewrites all builtin arithmetic types in the given (normalized) params to a normal form,
+/// so that equivalent spellings match (e.g. 'unsigned' -> 'unsigned int', 'long int' -> 'long',
+/// 'signed' -> 'int', 'long unsigned int' -> 'unsigned long').
+pub fn canonicalize_builtin_types(params: &str) -> String
+{
+    let mut out = String::with_capacity(params.len());
+    let mut run: Vec<&str> = Vec::new(); // Consecutive builtin type keywords
+    let mut space_after_run = false;
+    let mut rest = params;
+
+    while let Some(c) = rest.chars().next()
+    {
+        let word_len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if word_len > 0
+        {
+            let word = &rest[..word_len];
+            if BUILTIN_TYPE_WORDS.contains(&word)
+            {
+                run.push(word);
+                space_after_run = false;
+            }
+            else
+            {
+                flush_builtin_type(&mut out, &mut run, space_after_run);
+                out.push_str(word);
+            }
+            rest = &rest[word_len..];
+            continue;
+        }
+
+        if c.is_whitespace() && !run.is_empty()
+        {
+            space_after_run = true;
+        }
+        else
+        {
+            flush_builtin_type(&mut out, &mut run, space_after_run);
+            out.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    flush_builtin_type(&mut out, &mut run, space_after_run);
+    out
+}
+
+/// Writes the normal form of the builtin type spelled by the given keywords to 'out' and clears them.
+fn flush_builtin_type(out: &mut String, run: &mut Vec<&str>, space_after: bool)
+{
+    if run.is_empty() { return; }
+
+    let count = |w: &str| run.iter().filter(|r| **r == w).count();
+    let sign = if count("unsigned") > 0 { "unsigned " } else if count("signed") > 0 { "signed " } else { "" };
+    let normal = if count("char") > 0 { format!("{sign}char") } // 'char', 'signed char' and 'unsigned char' differ
+        else if count("double") > 0 { if count("long") > 0 { "long double".into() } else { "double".into() } }
+        else
+        {
+            let sign = if sign == "signed " { "" } else { sign }; // 'signed int' is 'int'
+            match (count("short"), count("long"))
+            {
+                (s, _) if s > 0 => format!("{sign}short"),
+                (_, 1) => format!("{sign}long"),
+                (_, l) if l > 1 => format!("{sign}long long"),
+                _ => format!("{sign}int"),
+            }
+        };
+
+    out.push_str(&normal);
+    if space_after { out.push(' '); }
+    run.clear();
+}
+
 /// Formats the given func_name with all its scope qualifiers based on the given
 /// source text and starting node.
 pub fn get_qualified_name(node: Node, source: &str, func_name: String) -> String
+{
+    // Friend functions are not members of the befriending class, only of the enclosing namespaces
+    let qualifiers = if is_friend(node) { get_scope_names(node, source, &["namespace_definition"]) }
+                     else { get_qualifiers(node, source) };
+    if qualifiers.is_empty() { func_name }  else {
+        format!("{}::{}", qualifiers.join("::"), func_name)
+    }
+}
+
+/// Returns the number of scopes the function at the given node is nested in.
+/// Counts the enclosing scopes (see get_qualifiers) and the qualifiers in the function's name
+/// (e.g. 'void A::B::f()' at top-level has a depth of 2).
+pub fn get_scope_depth(node: Node, source: &str) -> usize
+{
+    let name_qualifiers = find_declarator(node)
+        .and_then(|d| get_name_and_params(d, source).0)
+        .map(|name| name.matches("::").count())
+        .unwrap_or(0);
+
+    get_qualifiers(node, source).len() + name_qualifiers
+}
+
+/// Returns the names of all class, struct, union and namespace scopes enclosing the given node
+/// (outermost first) based on the given source text.
+pub fn get_qualifiers(node: Node, source: &str) -> Vec<String>
+{
+    get_scope_names(node, source, &["class_specifier", "struct_specifier", "union_specifier", "namespace_definition"])
+}
+
+/// Returns the names of all scopes of the given kinds enclosing the given node
+/// (outermost first) based on the given source text.
+fn get_scope_names(node: Node, source: &str, kinds: &[&str]) -> Vec<String>
 {
     let mut qualifiers = Vec::<String>::new();
     let mut current = node;
 
+    while let Some(parent) = current.parent()
+    {
+        if kinds.contains(&parent.kind())
+            && let Some(id) = parent.child_by_field_name("name")
+            && let Ok(txt) = id.utf8_text(source.as_bytes())
+        {
+            qualifiers.push(txt.to_string());
+        }
+        current = parent;
+    }
+
+    qualifiers.reverse();
+    qualifiers
+}
+
+/// Returns whether the given node is part of a 'friend' declaration.
+pub fn is_friend(node: Node) -> bool
+{
+    let mut current = node;
+    while let Some(parent) = current.parent()
+    {
+        match parent.kind()
+        {
+            "friend_declaration" => return true,
+            "field_declaration_list" | "namespace_definition" | "translation_unit" => return false,
+            _ => current = parent,
+        }
+    }
+    false
+}
+
+/// Returns whether the function at the given node has internal linkage, i.e. is declared 'static'
+/// or inside an anonymous namespace.
+pub fn has_internal_linkage(node: Node, source: &str) -> bool
+{
+    let mut cur = node.walk();
+    let is_static = node.children(&mut cur)
+        .any(|c| c.kind() == "storage_class_specifier" && c.utf8_text(source.as_bytes()) == Ok("static"));
+
+    let mut current = node;
+    let mut in_anonymous_namespace = false;
+    while let Some(parent) = current.parent()
+    {
+        if parent.kind() == "namespace_definition" && parent.child_by_field_name("name").is_none()
+        {
+            in_anonymous_namespace = true;
+        }
+        current = parent;
+    }
+
+    is_static || in_anonymous_namespace
+}
+
+/// Returns whether the function at the given function_definition or function_declarator node has
+/// internal linkage (see has_internal_linkage). Static members of classes are not internal.
+pub fn is_internal_function(node: Node, source: &str) -> bool
+{
+    // The storage class of a declaration belongs to the declaration around its declarators
+    let mut decl = node;
+    while decl.kind().ends_with("declarator") && let Some(parent) = decl.parent()
+    {
+        decl = parent;
+    }
+    !is_in_class_body(decl) && has_internal_linkage(decl, source)
+}
+
+/// Returns whether the given node is (part of) a member of a class, struct or union body.
+pub fn is_in_class_body(node: Node) -> bool
+{
+    let mut current = node;
     while let Some(parent) = current.parent()
     {
         match parent.kind()
         {
-            "class_specifier" | "struct_specifier" | "union_specifier" | "namespace_definition" =>
+            "field_declaration_list" => return true,
+            "declaration_list" | "translation_unit" => return false,
+            _ => current = parent,
+        }
+    }
+    false
+}
+
+/// A method declared or defined inside the body of a class (see find_override_pairs)
+struct ClassMethod
+{
+    class: String, // Qualified name of the enclosing class
+    id: FunctionID, // Unqualified
+    is_override: bool,
+    position: FilePosition
+}
+
+/// Collects all classes (qualified name -> names of their base classes) and all methods declared
+/// inside class bodies in the tree spanned by the given root node.
+fn extract_class_methods(root: Node, source: &str, file: &Path, classes: &mut HashMap<String, Vec<String>>,
+                         methods: &mut Vec<ClassMethod>)
+{
+    visit_all_nodes(root, &mut |node| {
+        match node.kind()
+        {
+            "class_specifier" | "struct_specifier" if node.child_by_field_name("body").is_some() =>
                 {
-                    if let Some(id) = parent.child_by_field_name("name")
-                    {
-                        if let Ok(txt) = id.utf8_text(source.as_bytes())
-                        {
-                            qualifiers.push(txt.to_string());
+                    let Some(name) = node.child_by_field_name("name")
+                        .and_then(|n| n.utf8_text(source.as_bytes()).ok()) else { return; };
+
+                    let mut qualifiers = get_qualifiers(node, source);
+                    qualifiers.push(name.to_string());
+
+                    let mut cur = node.walk();
+                    let bases = node.children(&mut cur)
+                        .filter(|c| c.kind() == "base_class_clause")
+                        .flat_map(|clause| get_base_names(clause, source))
+                        .collect();
+                    classes.insert(qualifiers.join("::"), bases);
+                },
+
+            "function_definition" | "function_declarator"
+                if !has_definition_ancestor(node) && !is_returned_function_pointer(node) =>
+                {
+                    let in_class_body = node.parent()
+                        .map(|p| if p.kind() == "field_declaration" { p.parent() } else { Some(p) })
+                        .is_some_and(|p| p.is_some_and(|p| p.kind() == "field_declaration_list"));
+                    if !in_class_body { return; }
+
+                    let Some(id) = get_function_id(node, source, false) else { return; };
+                    let is_override = find_declarator(node).is_some_and(|d| {
+                        let mut cur = d.walk();
+                        d.children(&mut cur).any(|c| c.kind() == "virtual_specifier")
+                    });
+
+                    methods.push(ClassMethod{
+                        class: get_qualifiers(node, source).join("::"),
+                        id,
+                        is_override,
+                        position: FilePosition{
+                            path: file.to_path_buf(),
+                            row: node.start_position().row,
+                            column: node.start_position().column,
+                            is_definition: node.kind() == "function_definition",
+                            needs_declaration: false
                         }
-                    }
-                }
+                    });
+                },
 
             _ => {}
         }
-        current = parent;
-    }
+    });
+}
 
-    qualifiers.reverse();
-    if qualifiers.is_empty() { func_name }  else {
-        format!("{}::{}", qualifiers.join("::"), func_name)
+/// Returns the names of all base classes listed in the given base_class_clause node
+/// (without template arguments).
+fn get_base_names(clause: Node, source: &str) -> Vec<String>
+{
+    let mut cur = clause.walk();
+    clause.children(&mut cur)
+        .filter(|c| matches!(c.kind(), "type_identifier" | "qualified_identifier" | "template_type"))
+        .filter_map(|c| c.utf8_text(source.as_bytes()).ok())
+        .map(|name| name.split('<').next().unwrap_or(name).trim().to_string())
+        .collect()
+}
+
+/// Returns the qualified name of the class the given base name refers to, if it is known.
+fn resolve_class<'a>(base: &str, classes: &'a HashMap<String, Vec<String>>) -> Option<&'a String>
+{
+    let base = base.trim_start_matches("::");
+    classes.keys()
+        .filter(|c| *c == base || c.ends_with(&format!("::{base}")))
+        .min_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b))) // Prefer the least nested match
+}
+
+/// Number of lines at the start of a file that are searched for generated markers
+pub const GENERATED_HEADER_LINES: usize = 5;
+
+/// Returns whether the given source text is generated, i.e. one of the given markers (e.g. '@generated'
+/// or 'DO NOT EDIT') occurs in its first GENERATED_HEADER_LINES lines.
+pub fn is_generated(src: &str, markers: &[String]) -> bool
+{
+    src.lines().take(GENERATED_HEADER_LINES).any(|l| markers.iter().any(|m| l.contains(m.as_str())))
+}
+
+/// Returns the (row, column) of the start of a block comment that is never closed ('/*' without '*/').
+/// Compilers treat the rest of the file as a comment, while tree-sitter parses it as (broken) code.
+/// Preprocessor lines, line comments, string literals and character literals are skipped.
+pub fn find_unterminated_comment(src: &str) -> Option<(usize, usize)>
+{
+    let masked = mask_preprocessor(src);
+    let bytes = masked.as_bytes();
+    let mut i = 0;
+    while i < bytes.len()
+    {
+        match bytes[i]
+        {
+            b'/' if bytes.get(i + 1) == Some(&b'/') =>
+                {
+                    i = masked[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                },
+            b'/' if bytes.get(i + 1) == Some(&b'*') =>
+                {
+                    match masked[i + 2..].find("*/")
+                    {
+                        Some(n) => i += 2 + n + 2,
+                        None =>
+                            {
+                                let row = masked[..i].matches('\n').count();
+                                let column = i - masked[..i].rfind('\n').map_or(0, |n| n + 1);
+                                return Some((row, column));
+                            }
+                    }
+                },
+            // Digit separators (e.g. 1'000) are no character literals
+            quote @ (b'"' | b'\'') if quote == b'"' || i == 0 || !bytes[i - 1].is_ascii_alphanumeric() =>
+                {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != quote && bytes[i] != b'\n'
+                    {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                    i += 1;
+                },
+            _ => i += 1,
+        }
     }
+    None
 }
 
 /// Masks out all preprocessor sections of the given src by replacing
@@ -195,7 +1502,7 @@ pub fn mask_preprocessor(src: &str) -> String
     let mut in_continuation = false;
 
     // HANDLE EACH LINE SEPARATELY
-    for line in src.split_inclusive(['\n', '\r'])
+    for line in src.split_inclusive('\n')
     {
         // SPLIT BODY FROM END OF LINE
         let (body, eol) = match line.strip_suffix('\n')
@@ -215,7 +1522,7 @@ pub fn mask_preprocessor(src: &str) -> String
         let starts_with_hash = body.trim_start().starts_with('#');
         if in_continuation || starts_with_hash
         {
-            out.extend(iter::repeat(' ').take(body.len()));
+            out.extend(iter::repeat_n(' ', body.len()));
             in_continuation = body.as_bytes().last() == Some(&b'\\');
         }
         else
@@ -230,6 +1537,171 @@ pub fn mask_preprocessor(src: &str) -> String
     out
 }
 
+/// Masks out the lines of all '#if'/'#ifdef'/'#ifndef'/'#elif'/'#else' branches of the given src that are
+/// inactive if only the given defines are defined, replacing them with whitespace that preserves column and row
+/// positioning. Directive lines are kept (see mask_preprocessor).
+/// Conditions are evaluated by evaluate_condition. The remaining branches of an '#if' whose condition
+/// cannot be evaluated are all kept.
+pub fn mask_inactive_branches(src: &str, defines: &[String]) -> String
+{
+    /// State of an '#if' that is currently open
+    struct Branch
+    {
+        parent_active: bool, // Whether the enclosing branch is active
+        known: bool, // Whether all conditions so far could be evaluated
+        taken: bool, // Whether a previous branch was active
+        active: bool // Whether the current branch is active
+    }
+
+    let mut out = String::with_capacity(src.len());
+    let mut stack: Vec<Branch> = Vec::new();
+    for line in src.split_inclusive('\n')
+    {
+        let active = stack.last().is_none_or(|b| b.active);
+        let directive = line.trim_start().strip_prefix('#').map(str::trim_start);
+        let word_len = directive.map_or(0, |d| d.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(d.len()));
+        let (keyword, condition) = directive.map_or(("", ""), |d| (&d[..word_len], d[word_len..].trim()));
+
+        // UPDATE THE BRANCH STACK
+        // '#ifdef'/'#ifndef' test the first identifier (e.g. 'X' of '#ifdef X // note')
+        let name = condition.split("//").next().unwrap_or_default();
+        let name = name.split("/*").next().unwrap_or_default().trim_start();
+        let name = Some(&name[..name.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(name.len())])
+            .filter(|name| !name.is_empty());
+        let evaluate = |condition: &str| match keyword
+        {
+            "ifdef" => name.map(|name| defines.iter().any(|d| d == name)),
+            "ifndef" => name.map(|name| !defines.iter().any(|d| d == name)),
+            _ => evaluate_condition(condition, defines),
+        };
+        match keyword
+        {
+            "if" | "ifdef" | "ifndef" =>
+                {
+                    let result = evaluate(condition);
+                    stack.push(Branch{
+                        parent_active: active,
+                        known: result.is_some(),
+                        taken: result == Some(true),
+                        active: active && result != Some(false)
+                    });
+                },
+            "elif" | "else" =>
+                {
+                    let result = if keyword == "else" { Some(true) } else { evaluate(condition) };
+                    if let Some(branch) = stack.last_mut()
+                    {
+                        if !branch.known || result.is_none()
+                        {
+                            branch.known = false;
+                            branch.active = branch.parent_active;
+                        }
+                        else
+                        {
+                            branch.active = branch.parent_active && !branch.taken && result == Some(true);
+                            branch.taken |= result == Some(true);
+                        }
+                    }
+                },
+            "endif" => { stack.pop(); },
+            _ => {},
+        }
+
+        // MASK LINES OF INACTIVE BRANCHES
+        if active || directive.is_some()
+        {
+            out.push_str(line);
+        }
+        else
+        {
+            let body_len = line.trim_end_matches(['\r', '\n']).len();
+            out.extend(iter::repeat_n(' ', body_len));
+            out.push_str(&line[body_len..]);
+        }
+    }
+    out
+}
+
+/// Evaluates the given '#if' condition if only the given defines are defined.
+/// Supports 'defined(X)', 'defined X', identifiers (true if defined), integer literals, '!', '&&', '||'
+/// and parentheses. Trailing comments are ignored.
+/// Returns None if the condition uses anything else (e.g. comparisons or macro arithmetic).
+pub fn evaluate_condition(condition: &str, defines: &[String]) -> Option<bool>
+{
+    // TOKENIZE
+    let condition = condition.split("//").next().unwrap_or_default();
+    let condition = condition.split("/*").next().unwrap_or_default();
+    let mut tokens: Vec<&str> = Vec::new();
+    let mut rest = condition.trim_start();
+    while !rest.is_empty()
+    {
+        let len = if rest.starts_with("&&") || rest.starts_with("||") { 2 }
+            else if rest.starts_with(['!', '(', ')']) { 1 }
+            else { rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len()) };
+        if len == 0 { return None; }
+        tokens.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+
+    // PARSE (RECURSIVE DESCENT)
+    fn or(tokens: &[&str], i: &mut usize, defines: &[String]) -> Option<bool>
+    {
+        let mut value = and(tokens, i, defines)?;
+        while tokens.get(*i) == Some(&"||")
+        {
+            *i += 1;
+            value |= and(tokens, i, defines)?;
+        }
+        Some(value)
+    }
+    fn and(tokens: &[&str], i: &mut usize, defines: &[String]) -> Option<bool>
+    {
+        let mut value = unary(tokens, i, defines)?;
+        while tokens.get(*i) == Some(&"&&")
+        {
+            *i += 1;
+            value &= unary(tokens, i, defines)?;
+        }
+        Some(value)
+    }
+    fn unary(tokens: &[&str], i: &mut usize, defines: &[String]) -> Option<bool>
+    {
+        let token = *tokens.get(*i)?;
+        *i += 1;
+        match token
+        {
+            "!" => unary(tokens, i, defines).map(|v| !v),
+            "(" =>
+                {
+                    let value = or(tokens, i, defines)?;
+                    if tokens.get(*i) != Some(&")") { return None; }
+                    *i += 1;
+                    Some(value)
+                },
+            "defined" =>
+                {
+                    let parenthesized = tokens.get(*i) == Some(&"(");
+                    if parenthesized { *i += 1; }
+                    let name = *tokens.get(*i)?;
+                    *i += 1;
+                    if parenthesized
+                    {
+                        if tokens.get(*i) != Some(&")") { return None; }
+                        *i += 1;
+                    }
+                    Some(defines.iter().any(|d| d == name))
+                },
+            t if t.starts_with(|c: char| c.is_ascii_digit()) => t.parse::<i64>().ok().map(|n| n != 0),
+            t if t.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') => Some(defines.iter().any(|d| d == t)),
+            _ => None,
+        }
+    }
+
+    let mut i = 0;
+    let value = or(&tokens, &mut i, defines)?;
+    (i == tokens.len()).then_some(value)
+}
+
 /// Performs the given FnMut(Node) on all descendents of the given node recursively
 pub fn visit_all_nodes<F>(node: Node, visit: &mut F)
 where
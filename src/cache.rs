@@ -0,0 +1,181 @@
+//! Handles caching extracted functions between docwen runs
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Context;
+use serde::{Serialize, Deserialize};
+use crate::c_parse::{Extractor, Language};
+use crate::docfig::Settings;
+use crate::docwen_check::{FilePosition, FunctionID};
+
+/// File name of the cache that 'check --cache' writes next to the *docwen.toml*.
+pub const CACHE_FILE_NAME: &str = ".docwen_cache.toml";
+
+/// Cache of the functions extracted per file, keyed by a hash of the file content.
+/// The whole cache is tied to a hash of the settings that influence extraction.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExtractionCache
+{
+    settings_hash: String,
+
+    #[serde(default)]
+    files: HashMap<String, CachedFile>,
+
+    #[serde(skip)]
+    hits: usize,
+
+    #[serde(skip)]
+    misses: usize,
+}
+
+/// The cached extraction of a single file
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFile
+{
+    content_hash: String,
+    functions: Vec<CachedFunction>,
+}
+
+/// A single cached function with its position inside the file
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFunction
+{
+    name: String,
+    params: String,
+    row: usize,
+    column: usize,
+    is_definition: bool,
+    needs_declaration: bool,
+}
+
+impl ExtractionCache
+{
+    /// Creates an empty cache for the given settings.
+    pub fn new(settings: &Settings) -> Self
+    {
+        ExtractionCache{settings_hash: settings_hash(settings), ..Default::default()}
+    }
+
+    /// Loads the cache at the given path.
+    /// Returns an empty cache if the file does not exist, cannot be parsed or
+    /// was written with different settings.
+    pub fn load(path: impl AsRef<Path>, settings: &Settings) -> Self
+    {
+        Self::load_with_language(path, settings, None)
+    }
+
+    /// Loads the cache at the given path like 'load' for a check that parses files with unregistered
+    /// extensions as the given Language (see CheckOptions::assume_language).
+    /// The cache is also discarded if it was written with a different assumed language.
+    pub fn load_with_language(path: impl AsRef<Path>, settings: &Settings, language: Option<Language>) -> Self
+    {
+        let loaded = fs::read_to_string(&path).ok()
+            .and_then(|raw| toml::from_str::<Self>(&raw).ok());
+
+        let hash = match language
+        {
+            Some(language) => format!("{}|{language:?}", settings_hash(settings)),
+            None => settings_hash(settings),
+        };
+        match loaded
+        {
+            Some(cache) if cache.settings_hash == hash => cache,
+            _ => ExtractionCache{settings_hash: hash, ..Default::default()},
+        }
+    }
+
+    /// Serializes the cache to the given file path
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()>
+    {
+        let raw = toml::to_string(self).context("Failed to convert cache to TOML")?;
+        fs::write(&path, raw).with_context(||
+            format!("Failed to write cache to {}", path.as_ref().display()))?;
+        Ok(())
+    }
+
+    /// Returns the functions of the file at the given path with the given source text.
+    /// Reuses the cached extraction if the content is unchanged, otherwise extracts
+    /// the functions with the given extractor and caches the result.
+    pub fn extract(&mut self, path: &Path, source: &str, extractor: &mut dyn Extractor)
+        -> anyhow::Result<Vec<(FunctionID, FilePosition)>>
+    {
+        let key = path.to_string_lossy().to_string();
+        let hash = content_hash(source.as_bytes());
+
+        if let Some(cached) = self.files.get(&key).filter(|c| c.content_hash == hash)
+        {
+            self.hits += 1;
+            return Ok(cached.functions.iter()
+                .map(|f| (
+                    FunctionID{name: f.name.clone(), params: f.params.clone()},
+                    FilePosition{path: path.to_path_buf(), row: f.row, column: f.column,
+                                 is_definition: f.is_definition, needs_declaration: f.needs_declaration}
+                ))
+                .collect());
+        }
+
+        self.misses += 1;
+        let functions = extractor.extract(source, path)?;
+        let cached = CachedFile{
+            content_hash: hash,
+            functions: functions.iter()
+                .map(|(id, pos)| CachedFunction{
+                    name: id.name.clone(),
+                    params: id.params.clone(),
+                    row: pos.row,
+                    column: pos.column,
+                    is_definition: pos.is_definition,
+                    needs_declaration: pos.needs_declaration
+                })
+                .collect()
+        };
+        self.files.insert(key, cached);
+        Ok(functions)
+    }
+
+    /// Returns the number of files whose cached extraction was reused.
+    pub fn hits(&self) -> usize
+    {
+        self.hits
+    }
+
+    /// Returns the number of files that had to be (re)parsed.
+    pub fn misses(&self) -> usize
+    {
+        self.misses
+    }
+}
+
+/// Returns the default cache path for the given *docwen.toml* path.
+pub fn default_cache_path(toml_path: impl AsRef<Path>) -> PathBuf
+{
+    toml_path.as_ref().parent()
+        .map(|p| p.join(CACHE_FILE_NAME))
+        .unwrap_or_else(|| PathBuf::from(CACHE_FILE_NAME))
+}
+
+/// Version of the extraction logic. Bumped whenever extracted FunctionIDs or positions change for the same source.
+const EXTRACTION_VERSION: u32 = 9;
+
+/// Hashes the settings (and extraction version) that influence function extraction.
+fn settings_hash(settings: &Settings) -> String
+{
+    let relevant = format!("{EXTRACTION_VERSION}|{:?}|{:?}|{:?}|{}|{}|{}|{:?}", settings.mode, settings.match_extensions,
+                           settings.max_scope_depth, settings.canonicalize_builtin_types,
+                           settings.ignore_macro_declarations, settings.only_public, settings.platform_defines);
+    content_hash(relevant.as_bytes())
+}
+
+/// Returns the FNV-1a hash of the given bytes as a hex string.
+/// (Stable across runs and compiler versions, unlike std's DefaultHasher)
+fn content_hash(bytes: &[u8]) -> String
+{
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes
+    {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
@@ -1,13 +1,15 @@
 //! Handles parsing *docwen.toml* into a suitable data structure
 
 use std::{fs, path::{Path, PathBuf}};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use anyhow::Context;
+use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
+use crate::toml_manager::{is_glob, normalize_path};
 
 
 /// Represents the entire of *docwen.toml*
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Docfig
 {
@@ -15,10 +17,13 @@ pub struct Docfig
 
     #[serde(rename = "filegroup", default)]
     pub file_groups: Vec<FileGroup>,
+
+    #[serde(rename = "link", default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Link>,
 }
 
 /// Represents the user-defined settings
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Settings
 {
@@ -27,23 +32,502 @@ pub struct Settings
     #[serde(default)]
     pub match_extensions: Vec<String>,
 
-    pub mode: Mode,
+    #[serde(default)]
+    #[schemars(with = "ModesRepr")]
+    pub mode: Modes, // A single mode or several modes that are checked one after another
 
     #[serde(default)]
-    pub manual: Vec<String>
+    pub manual: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>, // Maps stems to the name of the group they should join
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_regex: Option<String>, // Files are grouped by the first capture group of this regex (instead of their stem)
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub follow_symlinks: bool, // Whether 'update' follows symlinks (files reached through several links are grouped once)
+
+    #[serde(default = "default_generated_markers", skip_serializing_if = "is_default_generated_markers")]
+    pub generated_markers: Vec<String>, // Files containing these in their first lines are generated (skipped with '--ignore-generated')
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>, // Files larger than this (in bytes) are skipped by 'check' with a warning (None: unlimited)
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppression_prefixes: Vec<String>, // Doc lines starting with these are excluded from comparison
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict_suppressions: bool, // Whether suppression lines have to be present in all files
+
+    #[serde(default = "default_non_doc_markers", skip_serializing_if = "is_default_non_doc_markers")]
+    pub non_doc_markers: Vec<String>, // Comment lines whose text starts with one of these words (e.g. 'TODO') are no docs
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_scope_depth: Option<usize>, // Functions nested in more scopes are not checked (None: unlimited)
+
+    #[serde(default = "default_doc_prefixes", skip_serializing_if = "is_default_doc_prefixes")]
+    pub doc_prefixes: Vec<String>, // Trimmed lines starting with these are doc lines
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_doc_marker: Option<String>, // Only doc lines starting with this marker are docs, other comments above functions are skipped
+
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub doc_blank_lines: usize, // Number of consecutive blank lines allowed inside a doc block
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub comment_style_agnostic: bool, // Whether doc lines are compared without their comment markers
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub trim_doc_block: bool, // Whether empty doc lines at the start and end of a doc block are ignored
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reflow_insensitive: bool, // Whether '/* */' doc blocks only have to contain the same words (ignoring line breaks)
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignore_tag_order: bool, // Whether the Doxygen tag sections of doc blocks (e.g. '@param') may be in any order
+
+    #[serde(default, skip_serializing_if = "DocStyle::is_exact")]
+    pub doc_style: DocStyle,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tag_value_case_insensitive: Vec<String>, // Doxygen tags (e.g. '@brief') whose values are compared case-insensitively
+
+    #[serde(default = "default_file_level_tags", skip_serializing_if = "is_default_file_level_tags")]
+    pub ignore_file_level_tags: Vec<String>, // Doc lines starting with these Doxygen tags are not compared
+
+    #[serde(default, skip_serializing_if = "DocLocationPolicy::is_either")]
+    pub doc_location_policy: DocLocationPolicy,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_reference: Option<PathBuf>, // .docref file (relative to the docwen.toml) with the expected docs of functions
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pairing_manifest: Option<PathBuf>, // JSON or CSV file (relative to the docwen.toml) defining additional filegroups
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub merge_redeclarations: bool, // Whether repeated declarations of a defined function count as one (only declaration and definition are compared)
+
+    #[serde(default, skip_serializing_if = "FallbackIdentity::is_none")]
+    pub fallback_identity: FallbackIdentity, // How functions without a full-signature match are matched ('arity': by name and number of params)
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub override_inherits_docs: bool, // Whether overriding methods have to match the docs of their base declaration
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub template_specialization_inherits_docs: bool, // Whether explicit specializations have to match the docs of their primary template
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub canonicalize_builtin_types: bool, // Whether equivalent builtin type spellings (e.g. 'unsigned' and 'unsigned int') match
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignore_macro_declarations: bool, // Whether macro invocations like 'DECLARE_API(foo);' are not tracked as functions
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub only_public: bool, // Whether 'static' functions and functions in anonymous namespaces are not tracked
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform_defines: Option<Vec<String>>, // Defines that select the active '#if'/'#elif' branches (None: all branches are kept)
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inherit_include_scope: bool, // Whether files included inside a scope of another file in their group inherit its qualifiers
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_declaration: bool, // Whether functions with external linkage have to be declared in their group
+
+    #[serde(default = "default_msg_no_mismatch", skip_serializing_if = "is_default_msg_no_mismatch")]
+    pub msg_no_mismatch: String, // Printed by 'check' if no mismatches are found (empty: nothing is printed)
+
+    #[serde(default = "default_msg_mismatch_prefix", skip_serializing_if = "is_default_msg_mismatch_prefix")]
+    pub msg_mismatch_prefix: String, // Printed by 'check' in front of each mismatch
+
+    #[serde(default, skip_serializing_if = "DisplayPaths::is_relative")]
+    pub display_paths: DisplayPaths
+}
+
+/// Returns the default doc_prefixes (line and block comments)
+pub fn default_doc_prefixes() -> Vec<String>
+{
+    ["//", "/*", "*"].map(String::from).to_vec()
+}
+
+fn is_default_doc_prefixes(prefixes: &Vec<String>) -> bool
+{
+    *prefixes == default_doc_prefixes()
+}
+
+/// Returns the default generated_markers
+pub fn default_generated_markers() -> Vec<String>
+{
+    ["@generated", "DO NOT EDIT"].map(String::from).to_vec()
+}
+
+fn is_default_generated_markers(markers: &Vec<String>) -> bool
+{
+    *markers == default_generated_markers()
+}
+
+/// Returns the default non_doc_markers (annotations of work in progress)
+pub fn default_non_doc_markers() -> Vec<String>
+{
+    ["TODO", "FIXME", "XXX", "HACK"].map(String::from).to_vec()
+}
+
+fn is_default_non_doc_markers(markers: &Vec<String>) -> bool
+{
+    *markers == default_non_doc_markers()
+}
+
+/// Returns the default ignore_file_level_tags (file header tags that may end up above the first function)
+pub fn default_file_level_tags() -> Vec<String>
+{
+    ["@file", "@author", "@date", "@copyright"].map(String::from).to_vec()
+}
+
+fn is_default_file_level_tags(tags: &Vec<String>) -> bool
+{
+    *tags == default_file_level_tags()
+}
+
+/// Returns the default msg_no_mismatch
+pub fn default_msg_no_mismatch() -> String
+{
+    String::from("Found no mismatches!")
+}
+
+fn is_default_msg_no_mismatch(msg: &String) -> bool
+{
+    *msg == default_msg_no_mismatch()
+}
+
+/// Returns the default msg_mismatch_prefix
+pub fn default_msg_mismatch_prefix() -> String
+{
+    String::from("Mismatch in: ")
+}
+
+fn is_default_msg_mismatch_prefix(msg: &String) -> bool
+{
+    *msg == default_msg_mismatch_prefix()
+}
+
+fn is_zero(n: &usize) -> bool
+{
+    *n == 0
+}
+
+impl Settings
+{
+    /// Overrides the settings with the given 'key=value' pairs (e.g. 'mode=MATCH_MACRO_DOCS' or
+    /// 'trim_doc_block=true'). Values are parsed as TOML values or, if that fails, used as strings.
+    /// Returns an error for malformed pairs, unknown keys and invalid values.
+    pub fn apply_overrides(&mut self, overrides: &[String]) -> anyhow::Result<()>
+    {
+        let schema = serde_json::to_value(schemars::schema_for!(Settings)).unwrap_or_default();
+        let mut table = toml::Table::try_from(&*self).context("Failed to serialize settings")?;
+        for o in overrides
+        {
+            let Some((key, value)) = o.split_once('=') else {
+                anyhow::bail!("Invalid override '{o}' (expected <key>=<value>)");
+            };
+            let key = key.trim();
+            if schema["properties"].get(key).is_none()
+            {
+                anyhow::bail!("Unknown setting '{key}' in override '{o}'");
+            }
+
+            let value = toml::from_str::<toml::Table>(&format!("value = {value}"))
+                .ok()
+                .and_then(|mut t| t.remove("value"))
+                .unwrap_or_else(|| toml::Value::String(value.trim().to_string()));
+            table.insert(key.to_string(), value);
+        }
+
+        *self = table.try_into().with_context(|| format!("Invalid override in {}", overrides.join(", ")))?;
+        Ok(())
+    }
+}
+
+impl Default for Settings
+{
+    /// Settings targeting the current directory with all optional fields at their defaults
+    fn default() -> Self
+    {
+        Settings
+        {
+            target: PathBuf::from("."),
+            match_extensions: Vec::new(),
+            mode: Modes::default(),
+            manual: Vec::new(),
+            aliases: BTreeMap::new(),
+            group_regex: None,
+            follow_symlinks: false,
+            generated_markers: default_generated_markers(),
+            max_file_size: None,
+            suppression_prefixes: Vec::new(),
+            strict_suppressions: false,
+            non_doc_markers: default_non_doc_markers(),
+            max_scope_depth: None,
+            doc_prefixes: default_doc_prefixes(),
+            require_doc_marker: None,
+            doc_blank_lines: 0,
+            comment_style_agnostic: false,
+            trim_doc_block: false,
+            reflow_insensitive: false,
+            ignore_tag_order: false,
+            doc_style: DocStyle::default(),
+            tag_value_case_insensitive: Vec::new(),
+            ignore_file_level_tags: default_file_level_tags(),
+            doc_location_policy: DocLocationPolicy::default(),
+            doc_reference: None,
+            pairing_manifest: None,
+            merge_redeclarations: false,
+            fallback_identity: FallbackIdentity::default(),
+            override_inherits_docs: false,
+            template_specialization_inherits_docs: false,
+            canonicalize_builtin_types: false,
+            ignore_macro_declarations: false,
+            only_public: false,
+            platform_defines: None,
+            inherit_include_scope: false,
+            require_declaration: false,
+            msg_no_mismatch: default_msg_no_mismatch(),
+            msg_mismatch_prefix: default_msg_mismatch_prefix(),
+            display_paths: DisplayPaths::default()
+        }
+    }
 }
 
 /// Operational modes of docwen
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Mode
 {
+    #[default]
     MatchFunctionDocs,
-    MatchFunctionDocsUnqualified
+    MatchFunctionDocsUnqualified,
+    MatchMacroDocs,
+    MatchStructDocs,
+    MatchEnumDocs
+}
+
+impl Mode
+{
+    /// Returns whether the mode compares the docs of functions (qualified or unqualified).
+    pub fn matches_functions(&self) -> bool
+    {
+        matches!(self, Mode::MatchFunctionDocs | Mode::MatchFunctionDocsUnqualified)
+    }
+}
+
+/// The modes a check runs in: a single mode string (e.g. 'mode = "MATCH_FUNCTION_DOCS"') or a non-empty array
+/// of them in the *docwen.toml*. Duplicates are removed, so each mode is only checked once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Modes(Vec<Mode>);
+
+/// How Modes are written in the *docwen.toml*
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+enum ModesRepr
+{
+    One(Mode),
+    Many(Vec<Mode>)
+}
+
+impl Modes
+{
+    /// Returns the modes in the order they are checked.
+    pub fn iter(&self) -> impl Iterator<Item = Mode> + '_
+    {
+        self.0.iter().copied()
+    }
+
+    /// Returns the number of modes.
+    pub fn len(&self) -> usize
+    {
+        self.0.len()
+    }
+
+    /// Returns whether there are no modes (never the case for deserialized Modes).
+    pub fn is_empty(&self) -> bool
+    {
+        self.0.is_empty()
+    }
+
+    /// Returns the first mode, which defines the extraction outside of 'docwen check' (or the default mode if there is none).
+    pub fn primary(&self) -> Mode
+    {
+        self.0.first().copied().unwrap_or_default()
+    }
+}
+
+impl Default for Modes
+{
+    fn default() -> Self
+    {
+        Modes(vec![Mode::default()])
+    }
+}
+
+impl From<Mode> for Modes
+{
+    fn from(mode: Mode) -> Self
+    {
+        Modes(vec![mode])
+    }
+}
+
+/// Modes equal a Mode if they consist of exactly that mode
+impl PartialEq<Mode> for Modes
+{
+    fn eq(&self, other: &Mode) -> bool
+    {
+        self.0 == [*other]
+    }
+}
+
+impl Serialize for Modes
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        match self.0.as_slice()
+        {
+            [mode] => ModesRepr::One(*mode).serialize(serializer),
+            modes => ModesRepr::Many(modes.to_vec()).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Modes
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        deserializer.deserialize_any(ModesVisitor)
+    }
+}
+
+/// Deserializes Modes from a single mode string or an array of them (keeping the error messages of Mode)
+struct ModesVisitor;
+
+impl<'de> serde::de::Visitor<'de> for ModesVisitor
+{
+    type Value = Modes;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        write!(f, "a mode or a non-empty array of modes")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Modes, E>
+    {
+        Mode::deserialize(serde::de::value::StrDeserializer::<E>::new(value)).map(Modes::from)
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Modes, A::Error>
+    {
+        let mut modes: Vec<Mode> = Vec::new();
+        while let Some(mode) = seq.next_element::<Mode>()?
+        {
+            if !modes.contains(&mode) { modes.push(mode); }
+        }
+        if modes.is_empty() { return Err(serde::de::Error::invalid_length(0, &self)); }
+        Ok(Modes(modes))
+    }
+}
+
+/// Where the docs of a function are expected to be
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocLocationPolicy
+{
+    /// Docs may be on declarations and definitions (all of them are compared)
+    #[default]
+    Either,
+
+    /// Docs belong on the declarations only (documented definitions are reported)
+    Declaration,
+
+    /// Docs belong on the definitions only (documented declarations are reported)
+    Definition
+}
+
+impl DocLocationPolicy
+{
+    fn is_either(&self) -> bool
+    {
+        *self == DocLocationPolicy::Either
+    }
+}
+
+/// How functions are matched if their full signature has no match in another file of their group
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FallbackIdentity
+{
+    /// Functions are only matched by their full signature
+    #[default]
+    None,
+
+    /// Functions are matched by their unqualified name and number of params (differing param types are warned about)
+    Arity
+}
+
+impl FallbackIdentity
+{
+    fn is_none(&self) -> bool
+    {
+        *self == FallbackIdentity::None
+    }
+}
+
+/// How the comment styles of doc blocks are compared
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocStyle
+{
+    /// Doc lines are compared as written (see also 'comment_style_agnostic')
+    #[default]
+    Exact,
+
+    /// The comment style ('///', '//' or '/** */') of each doc block is detected and its markers
+    /// and empty opening/closing lines are ignored
+    Auto
+}
+
+impl DocStyle
+{
+    fn is_exact(&self) -> bool
+    {
+        *self == DocStyle::Exact
+    }
+}
+
+/// How 'docwen check' displays the paths of mismatching positions
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayPaths
+{
+    /// Relative to the target (if possible)
+    #[default]
+    Relative,
+
+    /// Absolute paths
+    Absolute,
+
+    /// Relative to the working directory of the docwen process (if possible)
+    FromCwd
+}
+
+impl DisplayPaths
+{
+    fn is_relative(&self) -> bool
+    {
+        *self == DisplayPaths::Relative
+    }
 }
 
 /// A single group of files that will be checked for matching docs
-#[derive(Debug, Serialize, Deserialize, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct FileGroup
 {
@@ -51,21 +535,98 @@ pub struct FileGroup
     pub files: Vec<PathBuf>
 }
 
+/// An explicit link between a header and a source file that are checked as a group
+/// regardless of their names
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Link
+{
+    pub header: PathBuf,
+    pub source: PathBuf
+}
+
+impl Link
+{
+    /// Returns the FileGroup formed by this link.
+    pub fn to_file_group(&self) -> FileGroup
+    {
+        FileGroup
+        {
+            name: format!("{} <-> {}", self.header.display(), self.source.display()),
+            files: vec![self.header.clone(), self.source.clone()]
+        }
+    }
+}
+
 impl Docfig
 {
+    /// Returns the JSON Schema of *docwen.toml* (derived from Docfig and its fields).
+    pub fn json_schema() -> serde_json::Value
+    {
+        serde_json::to_value(schemars::schema_for!(Docfig)).unwrap_or_default()
+    }
+
     /// Reads and parses a *docwen.toml*
     pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self>
     {
-        let raw = fs::read_to_string(&path).with_context(||
-            format!("Failed to read {}", path.as_ref().display()))?;
+        let path = path.as_ref();
+        let raw = fs::read_to_string(path).map_err(|e| {
+            let message = e.to_string();
+            anyhow::Error::new(e).context(ConfigError::new(ConfigErrorKind::Read, path, message))
+        })?;
+
+        let mut docfig: Self = toml::from_str(&raw).map_err(|e| {
+            let mut err = ConfigError::new(ConfigErrorKind::Parse, path, e.message().to_string());
+            if let Some(span) = e.span()
+            {
+                let (line, column) = line_and_column(&raw, span.start);
+                (err.line, err.column) = (Some(line), Some(column));
+            }
+            anyhow::Error::new(e).context(err)
+        })?;
 
-        let mut docfig: Self = toml::from_str(&raw).with_context(||
-            format!("Failed to parse {}", path.as_ref().display()))?;
+        docfig.validate().map_err(|e| ConfigError::new(ConfigErrorKind::Invalid, path, format!("{e:#}")))?;
+        Ok(docfig)
+    }
 
-        docfig.validate()?;
+    /// Like from_file, but applies the given 'key=value' overrides to the settings (see Settings::apply_overrides).
+    pub fn from_file_with_overrides(path: impl AsRef<Path>, overrides: &[String]) -> anyhow::Result<Self>
+    {
+        let mut docfig = Self::from_file(&path)?;
+        if overrides.is_empty() { return Ok(docfig); }
+
+        docfig.settings.apply_overrides(overrides)?;
+        docfig.validate().map_err(|e| ConfigError::new(ConfigErrorKind::Invalid, path.as_ref(), format!("{e:#}")))?;
         Ok(docfig)
     }
 
+    /// Returns all groups that have to be checked: the filegroups followed by the groups
+    /// formed by the links.
+    pub fn all_groups(&self) -> Vec<FileGroup>
+    {
+        self.file_groups.iter().cloned()
+            .chain(self.links.iter().map(Link::to_file_group))
+            .collect()
+    }
+
+    /// Returns the groups of this Docfig (see all_groups) that are not part of the given old Docfig
+    /// or whose (normalized) files differ from the old group of the same name.
+    pub fn changed_groups(&self, old: &Docfig) -> Vec<FileGroup>
+    {
+        let normalized = |g: &FileGroup| g.files.iter().map(normalize_path).collect::<Vec<_>>();
+        let old_groups = old.all_groups();
+        self.all_groups().into_iter()
+            .filter(|g| old_groups.iter().find(|o| *o == g).is_none_or(|o| normalized(o) != normalized(g)))
+            .collect()
+    }
+
+    /// Returns whether the given (target relative) path is part of a link.
+    pub fn is_linked(&self, path: impl AsRef<Path>) -> bool
+    {
+        let path = normalize_path(path);
+        self.links.iter().any(|l| normalize_path(&l.header) == path || normalize_path(&l.source) == path)
+    }
+
     /// Serializes the Docfig to the given file path
     pub fn write_file(&self, path: impl AsRef<Path>) -> anyhow::Result<()>
     {
@@ -87,10 +648,158 @@ impl Docfig
                 return Err(anyhow::anyhow!("Duplicate filegroup name: {}", fg.name));
             }
         }
+
+        // The group regex has to capture the group key
+        if let Some(group_regex) = &self.settings.group_regex
+        {
+            let regex = regex::Regex::new(group_regex)
+                .with_context(|| format!("Invalid group_regex: {group_regex}"))?;
+            if regex.captures_len() < 2
+            {
+                return Err(anyhow::anyhow!("group_regex has no capture group: {group_regex}"));
+            }
+        }
+
+        // Manual entries with glob metacharacters have to be valid globs
+        if let Some(invalid) = self.settings.manual.iter().find(|m| is_glob(m) && glob::Pattern::new(m).is_err())
+        {
+            return Err(anyhow::anyhow!("Invalid glob in manual: {invalid}"));
+        }
+
+        // No file listed twice in a filegroup (would be parsed twice and matched with itself)
+        for fg in &self.file_groups
+        {
+            let mut files = HashSet::new();
+            if let Some(duplicate) = fg.files.iter().find(|f| !files.insert(normalize_path(f)))
+            {
+                return Err(anyhow::anyhow!("Filegroup '{}' lists {} more than once", fg.name, duplicate.display()));
+            }
+        }
         Ok(())
     }
 }
 
+/// Kind of error that occurred while loading a *docwen.toml*
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigErrorKind
+{
+    Read, // The file could not be read
+    Parse, // The file is no valid TOML or does not match the Docfig structure
+    Invalid // The file was parsed but its content is inconsistent (see Docfig::validate)
+}
+
+impl ConfigErrorKind
+{
+    /// Returns the name of the kind as used in JSON output
+    pub fn as_str(&self) -> &'static str
+    {
+        match self
+        {
+            ConfigErrorKind::Read => "read",
+            ConfigErrorKind::Parse => "parse",
+            ConfigErrorKind::Invalid => "invalid",
+        }
+    }
+}
+
+/// Error returned (as context) by Docfig::from_file. Can be retrieved with 'anyhow::Error::downcast_ref'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError
+{
+    pub kind: ConfigErrorKind,
+    pub path: PathBuf,
+    pub message: String, // Message of the underlying error
+    pub line: Option<usize>, // 1-based line of the error in the TOML if known
+    pub column: Option<usize> // 1-based column of the error in the TOML if known
+}
+
+impl ConfigError
+{
+    /// Creates a ConfigError of the given kind for the file at the given path without a location.
+    pub fn new(kind: ConfigErrorKind, path: &Path, message: String) -> Self
+    {
+        ConfigError{kind, path: path.to_path_buf(), message, line: None, column: None}
+    }
+}
+
+impl std::fmt::Display for ConfigError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self.kind
+        {
+            ConfigErrorKind::Read => write!(f, "Failed to read {}", self.path.display()),
+            ConfigErrorKind::Parse => write!(f, "Failed to parse {}", self.path.display()),
+            ConfigErrorKind::Invalid => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Returns the 1-based line and column of the given byte offset in the given text.
+fn line_and_column(text: &str, offset: usize) -> (usize, usize)
+{
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |n| n + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+impl FileGroup
+{
+    /// Returns the absolute, normalized paths of the files of this group (without duplicates).
+    /// Paths that resolve to the same file (e.g. a symlink and its target) are only returned once.
+    /// Relative files are resolved against the given root. Files that do not exist are kept, so that
+    /// the caller decides how to report them (e.g. 'docwen check' warns about them and checks the rest).
+    pub fn resolved_paths(&self, root: &Path) -> anyhow::Result<Vec<PathBuf>>
+    {
+        let root = std::path::absolute(root).with_context(|| format!("Failed to resolve {}", root.display()))?;
+        let mut seen = HashSet::new();
+        Ok(self.files.iter()
+            .map(|f| normalize_path(root.join(f)))
+            .filter(|f| seen.insert(fs::canonicalize(f).unwrap_or_else(|_| f.clone())))
+            .collect())
+    }
+
+    /// Returns the absolute, normalized paths of the existing files of this group (without duplicates).
+    /// Relative files are resolved against the given root and entries with glob metacharacters
+    /// (e.g. 'src/*.h') are expanded to the files they match (in sorted order).
+    /// Aliases of the same file (e.g. 'src/../a.c' and 'a.c' or a symlink and its target) are only returned once.
+    /// Returns an error if a file does not exist or a glob is invalid or matches no file.
+    pub fn resolved_files(&self, root: &Path) -> anyhow::Result<Vec<PathBuf>>
+    {
+        let root = std::path::absolute(root).with_context(|| format!("Failed to resolve {}", root.display()))?;
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        for entry in &self.files
+        {
+            let path = normalize_path(root.join(entry));
+            let matches = match is_glob(&entry.to_string_lossy())
+            {
+                true =>
+                    {
+                        let mut matches: Vec<PathBuf> = glob::glob(&path.to_string_lossy())
+                            .with_context(|| format!("Invalid glob '{}' in group '{}'", entry.display(), self.name))?
+                            .filter_map(Result::ok)
+                            .filter(|f| f.is_file())
+                            .map(normalize_path)
+                            .collect();
+                        if matches.is_empty()
+                        {
+                            anyhow::bail!("Glob '{}' of group '{}' matches no file", entry.display(), self.name);
+                        }
+                        matches.sort();
+                        matches
+                    },
+                false if !path.exists() => anyhow::bail!("File {} of group '{}' does not exist", path.display(), self.name),
+                false => vec![path],
+            };
+            files.extend(matches.into_iter().filter(|f| seen.insert(fs::canonicalize(f).unwrap_or_else(|_| f.clone()))));
+        }
+        Ok(files)
+    }
+}
+
 impl PartialEq for FileGroup
 {
     fn eq(&self, other: &Self) -> bool
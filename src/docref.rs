@@ -0,0 +1,96 @@
+//! Handles *.docref* files, which map functions to their expected docs (see the 'doc_reference' setting)
+//!
+//! Format:
+//! ```text
+//! # Lines starting with '#' are comments
+//! [ns::add(int a, int b)]
+//! /// Adds a and b
+//! /// @return The sum
+//! ```
+//! Each entry starts with the name and params of a function in brackets (as docwen reports them),
+//! followed by its expected doc lines. Empty lines at the end of an entry are ignored.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use anyhow::{bail, Context};
+use crate::c_parse::{normalize_name, normalize_params};
+use crate::docwen_check::FunctionID;
+
+/// The expected docs of functions, parsed from a *.docref* file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DocReference
+{
+    entries: HashMap<FunctionID, Vec<String>>, // Expected (trimmed) doc lines of each function from top to bottom
+}
+
+impl DocReference
+{
+    /// Reads and parses the *.docref* file at the given path.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self>
+    {
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read doc reference {}", path.as_ref().display()))?;
+        Self::parse(&text).with_context(|| format!("Failed to parse doc reference {}", path.as_ref().display()))
+    }
+
+    /// Parses the given *.docref* text.
+    /// Returns an error for doc lines before the first entry, invalid entry headers and duplicate entries.
+    pub fn parse(text: &str) -> anyhow::Result<Self>
+    {
+        let mut entries: HashMap<FunctionID, Vec<String>> = HashMap::new();
+        let mut current: Option<FunctionID> = None;
+        for (i, line) in text.lines().enumerate()
+        {
+            let line = line.trim();
+            if line.starts_with('#') { continue; }
+
+            // ENTRY HEADER
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+            {
+                let Some(params_start) = header.find('(') else {
+                    bail!("Line {}: Expected '[name(params)]', found '{line}'", i + 1);
+                };
+                let id = FunctionID{
+                    name: normalize_name(&header[..params_start]),
+                    params: normalize_params(&header[params_start..])
+                };
+                if entries.insert(id.clone(), Vec::new()).is_some()
+                {
+                    bail!("Line {}: Duplicate entry for {}{}", i + 1, id.name, id.params);
+                }
+                current = Some(id);
+                continue;
+            }
+
+            // DOC LINE OF THE CURRENT ENTRY
+            match &current
+            {
+                Some(id) => entries.entry(id.clone()).or_default().push(line.to_string()),
+                None if line.is_empty() => {},
+                None => bail!("Line {}: Doc line before the first '[name(params)]' entry", i + 1),
+            }
+        }
+
+        entries.values_mut().for_each(|lines| while lines.last().is_some_and(|l| l.is_empty()) { lines.pop(); });
+        Ok(DocReference{entries})
+    }
+
+    /// Returns the expected doc lines of the given function (from top to bottom) if the reference has an entry for it.
+    pub fn get(&self, function: &FunctionID) -> Option<&[String]>
+    {
+        self.entries.get(function).map(Vec::as_slice)
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize
+    {
+        self.entries.len()
+    }
+
+    /// Returns whether the reference has no entries.
+    pub fn is_empty(&self) -> bool
+    {
+        self.entries.is_empty()
+    }
+}
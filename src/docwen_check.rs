@@ -1,24 +1,36 @@
 //! Implements the doc match check functionality of docwen
 
-use std::collections::{HashMap};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 use anyhow::Context;
-use crate::{c_parse, toml_manager};
-use crate::docfig::{Docfig};
-use crate::docfig::Mode::MatchFunctionDocsUnqualified;
+use crate::{c_parse, docfig, toml_manager};
+use crate::cache::ExtractionCache;
+use crate::docref::DocReference;
+use crate::manifest::PairingManifest;
+use crate::c_parse::{CppExtractor, ExtractOptions, ExtractorRegistry, Language};
+use crate::docfig::{DisplayPaths, DocLocationPolicy, DocStyle, Docfig, FallbackIdentity, FileGroup, Mode, Settings};
 
 /// Defines a position (column, row) inside a source file.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FilePosition
 {
     pub path: PathBuf,
     pub row: usize,
-    pub column: usize
+    pub column: usize,
+    pub is_definition: bool, // Whether the function is defined (instead of only declared) here
+    pub needs_declaration: bool // Whether a definition here should also be declared (not internal and not in a class body)
 }
 
 /// Defines an ID for a function through the (optionally: qualified) name and params.
-#[derive(Debug, PartialEq, Eq, Hash)]
+/// - name: The function name, prefixed with all enclosing class/struct/union/namespace scopes and
+///   the qualifiers written in the declarator (e.g. 'ns::A::f'). Unqualified IDs only keep the last part.
+///   Whitespace is only kept between words (e.g. 'operator ==' -> 'operator==', but 'operator new').
+/// - params: The parameter list without comments and with collapsed whitespace (e.g. '(int a, char *b)'),
+///   followed by the cv- and ref-qualifiers of member functions (e.g. '() const &').
+///   Empty for functions without a signature (e.g. assembly labels).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FunctionID
 {
     pub name: String,
@@ -45,74 +57,1562 @@ impl LineSource
             .unwrap_or("")
             .trim()
     }
+
+    /// Returns the doc portion of the src line at the given (negative) offset from init_row.
+    /// This is the trimmed line, except for lines that mix code with a trailing block comment
+    /// (e.g. 'int x; /** doc'), for which only the comment portion is returned.
+    /// Since such a line ends the doc block, all lines above it are returned as "".
+    pub fn doc_line_by_offset(&self, offset: isize) -> &str
+    {
+        let ends_block = (offset + 1..0)
+            .any(|o| trailing_block_comment(self.trimmed_line_by_offset(o)).is_some());
+        if ends_block { return ""; }
+
+        let line = self.trimmed_line_by_offset(offset);
+        trailing_block_comment(line).unwrap_or(line)
+    }
+
+    /// Collects the text of the markdown section whose header is at init_row as a DocBlock.
+    /// The section ends at the next header or fenced code block. Blank lines at its start and end
+    /// are not part of it.
+    pub fn markdown_section(&self) -> DocBlock<'_>
+    {
+        let mut lines: Vec<(isize, &str)> = Vec::new();
+        for (offset, line) in self.src.lines().skip(self.init_row + 1).enumerate()
+        {
+            let line = line.trim();
+            if line.starts_with('#') || line.starts_with("```") { break; }
+            lines.push((offset as isize + 1, line));
+        }
+
+        let start = lines.iter().position(|(_, l)| !l.is_empty()).unwrap_or(lines.len());
+        let end = lines.iter().rposition(|(_, l)| !l.is_empty()).map_or(start, |n| n + 1);
+        let mut lines: Vec<(isize, &str)> = lines[start..end].iter().rev().copied().collect();
+        let doc_len = lines.len();
+        lines.push((0, self.trimmed_line_by_offset(0))); // The header ends the block
+
+        DocBlock{lines, doc_len, suppressions: Vec::new()}
+    }
+
+    /// Collects the doc block directly above init_row according to the given DocRules.
+    /// Only the closest contiguous comment block counts, so comments further up that are separated
+    /// by more than 'max_blank_lines' blank lines (e.g. license headers) are never compared.
+    pub fn doc_block(&self, rules: &DocRules) -> DocBlock<'_>
+    {
+        self.doc_block_from(-1, rules) // Begin at the line directly above the function
+    }
+
+    /// Collects the contiguous doc block that ends (at the bottom) at the given offset from
+    /// init_row according to the given DocRules.
+    /// Doc lines starting with any suppression prefix are not part of the compared lines but are
+    /// collected separately. Up to 'max_blank_lines' consecutive blank lines are skipped if the
+    /// block continues above them.
+    pub fn doc_block_from(&self, start_offset: isize, rules: &DocRules) -> DocBlock<'_>
+    {
+        let mut block = DocBlock{lines: Vec::new(), doc_len: 0, suppressions: Vec::new()};
+        let mut offset = start_offset;
+        loop
+        {
+            let line = self.doc_line_by_offset(offset);
+            if line.is_empty() && block.doc_len > 0 && self.continues_after_blanks(offset, rules)
+            {
+                offset -= 1;
+                continue;
+            }
+
+            if !rules.is_doc_line(line)
+            {
+                block.lines.push((offset, line));
+                break;
+            }
+
+            if rules.is_suppression(line)
+            {
+                block.suppressions.push((offset, line));
+            }
+            else if rules.is_file_level(line)
+            {
+                // File header lines that ended up above the function are no docs of it
+            }
+            else if !rules.has_doc_marker(line)
+            {
+                // Ordinary comments (e.g. '// note' if docs require '///') are no docs either
+            }
+            else if rules.is_non_doc(line)
+            {
+                // Annotations of work in progress (e.g. '// TODO: fix this') are not compared
+            }
+            else
+            {
+                block.lines.push((offset, line));
+                block.doc_len += 1;
+            }
+            offset -= 1;
+        }
+        block
+    }
+
+    /// Returns whether the blank line at the given offset is followed (upwards) by a doc line
+    /// within the number of blank lines allowed by the given DocRules.
+    fn continues_after_blanks(&self, offset: isize, rules: &DocRules) -> bool
+    {
+        (0..rules.max_blank_lines as isize)
+            .map(|i| self.doc_line_by_offset(offset - i - 1))
+            .find(|l| !l.is_empty())
+            .is_some_and(|l| rules.is_doc_line(l))
+    }
+}
+
+/// Rules that define which lines form the doc block above a function.
+#[derive(Debug, Clone)]
+pub struct DocRules
+{
+    pub prefixes: Vec<String>, // Trimmed lines starting with any of these are doc lines
+    pub doc_marker: Option<String>, // If given, only doc lines starting with it are compared, others are skipped
+    pub suppression_prefixes: Vec<String>, // Doc lines starting with these are collected separately
+    pub non_doc_markers: Vec<String>, // Doc lines whose text starts with one of these words (e.g. 'TODO') are skipped
+    pub max_blank_lines: usize, // Number of consecutive blank lines allowed inside a doc block
+    pub comment_style_agnostic: bool, // Whether doc lines are compared without their comment markers
+    pub trim_doc_block: bool, // Whether empty doc lines at the start and end of a block are ignored
+    pub case_insensitive_tags: Vec<String>, // Doxygen tags (without '@' or '\\') whose values ignore case
+    pub file_level_tags: Vec<String> // Doxygen tags (without '@' or '\\') whose lines are skipped
+}
+
+impl Default for DocRules
+{
+    fn default() -> Self
+    {
+        DocRules
+        {
+            prefixes: docfig::default_doc_prefixes(),
+            doc_marker: None,
+            suppression_prefixes: Vec::new(),
+            non_doc_markers: docfig::default_non_doc_markers(),
+            max_blank_lines: 0,
+            comment_style_agnostic: false,
+            trim_doc_block: false,
+            case_insensitive_tags: Vec::new(),
+            file_level_tags: tag_names(&docfig::default_file_level_tags())
+        }
+    }
+}
+
+impl DocRules
+{
+    /// Returns the DocRules defined by the given settings.
+    pub fn from_settings(settings: &Settings) -> Self
+    {
+        DocRules
+        {
+            prefixes: settings.doc_prefixes.clone(),
+            doc_marker: settings.require_doc_marker.clone(),
+            suppression_prefixes: settings.suppression_prefixes.clone(),
+            non_doc_markers: settings.non_doc_markers.clone(),
+            max_blank_lines: settings.doc_blank_lines,
+            comment_style_agnostic: settings.comment_style_agnostic,
+            trim_doc_block: settings.trim_doc_block,
+            case_insensitive_tags: tag_names(&settings.tag_value_case_insensitive),
+            file_level_tags: tag_names(&settings.ignore_file_level_tags)
+        }
+    }
+
+    /// Returns whether the given trimmed line is part of a doc comment.
+    pub fn is_doc_line(&self, line: &str) -> bool
+    {
+        self.prefixes.iter().any(|p| line.starts_with(p.as_str()))
+    }
+
+    /// Returns whether the given trimmed doc line starts with the required 'doc_marker' (if one is given).
+    pub fn has_doc_marker(&self, line: &str) -> bool
+    {
+        self.doc_marker.as_deref().is_none_or(|m| line.starts_with(m))
+    }
+
+    /// Returns the part of the given trimmed line that is compared between files.
+    /// With 'comment_style_agnostic', the comment markers (e.g. '///', '//!', '/**', '/*!<', '*' and a
+    /// closing '*/') are stripped, so that '/*! brief */' matches '/** brief */'.
+    /// The values of the tags in 'case_insensitive_tags' are lowercased (e.g. '@brief Foo' -> '@brief foo').
+    pub fn comparable<'a>(&self, line: &'a str) -> Cow<'a, str>
+    {
+        self.comparable_in_style(line, None)
+    }
+
+    /// Like 'comparable', but only strips the markers of the given comment style if one is given
+    /// (see DocBlock::line_styles).
+    pub fn comparable_in_style<'a>(&self, line: &'a str, style: Option<CommentStyle>) -> Cow<'a, str>
+    {
+        let line = match style
+        {
+            Some(style) => style.strip_markers(line),
+            None if self.comment_style_agnostic => strip_comment_markers(line),
+            None => line,
+        };
+
+        let tag = leading_tag(line);
+        let tag_len = tag.len();
+        let is_case_insensitive = tag.strip_prefix(['@', '\\'])
+            .is_some_and(|name| self.case_insensitive_tags.iter().any(|t| t == name));
+        if !is_case_insensitive { return Cow::Borrowed(line); }
+
+        // Comment markers never contain '@' or '\\', so the first occurrence is the tag
+        let value_start = line.find(tag).unwrap_or(0) + tag_len;
+        Cow::Owned(format!("{}{}", &line[..value_start], line[value_start..].to_lowercase()))
+    }
+
+    /// Returns whether the given trimmed doc line starts with one of the 'file_level_tags' (e.g. '* @author Me').
+    pub fn is_file_level(&self, line: &str) -> bool
+    {
+        leading_tag(line).strip_prefix(['@', '\\'])
+            .is_some_and(|name| self.file_level_tags.iter().any(|t| t == name))
+    }
+
+    /// Returns whether the text of the given trimmed doc line (without comment markers) starts with one of the
+    /// 'non_doc_markers' as a whole word (e.g. '// TODO: fix this' or '/* FIXME */', but not '// TODOs are listed').
+    pub fn is_non_doc(&self, line: &str) -> bool
+    {
+        let text = strip_comment_markers(line).trim_start();
+        self.non_doc_markers.iter().filter(|m| !m.is_empty()).any(|m| text.strip_prefix(m.as_str())
+            .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_')))
+    }
+
+    /// Returns whether the given trimmed doc line is a suppression.
+    pub fn is_suppression(&self, line: &str) -> bool
+    {
+        self.suppression_prefixes.iter().any(|p| line.starts_with(p.as_str()))
+    }
+}
+
+/// Comment style of a doc block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentStyle
+{
+    TripleSlash, // '///' or '//!'
+    DoubleSlash, // '//'
+    Block // '/** */', '/*! */' or '/* */'
+}
+
+impl CommentStyle
+{
+    /// Returns the style of the given trimmed doc line.
+    pub fn of_line(line: &str) -> Self
+    {
+        if line.starts_with("///") || line.starts_with("//!") { CommentStyle::TripleSlash }
+        else if line.starts_with("//") { CommentStyle::DoubleSlash }
+        else { CommentStyle::Block }
+    }
+
+    /// Returns the given trimmed doc line without the comment markers of this style.
+    pub fn strip_markers(self, line: &str) -> &str
+    {
+        let markers: &[&str] = match self
+        {
+            CommentStyle::TripleSlash => &["///<", "//!<", "///", "//!"],
+            CommentStyle::DoubleSlash => &["//"],
+            CommentStyle::Block => &["/**<", "/*!<", "/**", "/*!", "/*", "*"],
+        };
+        let line = if self == CommentStyle::Block { line.strip_suffix("*/").unwrap_or(line) } else { line };
+        markers.iter().find_map(|m| line.strip_prefix(m)).unwrap_or(line).trim()
+    }
+}
+
+/// The doc block above a function inside a LineSource.
+#[derive(Debug)]
+pub struct DocBlock<'a>
+{
+    /// (Offset, line) pairs of the doc lines from the bottom of the block upwards,
+    /// followed by the first line that is not part of the block
+    pub lines: Vec<(isize, &'a str)>,
+
+    /// Number of doc lines in 'lines' (all except the last one)
+    pub doc_len: usize,
+
+    /// (Offset, line) pairs of the doc lines that were excluded from 'lines' as suppressions
+    pub suppressions: Vec<(isize, &'a str)>
+}
+
+impl DocBlock<'_>
+{
+    /// Returns the n-th line of the block (counting upwards) or "" if it does not exist.
+    pub fn line(&self, n: usize) -> &str
+    {
+        self.lines.get(n).map(|(_, l)| *l).unwrap_or("")
+    }
+
+    /// Returns whether the n-th line of the block (counting upwards) is a doc line.
+    pub fn is_doc(&self, n: usize) -> bool
+    {
+        n < self.doc_len
+    }
+
+    /// Returns the offset of the n-th line of the block (counting upwards) from the function row,
+    /// even if that line is not part of the block.
+    pub fn offset(&self, n: usize) -> isize
+    {
+        match self.lines.get(n)
+        {
+            Some((offset, _)) => *offset,
+            None =>
+                {
+                    let last = self.lines.last().map(|(o, _)| *o).unwrap_or(0);
+                    last - (n + 1 - self.lines.len()) as isize
+                }
+        }
+    }
+
+    /// Returns the block without the doc lines at its top and bottom that are empty apart from their
+    /// comment markers (e.g. a bare '/**' or '*/'). Empty lines inside the block are kept.
+    pub fn trimmed(mut self) -> Self
+    {
+        let is_empty = |l: &str| strip_comment_markers(l).is_empty();
+        let bottom = self.lines[..self.doc_len].iter().take_while(|(_, l)| is_empty(l)).count();
+        let top = self.lines[bottom..self.doc_len].iter().rev().take_while(|(_, l)| is_empty(l)).count();
+
+        self.lines.drain(..bottom);
+        self.doc_len -= bottom + top;
+        self
+    }
+
+    /// Returns the block with its Doxygen tag sections (a line starting with a tag like '@param' and the untagged
+    /// lines below it) sorted by their comparable text (see DocRules::comparable), so that blocks whose sections
+    /// only differ in their order have the same lines. The untagged lines above the first tag and the empty lines
+    /// at the bottom (e.g. a closing '*/') keep their place. Each line keeps its offset.
+    pub fn with_sorted_tag_sections(mut self, rules: &DocRules) -> Self
+    {
+        let is_empty = |l: &str| strip_comment_markers(l).is_empty();
+        let is_tag = |l: &str| leading_tag(l).len() > 1 && leading_tag(l).starts_with(['@', '\\']);
+
+        let mut top_down: Vec<(isize, &str)> = self.lines[..self.doc_len].iter().rev().copied().collect();
+        let bottom_len = top_down.iter().rev().take_while(|(_, l)| is_empty(l)).count();
+        let bottom = top_down.split_off(top_down.len() - bottom_len);
+        let first_tag = top_down.iter().position(|(_, l)| is_tag(l)).unwrap_or(top_down.len());
+
+        let mut sections: Vec<Vec<(isize, &str)>> = Vec::new();
+        for line in top_down.split_off(first_tag)
+        {
+            match sections.last_mut()
+            {
+                Some(section) if !is_tag(line.1) => section.push(line),
+                _ => sections.push(vec![line]),
+            }
+        }
+        sections.sort_by_cached_key(|s| s.iter().map(|(_, l)| rules.comparable(l).into_owned()).collect::<Vec<_>>());
+
+        let rest = self.lines.split_off(self.doc_len);
+        self.lines = top_down.into_iter().chain(sections.into_iter().flatten()).chain(bottom).rev().chain(rest).collect();
+        self
+    }
+
+    /// Returns the (first, last) row of the doc lines of the block in the file,
+    /// given the row of the function. Returns None if the block has no doc lines.
+    pub fn span(&self, row: usize) -> Option<(usize, usize)>
+    {
+        // The lines of blocks with sorted tag sections are not ordered by their offsets
+        let offsets = self.lines[..self.doc_len].iter().map(|(o, _)| *o);
+        Some((offset_row(row, offsets.clone().min()?), offset_row(row, offsets.max()?)))
+    }
+
+    /// Returns the comment style of each doc line of the block (counting upwards like 'line').
+    /// Blocks may mix styles (e.g. '// summary' above '/* note */'), so the style is detected per line.
+    /// Lines inside an open '/* */' comment (e.g. ' * detail') belong to it.
+    pub fn line_styles(&self) -> Vec<CommentStyle>
+    {
+        let mut styles = Vec::with_capacity(self.doc_len);
+        let mut in_block_comment = false;
+        for n in (0..self.doc_len).rev()
+        {
+            let line = self.line(n);
+            let style = if in_block_comment { CommentStyle::Block } else { CommentStyle::of_line(line) };
+            if style == CommentStyle::Block
+            {
+                // A closing '*/' after the opening '/*' (if this line opens the comment) ends it
+                let rest = if in_block_comment { line } else { line.get(2..).unwrap_or("") };
+                in_block_comment = !rest.contains("*/");
+            }
+            styles.push(style);
+        }
+        styles.reverse();
+        styles
+    }
+
+    /// Returns the comparable text (see DocRules::comparable_in_style) of all doc lines of the block
+    /// without '/* */' markers, joined from top to bottom with all whitespace collapsed to single spaces.
+    pub fn reflowed(&self, rules: &DocRules) -> String
+    {
+        let text: Vec<Cow<str>> = (0..self.doc_len).rev()
+            .map(|n| rules.comparable_in_style(self.line(n), Some(CommentStyle::Block)))
+            .collect();
+        text.iter().flat_map(|l| l.split_whitespace()).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Returns the offset of the given suppression line if the block contains it.
+    pub fn suppression_offset(&self, suppression: &str) -> Option<isize>
+    {
+        self.suppressions.iter().find(|(_, s)| *s == suppression).map(|(o, _)| *o)
+    }
+}
+
+/// Returns the block comment portion of the given trimmed line if the line starts with code
+/// and ends with a block comment (e.g. 'int x; /** doc */' or 'int x; /** doc').
+/// Member docs ('/**<' or '/*!<') document the code on their own line and are never returned.
+pub fn trailing_block_comment(line: &str) -> Option<&str>
+{
+    if is_doc_line(line) { return None; }
+
+    let comment = &line[comment_start(line)?..];
+    if !comment.starts_with("/*") || comment.starts_with("/**<") || comment.starts_with("/*!<") { return None; }
+    match comment[2..].find("*/")
+    {
+        Some(end) if !comment[2 + end + 2..].trim().is_empty() => None, // Code after the comment
+        _ => Some(comment),
+    }
+}
+
+/// Returns the comment after the code of the given line (e.g. '///< Red' of '    Red, ///< Red') if there is one.
+pub fn trailing_comment(line: &str) -> Option<&str>
+{
+    let line = line.trim();
+    if is_doc_line(line) { return None; }
+
+    Some(&line[comment_start(line)?..])
+}
+
+/// Returns the byte index of the first comment ('//' or '/*') of the given line that is not part of a
+/// string or character literal.
+fn comment_start(line: &str) -> Option<usize>
+{
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len()
+    {
+        match bytes[i]
+        {
+            b'/' if matches!(bytes.get(i + 1), Some(b'/' | b'*')) => return Some(i),
+            // Digit separators (e.g. 1'000) are no character literals
+            quote @ (b'"' | b'\'') if quote == b'"' || i == 0 || !bytes[i - 1].is_ascii_alphanumeric() =>
+                {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != quote
+                    {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                    i += 1;
+                },
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Normalization options of the doc comparison (see compare_docs)
+#[derive(Debug, Clone, Default)]
+pub struct CompareOptions
+{
+    pub rules: DocRules, // Define the doc lines and how each of them is normalized
+    pub auto_style: bool, // Whether only the markers of the detected comment style of each line are ignored
+    pub reflow_insensitive: bool, // Whether '/* */' blocks only have to contain the same words
+    pub ignore_tag_order: bool // Whether the tag sections of the blocks are compared in any order
+}
+
+impl CompareOptions
+{
+    /// Returns the CompareOptions defined by the given settings.
+    pub fn from_settings(settings: &Settings) -> Self
+    {
+        let auto_style = settings.doc_style == DocStyle::Auto;
+        let mut rules = DocRules::from_settings(settings);
+        if auto_style { rules.trim_doc_block = true; }
+        CompareOptions{rules, auto_style, reflow_insensitive: settings.reflow_insensitive, ignore_tag_order: settings.ignore_tag_order}
+    }
+}
+
+/// The first difference between two doc blocks found by compare_docs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocDiff
+{
+    pub kind: MismatchKind, // Differs if both blocks have a doc line at 'index', otherwise Missing
+    pub index: usize, // Index of the differing doc line, counting upwards from the bottom of the blocks
+    pub a: Option<String>, // Doc line of the first block at 'index' (None if it has none)
+    pub b: Option<String> // Doc line of the second block at 'index' (None if it has none)
+}
+
+/// Compares the given doc blocks (the lines above a function, e.g. "/// Adds a\n/// and b") like
+/// 'docwen check' compares the docs of a function, using the given normalization options.
+/// Only the contiguous doc block at the bottom of each string is compared.
+/// Returns the first difference or None if the docs match.
+pub fn compare_docs(a: &str, b: &str, options: &CompareOptions) -> Option<DocDiff>
+{
+    let sources = [a, b].map(|docs| LineSource{src: docs.to_string(), init_row: docs.lines().count()});
+    let blocks: Vec<DocBlock> = sources.iter()
+        .map(|s| s.doc_block(&options.rules))
+        .map(|b| if options.rules.trim_doc_block { b.trimmed() } else { b })
+        .map(|b| if options.ignore_tag_order { b.with_sorted_tag_sections(&options.rules) } else { b })
+        .collect();
+    let styles: Vec<Vec<CommentStyle>> = blocks.iter()
+        .map(|b| if options.auto_style { b.line_styles() } else { Vec::new() })
+        .collect();
+
+    let (index, kind) = first_difference(&blocks, &styles, options)?;
+    let doc_line = |b: &DocBlock| b.is_doc(index).then(|| b.line(index).to_string());
+    Some(DocDiff{kind, index, a: doc_line(&blocks[0]), b: doc_line(&blocks[1])})
+}
+
+/// Options that modify the behavior of 'docwen check'.
+#[derive(Debug, Default)]
+pub struct CheckOptions
+{
+    /// Path of the extraction cache to read and update (None: no caching)
+    pub cache: Option<PathBuf>,
+
+    /// (Path, content) of a file whose content is used instead of the file on disk
+    /// (e.g. an unsaved editor buffer). If given, only the groups containing it are checked.
+    pub stdin_file: Option<(PathBuf, String)>,
+
+    /// Language that files without a registered extractor for their extension (e.g. '.ipp' or no
+    /// extension) are parsed as (None: C++). Has no effect in MATCH_MACRO_DOCS mode.
+    pub assume_language: Option<Language>,
+
+    /// Files (relative to the current directory) that are checked as a single anonymous group
+    /// instead of the groups of the docwen.toml (None: check the configured groups)
+    pub files: Option<Vec<PathBuf>>,
+
+    /// Path of a previous version of the docwen.toml. If given, only the groups that are new or whose
+    /// files changed compared to it are checked (see Docfig::changed_groups)
+    pub changed_config: Option<PathBuf>,
+
+    /// Whether generated files (see c_parse::is_generated and the 'generated_markers' setting) are skipped
+    pub ignore_generated: bool,
+
+    /// 'key=value' overrides of the settings of the docwen.toml (see Settings::apply_overrides)
+    pub overrides: Vec<String>,
+}
+
+/// Category of a documentation mismatch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MismatchKind
+{
+    /// All files have a doc line at the mismatching offset, but they differ
+    Differs,
+
+    /// At least one file has no doc line at the mismatching offset
+    Missing,
+
+    /// A suppression line is not present in all files (only with 'strict_suppressions')
+    Suppression,
+
+    /// Docs were found in a location that the 'doc_location_policy' forbids
+    Location,
+
+    /// A function is defined but never declared in its group (only with 'require_declaration')
+    Undeclared,
+
+    /// The docs of a function differ from its entry in the 'doc_reference'
+    Reference
+}
+
+impl MismatchKind
+{
+    /// All kinds in the order they are documented
+    pub const ALL: [MismatchKind; 6] = [MismatchKind::Differs, MismatchKind::Missing, MismatchKind::Suppression,
+                                        MismatchKind::Location, MismatchKind::Undeclared, MismatchKind::Reference];
+
+    /// Returns the name of the kind as used in outputs.
+    pub fn as_str(&self) -> &'static str
+    {
+        match self
+        {
+            MismatchKind::Differs => "differs",
+            MismatchKind::Missing => "missing",
+            MismatchKind::Suppression => "suppression",
+            MismatchKind::Location => "location",
+            MismatchKind::Undeclared => "undeclared",
+            MismatchKind::Reference => "reference",
+        }
+    }
+}
+
+impl std::str::FromStr for MismatchKind
+{
+    type Err = anyhow::Error;
+
+    /// Parses the name of a kind as returned by 'as_str'.
+    fn from_str(s: &str) -> anyhow::Result<Self>
+    {
+        MismatchKind::ALL.into_iter()
+            .find(|k| k.as_str() == s)
+            .with_context(|| format!("Unknown mismatch kind '{s}' (expected one of: {})",
+                                     MismatchKind::ALL.map(|k| k.as_str()).join(", ")))
+    }
+}
+
+/// A single documentation mismatch between all positions of one function.
+#[derive(Debug, Clone)]
+pub struct Mismatch
+{
+    pub function: FunctionID,
+    pub kind: MismatchKind,
+    pub line: String, // The (trimmed) doc line of the first position that has one at the mismatching offset
+    pub positions: Vec<FilePosition>,
+    pub doc_rows: Vec<usize>, // Row of the mismatching doc line for each position
+    pub doc_spans: Vec<Option<(usize, usize)>> // (First, last) row of the compared doc block for each position
+}
+
+/// A problem found by a 'docwen check' run that is not a documentation mismatch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning
+{
+    /// A file of the group does not exist. The group is checked without it.
+    MissingFile { group: String, path: PathBuf },
+
+    /// A block comment of the file is never closed, so the functions after it are unreliable.
+    UnterminatedComment { path: PathBuf, row: usize, column: usize },
+
+    /// A file is larger than the 'max_file_size' setting (in bytes), so it is not parsed.
+    FileTooLarge { path: PathBuf, size: u64, limit: u64 },
+
+    /// The params of a function differ in type across files, so it is only matched by name and
+    /// number of params (see the 'fallback_identity' setting).
+    TypeDrift { function: String, signatures: Vec<String> }
+}
+
+impl std::fmt::Display for Warning
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Warning::MissingFile { group, path } =>
+                write!(f, "File {} of group '{}' does not exist", path.display(), group),
+            Warning::UnterminatedComment { path, row, column } =>
+                write!(f, "Block comment at {}:{}:{} is never closed", path.display(), row, column),
+            Warning::FileTooLarge { path, size, limit } =>
+                write!(f, "File {} is skipped, its size ({} bytes) exceeds max_file_size ({} bytes)", path.display(), size, limit),
+            Warning::TypeDrift { function, signatures } =>
+                write!(f, "Params of '{}' differ across files ({}), so it is matched by its number of params",
+                       function, signatures.join(" vs ")),
+        }
+    }
+}
+
+/// The result of a 'docwen check' run.
+#[derive(Debug)]
+pub struct CheckReport
+{
+    pub target: PathBuf, // Absolute path of the checked target
+    pub display_base: PathBuf, // Path the positions are displayed relative to (see CheckIter::display_base)
+    pub mismatches: Vec<Mismatch>,
+    pub warnings: Vec<Warning>,
+    pub groups: Vec<GroupSummary> // All checked groups, including the ones without mismatches
+}
+
+/// A file that does not parse cleanly (see CheckIter::parse_failures)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFailure
+{
+    pub path: PathBuf,
+    pub row: usize, // Position of the first syntax error
+    pub column: usize
+}
+
+/// The result of checking a single group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupSummary
+{
+    pub name: String,
+    pub files: usize, // Number of files that were read
+    pub functions: usize, // Number of distinct functions found in the files (matched or not)
+    pub shared: usize, // Number of functions found at more than one position (i.e. compared)
+    pub mismatches: usize
+}
+
+impl CheckReport
+{
+    /// Removes all mismatches that are not of one of the given kinds. Keeps all of them if 'kinds' is empty.
+    pub fn retain_kinds(&mut self, kinds: &[MismatchKind])
+    {
+        if kinds.is_empty() { return; }
+        self.mismatches.retain(|m| kinds.contains(&m.kind));
+    }
+}
+
+impl GroupSummary
+{
+    /// Returns "clean" if the group has no mismatches, otherwise "mismatch".
+    pub fn status(&self) -> &'static str
+    {
+        if self.mismatches == 0 { "clean" } else { "mismatch" }
+    }
 }
 
-/// Performs 'docwen check'.
+/// Performs 'docwen check' with the default CheckOptions.
 /// Returns a Result containing a Vec of all documentation mismatches that were found.
 pub fn check(toml_path: impl AsRef<Path>) -> anyhow::Result<Vec<String>>
 {
-    let mut mismatches: Vec<String> = Vec::new();
+    let report = check_with_options(toml_path, &CheckOptions::default())?;
+    Ok(report.mismatches.iter()
+        .map(|m| format_mismatch(&m.line, &m.positions, &report.display_base))
+        .collect())
+}
+
+/// Performs 'docwen check' with the given CheckOptions.
+/// Returns a Result containing a CheckReport with all documentation mismatches that were found.
+pub fn check_with_options(toml_path: impl AsRef<Path>, options: &CheckOptions)
+    -> anyhow::Result<CheckReport>
+{
+    let mut iter = check_iter(toml_path, options)?;
+    let target = iter.target().to_path_buf();
+    let display_base = iter.display_base()?;
+    let mismatches = iter.by_ref().collect::<anyhow::Result<Vec<_>>>()?;
+    let groups = std::mem::take(&mut iter.group_summaries);
+    Ok(CheckReport{target, display_base, mismatches, warnings: iter.take_warnings(), groups})
+}
 
+/// Prepares 'docwen check' with the given CheckOptions without checking any group yet.
+/// Returns a Result containing a CheckIter that lazily yields the mismatches group by group.
+pub fn check_iter(toml_path: impl AsRef<Path>, options: &CheckOptions) -> anyhow::Result<CheckIter<'_>>
+{
     // GET DOCFIG FROM TOML
-    let docfig = Docfig::from_file(&toml_path)?;
-    let abs_target_path = toml_manager::get_absolute_root(&toml_path, &docfig.settings.target)?;
+    let mut docfig = Docfig::from_file_with_overrides(&toml_path, &options.overrides)?;
+
+    // The cache holds the functions of a single mode
+    let cache = options.cache.as_ref()
+        .filter(|_| docfig.settings.mode.len() == 1)
+        .map(|p| ExtractionCache::load_with_language(p, &docfig.settings, options.assume_language));
+    let root = absolute_normalized(&toml_manager::existing_root(&toml_path, &docfig.settings.target)?)?;
 
-    // GET ALL FUNCTION POSITIONS THAT NEED TO BE CHECKED
-    let use_qualifiers = docfig.settings.mode != MatchFunctionDocsUnqualified;
-    let root = toml_manager::get_absolute_root(&toml_path, &docfig.settings.target)?;
-    let mut position_maps: Vec<HashMap<FunctionID, Vec<FilePosition>>> = Vec::new();
-    for file_group in docfig.file_groups
+    // One registry per mode, since the mode defines what is extracted
+    let modes = docfig.settings.mode.clone();
+    let mut registries = Vec::with_capacity(modes.len());
+    for mode in modes.iter()
     {
-        let abs_files = file_group.files.iter().map(|f| root.join(f)).collect::<Vec<_>>();
-        position_maps.push(c_parse::find_function_positions(abs_files, use_qualifiers)?);
+        docfig.settings.mode = mode.into();
+        let mut registry = ExtractorRegistry::from_settings(&docfig.settings)?;
+        if let Some(language) = options.assume_language && mode != Mode::MatchMacroDocs
+        {
+            let extract_options = ExtractOptions::from_settings(&docfig.settings);
+            registry.set_fallback(Box::new(CppExtractor::with_language(extract_options, language)?));
+        }
+        registries.push(registry);
     }
+    docfig.settings.mode = modes;
+    let stdin_path = options.stdin_file.as_ref().map(|(p, _)| absolute_normalized(p)).transpose()?;
+    let reference = docfig.settings.doc_reference.as_ref()
+        .map(|r| DocReference::from_file(toml_manager::get_absolute_root(&toml_path, r)?))
+        .transpose()?;
 
-    // CHECK FOR MATCHING DOCS
-    for map in position_maps
+    let groups = match &options.files
     {
-        for (_, vec) in map
+        Some(files) => vec![FileGroup{
+            name: "--files".to_string(),
+            files: files.iter().map(|f| absolute_normalized(f)).collect::<anyhow::Result<_>>()?
+        }],
+        None => match &options.changed_config
         {
-            // Get all sources
-            let sources: Vec<LineSource> = vec.iter()
-                .map(|f| fs::read_to_string(&f.path).map(|src| LineSource{src, init_row: f.row}))
-                .collect::<Result<_, _>>()?;
+            Some(old) => docfig.changed_groups(&Docfig::from_file(old)?),
+            None =>
+                {
+                    let manifest = docfig.settings.pairing_manifest.as_ref()
+                        .map(|m| PairingManifest::from_file(toml_manager::get_absolute_root(&toml_path, m)?))
+                        .transpose()?;
+                    docfig.all_groups().into_iter().chain(manifest.iter().flat_map(PairingManifest::file_groups)).collect()
+                },
+        },
+    };
 
-            // Get lines at the current offset
-            let mut offset = -1; // Begin at the line directly above the function
-            let mut cur_lines: Vec<&str> = sources.iter()
-                .map(|s| s.trimmed_line_by_offset(offset))
-                .collect::<Vec<_>>();
+    Ok(CheckIter{
+        options,
+        groups: groups.into_iter(),
+        settings: docfig.settings,
+        root,
+        registries,
+        cache,
+        stdin_path,
+        reference,
+        pending: VecDeque::new(),
+        warnings: Vec::new(),
+        group_summaries: Vec::new(),
+        done: false
+    })
+}
+
+/// Iterator over the mismatches of a 'docwen check' run (see check_iter).
+/// Each group is only read, parsed and checked once all mismatches of the previous groups
+/// have been consumed. After an error, no further items are yielded.
+/// The extraction cache (if any) is saved once all groups have been checked.
+pub struct CheckIter<'a>
+{
+    options: &'a CheckOptions,
+    groups: std::vec::IntoIter<FileGroup>,
+    settings: Settings,
+    root: PathBuf, // Absolute path of the checked target
+    registries: Vec<ExtractorRegistry>, // Extractors of each mode
+    cache: Option<ExtractionCache>,
+    stdin_path: Option<PathBuf>,
+    reference: Option<DocReference>, // Expected docs of functions (from the 'doc_reference' setting)
+    pending: VecDeque<Mismatch>, // Mismatches of the current group that have not been yielded yet
+    warnings: Vec<Warning>, // Warnings of all groups checked so far
+    group_summaries: Vec<GroupSummary>, // Summaries of all groups checked so far
+    done: bool
+}
+
+impl CheckIter<'_>
+{
+    /// Returns the absolute path of the checked target.
+    pub fn target(&self) -> &Path
+    {
+        &self.root
+    }
+
+    /// Returns the path the positions are displayed relative to according to the display_paths setting.
+    /// An empty path means that absolute paths are displayed.
+    pub fn display_base(&self) -> anyhow::Result<PathBuf>
+    {
+        match self.settings.display_paths
+        {
+            DisplayPaths::Relative => Ok(self.root.clone()),
+            DisplayPaths::Absolute => Ok(PathBuf::new()),
+            DisplayPaths::FromCwd => std::env::current_dir().context("Failed to get current directory"),
+        }
+    }
+
+    /// Returns the warnings of all groups that have been checked so far.
+    pub fn warnings(&self) -> &[Warning]
+    {
+        &self.warnings
+    }
 
-            // Check each comment line individually
-            while cur_lines.iter()
-                .any(|s| s.starts_with("//") || s.starts_with("/*") || s.starts_with("*"))
+    /// Takes the warnings of all groups that have been checked so far out of the iterator.
+    pub fn take_warnings(&mut self) -> Vec<Warning>
+    {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Parses every file of the groups that have not been checked yet once without comparing any docs
+    /// (with the extractors of the first mode) and returns the files with syntax errors in the order they were parsed.
+    /// Missing files are skipped with a warning, so are generated files if 'ignore_generated' is set.
+    pub fn parse_failures(&mut self) -> anyhow::Result<Vec<ParseFailure>>
+    {
+        let mut failures = Vec::new();
+        let mut seen = HashSet::new();
+        for group in std::mem::take(&mut self.groups)
+        {
+            for f in group.resolved_paths(&self.root)?
             {
-                let match_str = cur_lines.first().with_context(||"Failed to get 'match_str'")?;
-                if cur_lines.iter().any(|f| f != match_str)
+                if !seen.insert(f.clone()) { continue; }
+
+                let src = match (&self.stdin_path, &self.options.stdin_file)
                 {
-                    mismatches.push(format_mismatch(match_str, &vec, &abs_target_path));
-                    break;
+                    (Some(stdin_path), Some((_, content))) if *stdin_path == f => content.clone(),
+                    _ => match fs::read_to_string(&f)
+                    {
+                        Ok(src) => src,
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound =>
+                            {
+                                self.warnings.push(Warning::MissingFile{group: group.name.clone(), path: f});
+                                continue;
+                            },
+                        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", f.display())),
+                    },
+                };
+                if self.options.ignore_generated && c_parse::is_generated(&src, &self.settings.generated_markers)
+                {
+                    continue;
                 }
-                offset -= 1;
-                cur_lines = sources.iter()
-                    .map(|s| s.trimmed_line_by_offset(offset))
-                    .collect::<Vec<_>>();
+
+                if let Some((row, column)) = self.registries[0].extractor_for(&f).first_parse_error(&src)?
+                {
+                    failures.push(ParseFailure{path: f, row, column});
+                }
+            }
+        }
+        self.done = true;
+        Ok(failures)
+    }
+
+    /// Returns the summaries of all groups that have been checked so far.
+    pub fn group_summaries(&self) -> &[GroupSummary]
+    {
+        &self.group_summaries
+    }
+
+    /// Checks the given group in each mode and queues all its mismatches.
+    /// Mismatches of the same line at the same positions found by several modes are only queued once.
+    fn check_group(&mut self, file_group: &FileGroup) -> anyhow::Result<()>
+    {
+        let modes = self.settings.mode.clone();
+        let mut summary: Option<GroupSummary> = None;
+        for (index, mode) in modes.iter().enumerate()
+        {
+            let warnings = self.warnings.len();
+            self.settings.mode = mode.into();
+            let result = self.check_group_in_mode(file_group, index);
+            self.settings.mode = modes.clone();
+
+            let Some(mode_summary) = result? else { return Ok(()); };
+            if index > 0
+            {
+                // Files are read again in each mode
+                let new = self.warnings.split_off(warnings);
+                for w in new
+                {
+                    if !self.warnings.contains(&w) { self.warnings.push(w); }
+                }
+            }
+            summary = Some(match summary
+            {
+                Some(s) => GroupSummary{
+                    files: s.files.max(mode_summary.files),
+                    functions: s.functions + mode_summary.functions,
+                    shared: s.shared + mode_summary.shared,
+                    ..s
+                },
+                None => mode_summary,
+            });
+        }
+
+        let mut queued: Vec<Mismatch> = Vec::with_capacity(self.pending.len());
+        for m in std::mem::take(&mut self.pending)
+        {
+            // Only the same mismatch of the same entity found by several modes is dropped
+            // (an unqualified mode names 'ns::add' just 'add')
+            let same_function = |a: &FunctionID, b: &FunctionID| a.params == b.params
+                && (a.name == b.name || a.name.ends_with(&format!("::{}", b.name)) || b.name.ends_with(&format!("::{}", a.name)));
+            let duplicate = queued.iter().any(|q| same_function(&q.function, &m.function) && q.kind == m.kind && q.line == m.line
+                && q.positions.len() == m.positions.len()
+                && q.positions.iter().zip(&m.positions)
+                    .all(|(a, b)| a.path == b.path && a.row == b.row && a.column == b.column));
+            if !duplicate { queued.push(m); }
+        }
+        self.pending = queued.into();
+
+        if let Some(summary) = summary
+        {
+            self.group_summaries.push(GroupSummary{mismatches: self.pending.len(), ..summary});
+        }
+        Ok(())
+    }
+
+    /// Checks the given group in the (single) mode of the settings with the extractors of the mode at the given
+    /// index and queues all its mismatches.
+    /// Returns the summary of the group (without its mismatch count) or None if the group is skipped.
+    fn check_group_in_mode(&mut self, file_group: &FileGroup, mode_index: usize) -> anyhow::Result<Option<GroupSummary>>
+    {
+        let mode = self.settings.mode.primary();
+
+        // Missing files are only warnings (and the stdin file does not have to exist on disk)
+        let abs_files = file_group.resolved_paths(&self.root)?;
+
+        // Only check the groups containing the stdin file if one is given
+        if let Some(stdin_path) = &self.stdin_path && !abs_files.contains(stdin_path)
+        {
+            return Ok(None);
+        }
+
+        let mut sources: HashMap<PathBuf, String> = HashMap::new();
+        for f in &abs_files
+        {
+            if sources.contains_key(f) { continue; }
+
+            // Oversized (e.g. generated) files are not parsed
+            let size = match (&self.stdin_path, &self.options.stdin_file)
+            {
+                (Some(stdin_path), Some((_, content))) if stdin_path == f => Some(content.len() as u64),
+                _ => fs::metadata(f).ok().map(|m| m.len()),
+            };
+            if let (Some(limit), Some(size)) = (self.settings.max_file_size, size) && size > limit
+            {
+                self.warnings.push(Warning::FileTooLarge{path: f.clone(), size, limit});
+                continue;
+            }
+
+            let src = match (&self.stdin_path, &self.options.stdin_file)
+            {
+                (Some(stdin_path), Some((_, content))) if stdin_path == f => content.clone(),
+                _ => match fs::read_to_string(f)
+                {
+                    Ok(src) => src,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound =>
+                        {
+                            self.warnings.push(Warning::MissingFile{group: file_group.name.clone(), path: f.clone()});
+                            continue;
+                        },
+                    Err(e) => return Err(e).with_context(|| format!("Failed to read {}", f.display())),
+                },
+            };
+            if self.options.ignore_generated && c_parse::is_generated(&src, &self.settings.generated_markers)
+            {
+                continue;
+            }
+            if !c_parse::is_markdown(f) && let Some((row, column)) = c_parse::find_unterminated_comment(&src)
+            {
+                self.warnings.push(Warning::UnterminatedComment{path: f.clone(), row, column});
+            }
+
+            // Positions and docs are both taken from the doc source, so they always refer to the same lines
+            let src = self.registries[mode_index].extractor_for(f).doc_source(&src).into_owned();
+            sources.insert(f.clone(), src);
+        }
+
+        // GET ALL FUNCTION POSITIONS THAT NEED TO BE CHECKED
+        let group_sources = abs_files.iter()
+            .filter_map(|f| sources.get(f).map(|src| (f.as_path(), src.as_str())));
+        let mut map = c_parse::collect_function_positions(group_sources, &mut self.registries[mode_index], self.cache.as_mut())?;
+
+        // Definitions whose exact ID is never declared (markdown sections are no declarations)
+        let mut undeclared: Vec<(FunctionID, FilePosition)> = match self.settings.require_declaration && mode.matches_functions()
+        {
+            true => map.iter()
+                .filter(|(_, vec)| !vec.iter().any(|p| !p.is_definition && !c_parse::is_markdown(&p.path)))
+                .flat_map(|(id, vec)| vec.iter().filter(|p| p.needs_declaration).map(|p| (id.clone(), p.clone())))
+                .collect(),
+            false => Vec::new(),
+        };
+        undeclared.sort_by_key(|(_, p)| (abs_files.iter().position(|f| *f == p.path), p.row, p.column));
+        if self.settings.inherit_include_scope && mode == Mode::MatchFunctionDocs
+        {
+            let group_sources = abs_files.iter()
+                .filter(|f| !c_parse::is_markdown(f))
+                .filter_map(|f| sources.get(f).map(|src| (f.as_path(), src.as_str())));
+            let scopes = c_parse::find_include_scopes(group_sources)?;
+            if !scopes.is_empty()
+            {
+                map = inherit_include_scopes(map, &scopes);
+            }
+        }
+        if self.settings.fallback_identity == FallbackIdentity::Arity && mode.matches_functions()
+        {
+            let (matched, drift) = match_by_arity(map, &abs_files);
+            map = matched;
+            self.warnings.extend(drift);
+        }
+        let function_count = map.len();
+
+        // Enumerators are named after their enum, which is extracted as well
+        let enumerators: HashSet<FunctionID> = match mode
+        {
+            Mode::MatchEnumDocs => map.keys()
+                .filter(|id| id.name.rsplit_once("::")
+                    .is_some_and(|(e, _)| map.contains_key(&FunctionID{name: e.to_string(), params: String::new()})))
+                .cloned()
+                .collect(),
+            _ => HashSet::new(),
+        };
+        // Trailing comments document their own enumerator and never the one below it
+        let leading_sources = match enumerators.is_empty()
+        {
+            true => Cow::Borrowed(&sources),
+            false => Cow::Owned(without_trailing_comments(&sources, enumerators.iter().flat_map(|id| &map[id]))),
+        };
+        map.retain(|_, vec| vec.len() > 1);
+        let shared_count = map.len();
+
+        // CHECK FOR MATCHING DOCS
+        for (function, vec) in map
+        {
+            let expected = self.reference.as_ref().and_then(|r| r.get(&function));
+            let reference_args = expected.map(|e| (function.clone(), vec.clone(), e));
+            let enumerator = enumerators.contains(&function).then(|| (function.clone(), vec.clone()));
+            let mut mismatches = check_function(function, vec, &leading_sources, &self.settings)?;
+
+            // Enumerators are also documented after their code and are named in their mismatches
+            if let Some((function, vec)) = enumerator
+            {
+                mismatches.extend(check_trailing_comment(function, vec, &sources, &self.settings));
+                for m in &mut mismatches
+                {
+                    let name = m.function.name.rsplit("::").next().unwrap_or_default();
+                    m.line = format!("{name}: {}", m.line);
+                }
+            }
+
+            // Docs that agree across all files are compared against the reference
+            let agreed = !mismatches.iter().any(|m| matches!(m.kind, MismatchKind::Differs | MismatchKind::Missing));
+            self.pending.extend(mismatches);
+            if let Some((function, vec, expected)) = reference_args && agreed
+            {
+                self.pending.extend(check_reference(function, vec, &leading_sources, &self.settings, expected));
+            }
+        }
+
+        // CHECK OVERRIDES AGAINST THEIR BASE DECLARATIONS
+        // (Unqualified mode already matches them by name and params)
+        if self.settings.override_inherits_docs && mode == Mode::MatchFunctionDocs
+        {
+            let group_sources = abs_files.iter()
+                .filter(|f| !c_parse::is_markdown(f))
+                .filter_map(|f| sources.get(f).map(|src| (f.as_path(), src.as_str())));
+            for (function, vec) in c_parse::find_override_pairs(group_sources)?
+            {
+                self.pending.extend(check_function(function, vec, &sources, &self.settings)?);
+            }
+        }
+
+        // CHECK EXPLICIT TEMPLATE SPECIALIZATIONS AGAINST THEIR PRIMARY TEMPLATE
+        if self.settings.template_specialization_inherits_docs && mode.matches_functions()
+        {
+            let group_sources = abs_files.iter()
+                .filter(|f| !c_parse::is_markdown(f))
+                .filter_map(|f| sources.get(f).map(|src| (f.as_path(), src.as_str())));
+            for (function, vec) in c_parse::find_specialization_pairs(group_sources)?
+            {
+                self.pending.extend(check_function(function, vec, &sources, &self.settings)?);
+            }
+        }
+
+        // CHECK FOR DEFINITIONS WITHOUT DECLARATION
+        if !undeclared.is_empty()
+        {
+            let rules = DocRules::from_settings(&self.settings);
+            for (function, pos) in undeclared
+            {
+                let line_source = LineSource{src: sources[&pos.path].clone(), init_row: pos.row};
+                let block = line_source.doc_block(&rules);
+                self.pending.push_back(Mismatch{
+                    function,
+                    kind: MismatchKind::Undeclared,
+                    line: if block.is_doc(0) { block.line(0).to_string() } else { String::new() },
+                    doc_rows: vec![offset_row(pos.row, block.offset(0))],
+                    doc_spans: vec![block.span(pos.row)],
+                    positions: vec![pos]
+                });
             }
         }
+
+        Ok(Some(GroupSummary{
+            name: file_group.name.clone(),
+            files: sources.len(),
+            functions: function_count,
+            shared: shared_count,
+            mismatches: 0
+        }))
+    }
+
+    /// Saves the extraction cache if one is used.
+    fn save_cache(&self) -> anyhow::Result<()>
+    {
+        if let (Some(cache), Some(path)) = (&self.cache, &self.options.cache)
+        {
+            cache.save(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for CheckIter<'_>
+{
+    type Item = anyhow::Result<Mismatch>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        loop
+        {
+            if let Some(m) = self.pending.pop_front() { return Some(Ok(m)); }
+            if self.done { return None; }
+
+            let result = match self.groups.next()
+            {
+                Some(group) => self.check_group(&group),
+                None => { self.done = true; self.save_cache() },
+            };
+
+            if let Err(e) = result
+            {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Keeps only one declaration (the first documented one, or else the first one) of the given positions and
+/// their doc blocks if the function is also defined, so that repeated declarations (e.g. in a forward declaring
+/// header) are one canonical doc source and only declaration and definition are compared.
+fn merged_redeclarations(vec: Vec<FilePosition>, blocks: Vec<DocBlock>) -> (Vec<FilePosition>, Vec<DocBlock>)
+{
+    let declarations = vec.iter().filter(|p| !p.is_definition).count();
+    if declarations < 2 || declarations == vec.len() { return (vec, blocks); }
+
+    let canonical = (0..vec.len()).filter(|i| !vec[*i].is_definition)
+        .find(|i| blocks[*i].is_doc(0))
+        .or_else(|| vec.iter().position(|p| !p.is_definition));
+    vec.into_iter().zip(blocks).enumerate()
+        .filter(|(i, (p, _))| p.is_definition || Some(*i) == canonical)
+        .map(|(_, pair)| pair)
+        .unzip()
+}
+
+/// Checks the docs of the given function at the given positions for mismatches.
+/// 'sources' maps the paths of all positions to the extractor's doc source (see Extractor::doc_source)
+/// that the positions were extracted from, so both refer to the same rows and columns. In MATCH_ENUM_DOCS
+/// mode, the trailing comments of enumerators are removed from it (see without_trailing_comments).
+pub fn check_function(function: FunctionID, vec: Vec<FilePosition>, sources: &HashMap<PathBuf, String>,
+                      settings: &Settings) -> anyhow::Result<Vec<Mismatch>>
+{
+    let mut mismatches = Vec::new();
+
+    // Get all sources
+    let line_sources: Vec<LineSource> = vec.iter()
+        .map(|f| sources.get(&f.path)
+            .map(|src| LineSource{src: src.clone(), init_row: f.row})
+            .with_context(|| format!("Missing source of {}", f.path.display())))
+        .collect::<anyhow::Result<_>>()?;
+
+    // Markdown sections have no comment markers and no empty lines at their ends
+    let mut options = CompareOptions::from_settings(settings);
+    if vec.iter().any(|p| c_parse::is_markdown(&p.path))
+    {
+        options.rules.comment_style_agnostic = true;
+        options.rules.trim_doc_block = true;
+    }
+    let rules = &options.rules;
+
+    let blocks: Vec<DocBlock> = line_sources.iter().zip(&vec)
+        .map(|(s, p)| if c_parse::is_markdown(&p.path) { s.markdown_section() } else { s.doc_block(rules) })
+        .map(|b| if rules.trim_doc_block { b.trimmed() } else { b })
+        .map(|b| if options.ignore_tag_order { b.with_sorted_tag_sections(rules) } else { b })
+        .collect();
+
+    // Report documented positions in forbidden locations and only compare the allowed ones
+    let forbidden = |p: &FilePosition| !c_parse::is_markdown(&p.path) && match settings.doc_location_policy
+    {
+        DocLocationPolicy::Either => false,
+        DocLocationPolicy::Declaration => p.is_definition,
+        DocLocationPolicy::Definition => !p.is_definition,
+    };
+
+    let misplaced: Vec<usize> = (0..vec.len()).filter(|i| forbidden(&vec[*i]) && blocks[*i].is_doc(0)).collect();
+    if let Some(first) = misplaced.first()
+    {
+        mismatches.push(Mismatch{
+            function: function.clone(),
+            kind: MismatchKind::Location,
+            line: blocks[*first].line(0).to_string(),
+            positions: misplaced.iter().map(|i| vec[*i].clone()).collect(),
+            doc_rows: misplaced.iter().map(|i| offset_row(vec[*i].row, blocks[*i].offset(0))).collect(),
+            doc_spans: misplaced.iter().map(|i| blocks[*i].span(vec[*i].row)).collect()
+        });
+    }
+
+    let (vec, blocks): (Vec<FilePosition>, Vec<DocBlock>) = vec.into_iter().zip(blocks)
+        .filter(|(p, _)| !forbidden(p))
+        .unzip();
+    let (vec, blocks) = if settings.merge_redeclarations { merged_redeclarations(vec, blocks) } else { (vec, blocks) };
+    if vec.len() < 2 { return Ok(mismatches); }
+
+    // With the auto doc style, only the markers of the style of each line are ignored
+    let styles: Vec<Vec<CommentStyle>> = vec.iter().zip(&blocks)
+        .map(|(p, b)| if options.auto_style && !c_parse::is_markdown(&p.path) { b.line_styles() } else { Vec::new() })
+        .collect();
+
+    if let Some((n, kind)) = first_difference(&blocks, &styles, &options)
+    {
+        // Report an actual doc line, not the line above the docs of a shorter block
+        let doc_line = blocks.iter()
+            .find(|b| b.is_doc(n))
+            .map_or(blocks[0].line(n), |b| b.line(n));
+
+        mismatches.push(Mismatch{
+            function: function.clone(),
+            kind,
+            line: doc_line.to_string(),
+            doc_rows: vec.iter().zip(&blocks).map(|(p, b)| offset_row(p.row, b.offset(n))).collect(),
+            doc_spans: vec.iter().zip(&blocks).map(|(p, b)| b.span(p.row)).collect(),
+            positions: vec.clone()
+        });
+    }
+
+    // Suppressions are only compared in strict mode
+    if settings.strict_suppressions
+        && let Some(suppression) = inconsistent_suppression(&blocks)
+    {
+        // Files without the suppression point at the line directly above the function
+        let doc_rows = vec.iter().zip(&blocks)
+            .map(|(p, b)| offset_row(p.row, b.suppression_offset(suppression).unwrap_or(-1)))
+            .collect();
+
+        mismatches.push(Mismatch{
+            function,
+            kind: MismatchKind::Suppression,
+            line: suppression.to_string(),
+            doc_rows,
+            doc_spans: vec.iter().zip(&blocks).map(|(p, b)| b.span(p.row)).collect(),
+            positions: vec
+        });
     }
 
     Ok(mismatches)
 }
 
+/// Compares the comments after the code on the lines of the given positions (e.g. 'Red, ///< The color red')
+/// using the doc comparison of the given settings.
+/// Returns a mismatch of kind Differs (or Missing if some lines have no such comment) if they differ.
+pub fn check_trailing_comment(function: FunctionID, vec: Vec<FilePosition>, sources: &HashMap<PathBuf, String>,
+                              settings: &Settings) -> Option<Mismatch>
+{
+    let rules = DocRules::from_settings(settings);
+    let comments: Vec<Option<&str>> = vec.iter()
+        .map(|p| sources.get(&p.path).and_then(|src| src.lines().nth(p.row)).and_then(trailing_comment))
+        .collect();
+    let comparable: Vec<Option<Cow<str>>> = comments.iter().map(|c| c.map(|c| rules.comparable(c))).collect();
+    if comparable.iter().all(|c| *c == comparable[0]) { return None; }
+
+    Some(Mismatch{
+        function,
+        kind: if comments.contains(&None) { MismatchKind::Missing } else { MismatchKind::Differs },
+        line: comments.iter().flatten().next().copied().unwrap_or_default().to_string(),
+        doc_rows: vec.iter().map(|p| p.row).collect(),
+        doc_spans: vec.iter().zip(&comments).map(|(p, c)| c.map(|_| (p.row, p.row))).collect(),
+        positions: vec
+    })
+}
+
+/// Returns the given sources without the comments after the code on the lines of the given positions,
+/// so that they are not taken as docs of the code below them (e.g. 'Red, /* Red */' above 'Green').
+fn without_trailing_comments<'a>(sources: &HashMap<PathBuf, String>, positions: impl IntoIterator<Item = &'a FilePosition>)
+    -> HashMap<PathBuf, String>
+{
+    let mut rows: HashMap<&Path, HashSet<usize>> = HashMap::new();
+    for p in positions
+    {
+        rows.entry(p.path.as_path()).or_default().insert(p.row);
+    }
+
+    sources.iter()
+        .map(|(path, src)| match rows.get(path.as_path())
+        {
+            None => (path.clone(), src.clone()),
+            Some(rows) => (path.clone(), src.split('\n').enumerate()
+                .map(|(row, line)| match trailing_comment(line).filter(|_| rows.contains(&row))
+                {
+                    Some(comment) => line.find(comment).map_or(line, |start| &line[..start]),
+                    None => line,
+                })
+                .collect::<Vec<_>>()
+                .join("\n")),
+        })
+        .collect()
+}
+
+/// Checks the docs of the given function at the given positions against the given expected doc lines
+/// (from top to bottom, see DocReference) using the doc comparison of the given settings.
+/// The docs of the first position are compared, so all positions should agree on them.
+/// Returns a mismatch of kind Reference (with the file's doc line, or the expected one if the file has none)
+/// if they differ. Markdown positions are not compared.
+pub fn check_reference(function: FunctionID, vec: Vec<FilePosition>, sources: &HashMap<PathBuf, String>,
+                       settings: &Settings, expected: &[String]) -> Option<Mismatch>
+{
+    let vec: Vec<FilePosition> = vec.into_iter().filter(|p| !c_parse::is_markdown(&p.path)).collect();
+    let first = vec.first()?;
+    let options = CompareOptions::from_settings(settings);
+
+    // Only the doc block directly above the function is compared
+    let above = sources.get(&first.path)?.lines().take(first.row).collect::<Vec<_>>().join("\n");
+    let diff = compare_docs(&above, &expected.join("\n"), &options)?;
+
+    let line_sources: Vec<LineSource> = vec.iter()
+        .map(|p| LineSource{src: sources.get(&p.path).cloned().unwrap_or_default(), init_row: p.row})
+        .collect();
+    let blocks: Vec<DocBlock> = line_sources.iter()
+        .map(|s| s.doc_block(&options.rules))
+        .map(|b| if options.rules.trim_doc_block { b.trimmed() } else { b })
+        .collect();
+
+    Some(Mismatch{
+        function,
+        kind: MismatchKind::Reference,
+        line: diff.a.or(diff.b).unwrap_or_default(),
+        doc_rows: vec.iter().zip(&blocks).map(|(p, b)| offset_row(p.row, b.offset(diff.index))).collect(),
+        doc_spans: vec.iter().zip(&blocks).map(|(p, b)| b.span(p.row)).collect(),
+        positions: vec
+    })
+}
+
+/// Returns the index (counting upwards like DocBlock::line) and kind of the first doc line that differs
+/// between the given doc blocks, or None if they all match according to the given CompareOptions.
+/// 'styles' holds the comment style of each doc line of each block whose markers are ignored
+/// (empty for blocks that are compared as they are, see DocBlock::line_styles).
+fn first_difference(blocks: &[DocBlock], styles: &[Vec<CommentStyle>], options: &CompareOptions)
+    -> Option<(usize, MismatchKind)>
+{
+    let rules = &options.rules;
+
+    // Reflowed block comments only have to contain the same words
+    let is_block_comment = |b: &DocBlock| b.doc_len > 0 && b.line_styles().iter().all(|s| *s == CommentStyle::Block);
+    let reflowed_match = options.reflow_insensitive
+        && blocks.iter().all(is_block_comment)
+        && blocks.windows(2).all(|w| w[0].reflowed(rules) == w[1].reflowed(rules));
+    if reflowed_match { return None; }
+
+    // Check each comment line individually
+    let mut n = 0;
+    while blocks.iter().any(|b| b.is_doc(n))
+    {
+        let cur_lines: Vec<(bool, Cow<str>)> = blocks.iter().zip(styles)
+            .map(|(b, styles)| (b.is_doc(n), rules.comparable_in_style(b.line(n), styles.get(n).copied())))
+            .collect();
+        let match_str = cur_lines.first()?;
+        if cur_lines.iter().any(|f| f != match_str)
+        {
+            let kind = if blocks.iter().all(|b| b.is_doc(n))
+                { MismatchKind::Differs } else { MismatchKind::Missing };
+            return Some((n, kind));
+        }
+        n += 1;
+    }
+    None
+}
+
+/// Prefixes the names of the functions found in the given included files with the scopes they are included in
+/// (see c_parse::find_include_scopes) and regroups all positions by the resulting FunctionIDs.
+fn inherit_include_scopes(map: HashMap<FunctionID, Vec<FilePosition>>, scopes: &HashMap<PathBuf, Vec<String>>)
+    -> HashMap<FunctionID, Vec<FilePosition>>
+{
+    let mut qualified: HashMap<FunctionID, Vec<FilePosition>> = HashMap::new();
+    for (id, positions) in map
+    {
+        for pos in positions
+        {
+            let id = match scopes.get(&pos.path)
+            {
+                Some(scope) => FunctionID{name: format!("{}::{}", scope.join("::"), id.name), params: id.params.clone()},
+                None => id.clone(),
+            };
+            qualified.entry(id).or_default().push(pos);
+        }
+    }
+    qualified
+}
+
+/// Merges the functions that only occur once and whose unqualified name has no match in another file
+/// into one function if they have the same unqualified name and number of params and are in different files
+/// (functions of the same name and arity in one file are ambiguous and stay unmatched).
+/// The merged function keeps the FunctionID of the position in the first of the given files.
+/// Returns the merged map and a TypeDrift warning for each merged function.
+fn match_by_arity(mut map: HashMap<FunctionID, Vec<FilePosition>>, files: &[PathBuf])
+    -> (HashMap<FunctionID, Vec<FilePosition>>, Vec<Warning>)
+{
+    let unqualified = |id: &FunctionID| id.name.rsplit("::").next().unwrap_or(&id.name).to_string();
+    let matched: HashSet<String> = map.iter()
+        .filter(|(_, vec)| vec.len() > 1)
+        .map(|(id, _)| unqualified(id))
+        .collect();
+
+    // GROUP THE UNMATCHED FUNCTIONS BY UNQUALIFIED NAME AND ARITY
+    let mut candidates: HashMap<(String, usize), Vec<FunctionID>> = HashMap::new();
+    for (id, vec) in &map
+    {
+        let name = unqualified(id);
+        if vec.len() == 1 && !id.params.is_empty() && !matched.contains(&name)
+        {
+            candidates.entry((name, c_parse::param_count(&id.params))).or_default().push(id.clone());
+        }
+    }
+
+    // MERGE CANDIDATES FROM DIFFERENT FILES
+    let file_index = |id: &FunctionID, map: &HashMap<FunctionID, Vec<FilePosition>>| {
+        files.iter().position(|f| *f == map[id][0].path).unwrap_or(usize::MAX)
+    };
+    let mut warnings = Vec::new();
+    for (_, mut ids) in candidates
+    {
+        if ids.len() < 2 { continue; }
+        ids.sort_by_key(|id| (file_index(id, &map), id.params.clone()));
+        let mut paths: Vec<&PathBuf> = ids.iter().map(|id| &map[id][0].path).collect();
+        paths.dedup();
+        if paths.len() != ids.len() { continue; }
+
+        let positions: Vec<FilePosition> = ids.iter().flat_map(|id| map.remove(id).unwrap_or_default()).collect();
+        warnings.push(Warning::TypeDrift{
+            function: ids[0].name.clone(),
+            signatures: ids.iter().map(|id| id.params.clone()).collect()
+        });
+        map.insert(ids.swap_remove(0), positions);
+    }
+    warnings.sort_by_key(|w| w.to_string());
+    (map, warnings)
+}
+
+/// Returns the given path as an absolute (relative to the cwd), lexically normalized path.
+fn absolute_normalized(path: &Path) -> anyhow::Result<PathBuf>
+{
+    let abs = if path.is_absolute() { path.to_path_buf() } else {
+        std::env::current_dir().context("Failed to get current directory")?.join(path)
+    };
+    Ok(toml_manager::normalize_path(abs))
+}
+
+/// Returns the given Doxygen tags (e.g. '@brief' or '\\brief') without their '@' or '\\'.
+fn tag_names(tags: &[String]) -> Vec<String>
+{
+    tags.iter().map(|t| t.trim_start_matches(['@', '\\']).to_string()).collect()
+}
+
+/// Returns the first word of the comment content of the given trimmed doc line (e.g. '@brief').
+fn leading_tag(line: &str) -> &str
+{
+    let content = strip_comment_markers(line);
+    &content[..content.find(char::is_whitespace).unwrap_or(content.len())]
+}
+
+/// Returns the row at the given offset from the given row (0 if it would be before the first row).
+fn offset_row(row: usize, offset: isize) -> usize
+{
+    usize::try_from(row as isize + offset).unwrap_or(0)
+}
+
+/// Returns a suppression line that is not present in all the given doc blocks, if there is one.
+fn inconsistent_suppression<'a>(blocks: &[DocBlock<'a>]) -> Option<&'a str>
+{
+    blocks.iter()
+        .flat_map(|b| b.suppressions.iter().map(|(_, s)| *s))
+        .find(|s| !blocks.iter().all(|b| b.suppression_offset(s).is_some()))
+}
+
+/// Comment markers stripped by strip_comment_markers (longest first)
+const COMMENT_MARKERS: [&str; 11] = ["///<", "//!<", "/**<", "/*!<", "///", "//!", "/**", "/*!", "//", "/*", "*"];
+
+/// Returns the given trimmed comment line without its opening (e.g. '///', '//!', '/**', '/*!<' or a
+/// leading '*') and closing ('*/') comment markers.
+pub fn strip_comment_markers(line: &str) -> &str
+{
+    let line = line.strip_suffix("*/").unwrap_or(line).trim_end();
+    COMMENT_MARKERS.iter()
+        .find_map(|m| line.strip_prefix(m))
+        .unwrap_or(line)
+        .trim()
+}
+
+/// Returns whether the given trimmed line is part of a doc comment.
+pub fn is_doc_line(line: &str) -> bool
+{
+    line.starts_with("//") || line.starts_with("/*") || line.starts_with("*")
+}
+
+/// Formats the given mismatch like format_mismatch, but additionally includes the row of the
+/// mismatching doc line for each position.
+/// Uses the given (absolute!) target_path to display the file positions as relative paths if possible.
+pub fn format_mismatch_with_doc_rows(mismatch: &Mismatch, abs_target_path: impl AsRef<Path>) -> String
+{
+    let abs_target_path = toml_manager::normalize_path(abs_target_path);
+    let group_str = mismatch.positions.iter().zip(&mismatch.doc_rows)
+        .map(|(p, doc_row)| (toml_manager::normalize_path(&p.path), p, doc_row))
+        .map(|(path, p, doc_row)| format!("{:?}:{}:{} (doc row {})",
+                                          path.strip_prefix(&abs_target_path).unwrap_or(&path),
+                                          p.row, p.column, doc_row))
+        .collect::<Vec<_>>().join(", ");
+    format!("\"{}\"\n-> [{}]", mismatch.line, group_str)
+}
+
 /// Formats the given vec of file positions with a mismatch at 'match_str'.
 /// Uses the given (absolute!) target_path to display the file positions as relative paths if possible.
-pub fn format_mismatch(match_str: &str, vec: &Vec<FilePosition>, abs_target_path: impl AsRef<Path>)
+pub fn format_mismatch(match_str: &str, vec: &[FilePosition], abs_target_path: impl AsRef<Path>)
     -> String
 {
+    let abs_target_path = toml_manager::normalize_path(abs_target_path);
     let group_str = vec.iter()
-        .map(|p| format!("{:?}:{}:{}",
-                         p.path.strip_prefix(&abs_target_path).unwrap_or(&p.path),
-                         p.row, p.column))
+        .map(|p| (toml_manager::normalize_path(&p.path), p))
+        .map(|(path, p)| format!("{:?}:{}:{}",
+                                 path.strip_prefix(&abs_target_path).unwrap_or(&path),
+                                 p.row, p.column))
         .collect::<Vec<_>>().join(", ");
     format!("\"{}\"\n-> [{}]", match_str, group_str)
 }
\ No newline at end of file
@@ -0,0 +1,242 @@
+//! Handles fixing documentation mismatches by copying docs between the files of a group
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Context;
+use crate::c_parse::is_markdown;
+use crate::docfig::Settings;
+use crate::docwen_check::{DocRules, FilePosition, LineSource, Mismatch, MismatchKind};
+use crate::output::relative_path;
+
+/// Number of unchanged lines shown around each change in a unified diff
+const DIFF_CONTEXT: usize = 3;
+
+/// The planned edits of a single file
+#[derive(Debug)]
+pub struct FileFix
+{
+    pub path: PathBuf,
+    pub original: String,
+    replacements: Vec<Replacement> // Sorted by start, not overlapping
+}
+
+/// Replaces 'removed' lines starting at row 'start' with the 'inserted' lines
+#[derive(Debug)]
+struct Replacement
+{
+    start: usize,
+    removed: usize,
+    inserted: Vec<String>
+}
+
+/// The lines of the doc block above a function and the rows they occupy
+struct DocRange
+{
+    start: usize, // First row of the block (== function row if there are no docs)
+    lines: Vec<String>, // Raw lines of the block without the indentation of the function
+    indent: String // Indentation of the function line
+}
+
+impl FileFix
+{
+    /// Returns the fixed content of the file.
+    pub fn fixed(&self) -> String
+    {
+        let newline = if self.original.contains("\r\n") { "\r\n" } else { "\n" };
+        let lines: Vec<&str> = self.original.lines().collect();
+
+        let mut out: Vec<&str> = Vec::with_capacity(lines.len());
+        let mut row = 0;
+        for r in &self.replacements
+        {
+            out.extend(&lines[row..r.start]);
+            out.extend(r.inserted.iter().map(String::as_str));
+            row = r.start + r.removed;
+        }
+        out.extend(&lines[row.min(lines.len())..]);
+
+        let mut fixed = out.join(newline);
+        if self.original.ends_with('\n') { fixed.push_str(newline); }
+        fixed
+    }
+
+    /// Returns the planned edits as a unified diff.
+    /// Uses the given (absolute!) target_path to display the path relative to it if possible.
+    pub fn unified_diff(&self, abs_target_path: impl AsRef<Path>) -> String
+    {
+        let path = relative_path(&self.path, abs_target_path.as_ref()).replace('\\', "/");
+        let lines: Vec<&str> = self.original.lines().collect();
+        let mut out = format!("--- a/{path}\n+++ b/{path}\n");
+
+        // GROUP REPLACEMENTS WHOSE CONTEXTS OVERLAP INTO HUNKS
+        let mut hunks: Vec<&[Replacement]> = Vec::new();
+        let mut first = 0;
+        for i in 1..=self.replacements.len()
+        {
+            let ends_hunk = i == self.replacements.len() || {
+                let prev = &self.replacements[i - 1];
+                self.replacements[i].start > prev.start + prev.removed + 2 * DIFF_CONTEXT
+            };
+            if ends_hunk
+            {
+                hunks.push(&self.replacements[first..i]);
+                first = i;
+            }
+        }
+
+        // WRITE HUNKS
+        let mut delta: isize = 0; // Difference between new and old line numbers before the current hunk
+        for hunk in hunks
+        {
+            let (Some(head), Some(tail)) = (hunk.first(), hunk.last()) else { continue; };
+            let old_start = head.start.saturating_sub(DIFF_CONTEXT);
+            let old_end = (tail.start + tail.removed + DIFF_CONTEXT).min(lines.len());
+
+            let mut body = String::new();
+            let mut row = old_start;
+            let mut new_len = 0;
+            for r in hunk
+            {
+                lines[row..r.start].iter().for_each(|l| body.push_str(&format!(" {l}\n")));
+                lines[r.start..r.start + r.removed].iter().for_each(|l| body.push_str(&format!("-{l}\n")));
+                r.inserted.iter().for_each(|l| body.push_str(&format!("+{l}\n")));
+                new_len += r.start - row + r.inserted.len();
+                row = r.start + r.removed;
+            }
+            lines[row..old_end].iter().for_each(|l| body.push_str(&format!(" {l}\n")));
+            new_len += old_end - row;
+
+            let old_len = old_end - old_start;
+            let new_start = (old_start as isize + delta) as usize;
+            out.push_str(&format!("@@ -{} +{} @@\n", hunk_range(old_start, old_len), hunk_range(new_start, new_len)));
+            out.push_str(&body);
+            delta += new_len as isize - old_len as isize;
+        }
+        out
+    }
+}
+
+/// Formats the (0-based) start row and length of a hunk side as in a unified diff.
+fn hunk_range(start: usize, len: usize) -> String
+{
+    // Empty ranges refer to the line before them
+    if len == 0 { format!("{start},0") } else { format!("{},{len}", start + 1) }
+}
+
+/// Computes the edits that fix the given mismatches by copying the doc block of the first documented
+/// position of each mismatch to all other positions. Only 'differs' and 'missing' mismatches are fixed.
+/// Positions whose doc blocks contain suppressions or share a line with code and markdown sections
+/// are left unchanged.
+pub fn compute_fixes(mismatches: &[Mismatch], settings: &Settings) -> anyhow::Result<Vec<FileFix>>
+{
+    Ok(compute_fixes_with_unfixed(mismatches, settings)?.0)
+}
+
+/// Like compute_fixes, but also returns the mismatches that the fixes leave (at least partly) unfixed:
+/// all mismatches of other kinds (e.g. suppressions, locations or references), those without any
+/// documented position and those with a position whose doc block cannot be replaced.
+pub fn compute_fixes_with_unfixed<'a>(mismatches: &'a [Mismatch], settings: &Settings)
+    -> anyhow::Result<(Vec<FileFix>, Vec<&'a Mismatch>)>
+{
+    let rules = DocRules::from_settings(settings);
+    let mut sources: HashMap<PathBuf, String> = HashMap::new();
+    let mut replacements: HashMap<PathBuf, Vec<Replacement>> = HashMap::new();
+    let mut unfixed: Vec<&Mismatch> = Vec::new();
+
+    for m in mismatches
+    {
+        if !matches!(m.kind, MismatchKind::Differs | MismatchKind::Missing)
+        {
+            unfixed.push(m);
+            continue;
+        }
+
+        for p in &m.positions
+        {
+            if !sources.contains_key(&p.path)
+            {
+                let src = fs::read_to_string(&p.path)
+                    .with_context(|| format!("Failed to read {}", p.path.display()))?;
+                sources.insert(p.path.clone(), src);
+            }
+        }
+
+        let ranges: Vec<Option<DocRange>> = m.positions.iter()
+            .map(|p| doc_range(&sources[&p.path], p, &rules))
+            .collect();
+
+        let Some(canonical) = ranges.iter().flatten().find(|r| !r.lines.is_empty()) else {
+            unfixed.push(m);
+            continue;
+        };
+        if ranges.iter().any(Option::is_none) { unfixed.push(m); }
+        for (p, range) in m.positions.iter().zip(&ranges)
+        {
+            let Some(range) = range else { continue; };
+            if range.lines == canonical.lines { continue; }
+
+            replacements.entry(p.path.clone()).or_default().push(Replacement{
+                start: range.start,
+                removed: range.lines.len(),
+                inserted: canonical.lines.iter()
+                    .map(|l| if l.is_empty() { String::new() } else { format!("{}{l}", range.indent) })
+                    .collect()
+            });
+        }
+    }
+
+    let mut fixes: Vec<FileFix> = replacements.into_iter()
+        .map(|(path, mut replacements)| {
+            // Drop replacements that overlap an earlier one (e.g. the same position in multiple mismatches)
+            replacements.sort_by_key(|r| r.start);
+            let mut kept: Vec<Replacement> = Vec::new();
+            for r in replacements
+            {
+                if kept.last().is_none_or(|k| r.start >= k.start + k.removed.max(1)) { kept.push(r); }
+            }
+            let original = sources.remove(&path).unwrap_or_default();
+            FileFix{path, original, replacements: kept}
+        })
+        .collect();
+    fixes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok((fixes, unfixed))
+}
+
+/// Writes the fixed content of all given fixes to their files.
+pub fn apply_fixes(fixes: &[FileFix]) -> anyhow::Result<()>
+{
+    for fix in fixes
+    {
+        fs::write(&fix.path, fix.fixed()).with_context(|| format!("Failed to write {}", fix.path.display()))?;
+    }
+    Ok(())
+}
+
+/// Returns the doc block above the function at the given position in the given source text.
+/// Returns None if the block cannot be replaced safely (suppressions, docs sharing a line with code or
+/// markdown sections).
+fn doc_range(source: &str, pos: &FilePosition, rules: &DocRules) -> Option<DocRange>
+{
+    if is_markdown(&pos.path) { return None; }
+
+    let line_source = LineSource{src: source.to_string(), init_row: pos.row};
+    let block = line_source.doc_block(rules);
+    if !block.suppressions.is_empty() { return None; }
+
+    let is_whole_line = (0..block.doc_len)
+        .all(|n| line_source.trimmed_line_by_offset(block.offset(n)) == block.line(n));
+    if !is_whole_line { return None; }
+
+    let raw_lines: Vec<&str> = source.lines().collect();
+    let function_line = raw_lines.get(pos.row).copied().unwrap_or("");
+    let indent = function_line[..function_line.len() - function_line.trim_start().len()].to_string();
+
+    let height = if block.doc_len == 0 { 0 } else { (-block.offset(block.doc_len - 1)) as usize };
+    let start = pos.row.checked_sub(height)?;
+    let lines = raw_lines.get(start..pos.row)?.iter()
+        .map(|l| l.strip_prefix(indent.as_str()).unwrap_or(l.trim_start()).trim_end().to_string())
+        .collect();
+
+    Some(DocRange{start, lines, indent})
+}
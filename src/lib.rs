@@ -1,4 +1,15 @@
 pub mod docfig;
 pub mod toml_manager;
 pub mod docwen_check;
-pub mod c_parse;
\ No newline at end of file
+pub mod c_parse;
+pub mod cache;
+pub mod output;
+pub mod fix;
+pub mod blame;
+pub mod docref;
+pub mod manifest;
+pub mod watch;
+pub mod lsp;
+pub mod tui;
+pub use c_parse::{get_function_id, parse_file_functions};
+pub use docwen_check::{FilePosition, FunctionID};
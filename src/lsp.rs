@@ -0,0 +1,213 @@
+//! Handles 'docwen lsp': a minimal language server (JSON-RPC over stdio) that publishes the mismatches
+//! of the groups containing a saved file as diagnostics
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
+use crate::docwen_check::{self, CheckOptions, CheckReport, Mismatch};
+use crate::toml_manager;
+
+/// JSON-RPC error code for requests whose method is not supported
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// LSP severity of the published diagnostics (warning)
+const SEVERITY_WARNING: u8 = 2;
+
+/// Runs the language server on the given input and output until the client sends 'exit' or closes the input.
+/// On 'textDocument/didSave', the groups of the nearest *docwen.toml* that contain the saved file are
+/// checked (with the saved text if the client sends it) and their mismatches are published as diagnostics
+/// at the (0-based) rows of the divergent doc lines. The diagnostics of each file combine the last check of
+/// every group, so files whose mismatches were fixed get empty diagnostics and those of unchecked groups are kept.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> anyhow::Result<()>
+{
+    let mut published: HashMap<String, BTreeMap<String, Vec<Value>>> = HashMap::new(); // Diagnostics of each group by URI
+    while let Some(message) = read_message(&mut input)?
+    {
+        let method = message["method"].as_str().unwrap_or_default();
+        let id = message.get("id").cloned();
+        match method
+        {
+            "initialize" =>
+                {
+                    let capabilities = json!({
+                        "textDocumentSync": {"openClose": true, "save": {"includeText": true}}
+                    });
+                    let result = json!({"capabilities": capabilities, "serverInfo": {"name": "docwen"}});
+                    write_message(&mut output, &json!({"jsonrpc": "2.0", "id": id, "result": result}))?;
+                },
+            "textDocument/didSave" =>
+                {
+                    let params = &message["params"];
+                    let Some(path) = params["textDocument"]["uri"].as_str().and_then(path_from_uri) else { continue; };
+                    let text = params["text"].as_str().map(str::to_string);
+                    match check_saved_file(&path, text)
+                    {
+                        Ok((toml, report)) =>
+                            {
+                                // Only the files of the checked groups can change
+                                let mut changed: BTreeSet<String> = BTreeSet::new();
+                                for (group, diagnostics) in group_diagnostics(&report)
+                                {
+                                    changed.extend(diagnostics.keys().cloned());
+                                    let key = format!("{}:{group}", toml.display());
+                                    changed.extend(published.insert(key, diagnostics).into_iter().flat_map(BTreeMap::into_keys));
+                                }
+                                for uri in changed
+                                {
+                                    let diagnostics = published.values().filter_map(|d| d.get(&uri)).flatten().cloned().collect();
+                                    publish(&mut output, &uri, diagnostics)?;
+                                }
+                            },
+                        Err(e) =>
+                            {
+                                let params = json!({"type": 1, "message": format!("docwen: {e:#}")});
+                                write_message(&mut output, &json!({"jsonrpc": "2.0", "method": "window/logMessage", "params": params}))?;
+                            },
+                    }
+                },
+            "shutdown" => write_message(&mut output, &json!({"jsonrpc": "2.0", "id": id, "result": null}))?,
+            "exit" => return Ok(()),
+            _ if id.is_some() =>
+                {
+                    let error = json!({"code": METHOD_NOT_FOUND, "message": format!("Unsupported method '{method}'")});
+                    write_message(&mut output, &json!({"jsonrpc": "2.0", "id": id, "error": error}))?;
+                },
+            _ => {}, // Other notifications (e.g. 'initialized', 'textDocument/didOpen') are ignored
+        }
+    }
+    Ok(())
+}
+
+/// Checks the groups of the nearest *docwen.toml* (above the given file) that contain the given file,
+/// using the given text as its content if one is given. Returns the path of the *docwen.toml* with the report.
+fn check_saved_file(path: &Path, text: Option<String>) -> anyhow::Result<(PathBuf, CheckReport)>
+{
+    let dir = path.parent().with_context(|| format!("Could not access parent of {}", path.display()))?;
+    let toml = toml_manager::find_toml(dir)?;
+    let text = match text
+    {
+        Some(text) => text,
+        None => std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?,
+    };
+    let options = CheckOptions{stdin_file: Some((path.to_path_buf(), text)), ..Default::default()};
+    let report = docwen_check::check_with_options(&toml, &options)?;
+    Ok((toml, report))
+}
+
+/// Returns the name and the LSP diagnostics of each checked group of the given report
+/// (whose mismatches are in group order, see docwen_check::check_iter).
+pub fn group_diagnostics(report: &CheckReport) -> Vec<(String, BTreeMap<String, Vec<Value>>)>
+{
+    let mut rest = report.mismatches.as_slice();
+    report.groups.iter()
+        .map(|group|
+            {
+                let (mismatches, tail) = rest.split_at(group.mismatches.min(rest.len()));
+                rest = tail;
+                (group.name.clone(), diagnostics(mismatches))
+            })
+        .collect()
+}
+
+/// Returns the LSP diagnostics of the given mismatches by URI: one per mismatch and position, at its doc row.
+pub fn diagnostics(mismatches: &[Mismatch]) -> BTreeMap<String, Vec<Value>>
+{
+    let mut diagnostics: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for m in mismatches
+    {
+        for (pos, doc_row) in m.positions.iter().zip(&m.doc_rows)
+        {
+            let range = json!({
+                "start": {"line": doc_row, "character": 0},
+                "end": {"line": doc_row + 1, "character": 0}
+            });
+            diagnostics.entry(uri_from_path(&pos.path)).or_default().push(json!({
+                "range": range,
+                "severity": SEVERITY_WARNING,
+                "code": m.kind.as_str(),
+                "source": "docwen",
+                "message": format!("Docs of '{}' {}: {}", m.function.name, m.kind.as_str(), m.line)
+            }));
+        }
+    }
+    diagnostics
+}
+
+/// Sends a 'textDocument/publishDiagnostics' notification with the given diagnostics for the given URI.
+fn publish(output: &mut impl Write, uri: &str, diagnostics: Vec<Value>) -> anyhow::Result<()>
+{
+    let params = json!({"uri": uri, "diagnostics": diagnostics});
+    write_message(output, &json!({"jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": params}))
+}
+
+/// Reads the next JSON-RPC message ('Content-Length' header, empty line, JSON body) from the given input.
+/// Returns None if the input is closed.
+pub fn read_message(input: &mut impl BufRead) -> anyhow::Result<Option<Value>>
+{
+    // HEADERS
+    let mut content_length: Option<usize> = None;
+    loop
+    {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 { return Ok(None); }
+        let line = line.trim_end();
+        if line.is_empty()
+        {
+            if content_length.is_some() { break; }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') && name.eq_ignore_ascii_case("Content-Length")
+        {
+            content_length = Some(value.trim().parse().with_context(|| format!("Invalid header '{line}'"))?);
+        }
+    }
+
+    // BODY
+    let Some(len) = content_length else { bail!("Missing Content-Length header"); };
+    let mut body = vec![0; len];
+    input.read_exact(&mut body).context("Failed to read message body")?;
+    Ok(Some(serde_json::from_slice(&body).context("Invalid JSON-RPC message")?))
+}
+
+/// Writes the given JSON-RPC message with its 'Content-Length' header to the given output.
+pub fn write_message(output: &mut impl Write, message: &Value) -> anyhow::Result<()>
+{
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
+
+/// Returns the path of the given 'file://' URI (percent-decoded) or None for other schemes.
+pub fn path_from_uri(uri: &str) -> Option<PathBuf>
+{
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len()
+    {
+        let hex = encoded.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (encoded[i], hex)
+        {
+            (b'%', Some(byte)) => { decoded.push(byte); i += 3; },
+            (byte, _) => { decoded.push(byte); i += 1; },
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(decoded).ok()?))
+}
+
+/// Returns the 'file://' URI of the given absolute path (percent-encoding all bytes except unreserved
+/// characters and '/').
+pub fn uri_from_path(path: &Path) -> String
+{
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes()
+    {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) { uri.push(byte as char); }
+        else { uri.push_str(&format!("%{byte:02X}")); }
+    }
+    uri
+}
@@ -1,7 +1,16 @@
-use std::path::{PathBuf};
-use std::process;
-use clap::{Parser, Subcommand};
-use docwen::{docwen_check, toml_manager};
+use std::path::{Path, PathBuf};
+use std::{io, process};
+use std::io::{IsTerminal, Write};
+use std::time::{Instant, SystemTime};
+use anyhow::Context;
+use clap::{Parser, Subcommand, ValueEnum};
+use docwen::{cache, docwen_check, fix, lsp, toml_manager, tui, watch};
+use docwen::docfig::{ConfigError, Docfig};
+use docwen::c_parse::Language;
+use docwen::docwen_check::{CheckOptions, CheckReport, MismatchKind};
+use docwen::output;
+use docwen::output::Template;
+use docwen::toml_manager::{CreateOptions, UpdateOptions};
 
 /// 'docwen' - A tool for automatically checking if docs match between C/C++ header and source files
 #[derive(Parser)]
@@ -11,10 +20,14 @@ use docwen::{docwen_check, toml_manager};
     about = "Scans file pairs and reports documentation mismatches",
     propagate_version = true
 )]
-struct CLI
+struct Cli
 {
     #[command(subcommand)]
     command: Command,
+
+    /// Print errors while loading the docwen.toml as one JSON object (kind, message, path, line, column) to stderr
+    #[arg(long, global = true)]
+    print_config_errors_as_json: bool,
 }
 
 /// All commands for *docwen*. More information about the commands
@@ -25,66 +38,499 @@ enum Command
     /// create [<path>] - Creates a default docwen.toml file at the specified path
     Create
     {
-        path: Option<PathBuf>
+        path: Option<PathBuf>,
+
+        /// Target the given directory and immediately add the file groups found in it
+        #[arg(long, value_name = "DIR")]
+        init_from: Option<PathBuf>,
+
+        /// Write the default docwen.toml with comments describing each setting
+        #[arg(long, conflicts_with = "init_from")]
+        annotated: bool,
+
+        /// Write an empty 'match_extensions' list instead of the default extensions, so nothing is grouped until extensions are added
+        #[arg(long)]
+        no_default_extensions: bool
     },
 
     /// update [<docwen.toml path>] - Updates the list of files tracked by the specified docwen.toml
     Update
     {
-      path: Option<PathBuf>
+      path: Option<PathBuf>,
+
+      /// Do not add generated files (containing one of the 'generated_markers' in their first lines)
+      #[arg(long)]
+      ignore_generated: bool,
+
+      /// Override a setting of the docwen.toml for this run without writing it (e.g. 'follow_symlinks=true')
+      #[arg(long = "set", value_name = "KEY=VALUE")]
+      overrides: Vec<String>,
+
+      /// Do not write the docwen.toml, but print the changes an update would make and exit with 1 if there are any (for CI)
+      #[arg(long)]
+      check: bool,
+
+      /// Output format of the changes
+      #[arg(long, value_enum, default_value_t = UpdateFormat::Text)]
+      format: UpdateFormat
+    },
+
+    /// schema - Prints the JSON Schema of docwen.toml (e.g. for editor integrations)
+    Schema,
+
+    /// tui [<docwen.toml path>] - Checks the specified docwen.toml once and shows the results interactively
+    Tui
+    {
+        path: Option<PathBuf>,
+
+        /// Override a setting of the docwen.toml for this run (e.g. 'trim_doc_block=true')
+        #[arg(long = "set", value_name = "KEY=VALUE")]
+        overrides: Vec<String>
     },
 
+    /// lsp - Runs a language server over stdio that publishes the mismatches of the groups
+    /// containing a saved file as diagnostics
+    Lsp,
+
     /// check [<docwen.toml path>] - Runs the docwen check and outputs mismatches between docs
     /// if any are found
     Check
     {
-        path: Option<PathBuf>
+        path: Option<PathBuf>,
+
+        /// Cache extracted functions next to the docwen.toml so unchanged files are not reparsed
+        #[arg(long)]
+        cache: bool,
+
+        /// Output format of the mismatches
+        #[arg(long, value_enum, default_value_t = Format::Text, conflicts_with = "format_template")]
+        format: Format,
+
+        /// Print one line per mismatching position using the given template.
+        /// Placeholders: {path}, {row}, {col}, {func}, {line}, {kind}
+        #[arg(long, value_name = "TEMPLATE")]
+        format_template: Option<String>,
+
+        /// Read the content of the file at the given path from stdin instead of from disk
+        /// and only check the groups containing it
+        #[arg(long, value_name = "PATH")]
+        stdin_file: Option<PathBuf>,
+
+        /// Additionally print the status of every checked group, including the ones without mismatches
+        #[arg(long, conflicts_with = "format_template")]
+        report_all_groups: bool,
+
+        /// Only print mismatches of the given kinds (differs, missing, suppression, location, undeclared, reference).
+        /// The exit code still reflects all mismatches
+        #[arg(long, value_name = "KIND", value_delimiter = ',', conflicts_with = "fix")]
+        only: Vec<MismatchKind>,
+
+        /// Preset for pre-commit hooks: print nothing if there are no mismatches, otherwise one
+        /// '{path}:{row}:{col}: {kind}: {func}: {line}' line per mismatch (and exit with 1)
+        #[arg(long, conflicts_with_all = ["format", "format_template", "report_all_groups", "fix"])]
+        pre_commit: bool,
+
+        /// Show the last author of each mismatching doc line (from 'git blame') below its mismatch
+        #[arg(long, conflicts_with_all = ["format", "format_template", "pre_commit", "fix"])]
+        blame: bool,
+
+        /// Print nothing and only exit with 0 if there are no mismatches, 1 if there are and 2 on errors
+        #[arg(long, conflicts_with_all = ["format", "format_template", "stdin_file", "report_all_groups", "only",
+                                          "pre_commit", "blame", "fix", "bench"])]
+        assert_clean: bool,
+
+        /// Only check the groups that are new or whose files changed compared to the given previous
+        /// version of the docwen.toml
+        #[arg(long, value_name = "OLD_TOML", conflicts_with = "files")]
+        changed_config: Option<PathBuf>,
+
+        /// Print the number of files and functions, shared and singleton functions and the coverage
+        /// (percentage of shared functions) in total and per group as JSON instead of the mismatches
+        #[arg(long, conflicts_with_all = ["format", "format_template", "report_all_groups", "pre_commit", "blame",
+                                          "assert_clean", "fix"])]
+        stats_json: bool,
+
+        /// Skip generated files (containing one of the 'generated_markers' in their first lines)
+        #[arg(long)]
+        ignore_generated: bool,
+
+        /// Keep running and check again whenever the docwen.toml or a file of its target changes
+        #[arg(long, conflicts_with_all = ["format", "format_template", "stdin_file", "pre_commit", "assert_clean",
+                                          "stats_json", "fix", "bench"])]
+        watch: bool,
+
+        /// Together with --watch: clear the terminal before each check (no-op if stdout is not a terminal)
+        #[arg(long, requires = "watch")]
+        clear: bool,
+
+        /// Override a setting of the docwen.toml for this run (e.g. 'mode=MATCH_MACRO_DOCS' or 'trim_doc_block=true')
+        #[arg(long = "set", value_name = "KEY=VALUE")]
+        overrides: Vec<String>,
+
+        /// Fix 'differs' and 'missing' mismatches by copying the docs of the first documented file
+        /// of each mismatch to the others
+        #[arg(long, conflicts_with_all = ["stdin_file", "format", "format_template"])]
+        fix: bool,
+
+        /// Together with --fix: print the changes as a unified diff instead of writing them
+        #[arg(long, requires = "fix")]
+        dry_run: bool,
+
+        /// Parse files whose extension has no registered extractor (e.g. '.ipp' or none) as this language (c or cpp)
+        #[arg(long, value_name = "LANGUAGE")]
+        assume_language: Option<Language>,
+
+        /// Check exactly the given files (relative to the current directory) against each other
+        /// instead of the filegroups of the docwen.toml (its settings are still used)
+        #[arg(long, value_name = "FILES", num_args = 1..)]
+        files: Option<Vec<PathBuf>>,
+
+        /// Skip files larger than this many bytes (with a warning) instead of parsing them (overrides the 'max_file_size' setting)
+        #[arg(long, value_name = "BYTES")]
+        max_file_size: Option<u64>,
+
+        /// Do not print hints on how to resolve mismatches (to stderr) after them
+        #[arg(long)]
+        no_hints: bool,
+
+        /// Only parse every file of every group and print the first syntax error of each file that does not
+        /// parse cleanly (exits with 1 if there are any), without comparing any docs
+        #[arg(long, conflicts_with_all = ["format", "format_template", "report_all_groups", "only", "pre_commit", "blame",
+                                          "assert_clean", "stats_json", "watch", "fix", "bench"])]
+        parse_only: bool,
+
+        /// Print the time the check took to stderr (for profiling)
+        #[arg(long, hide = true)]
+        bench: bool
     },
 }
 
+/// Output formats of the *check* command
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format
+{
+    /// Human-readable text
+    Text,
+
+    /// One JSON object per mismatch and line, printed as soon as its group is checked
+    Ndjson,
+
+    /// One XML document with a '<mismatch>' element per mismatch
+    Xml
+}
+
+/// Output formats of the *update* command
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum UpdateFormat
+{
+    /// Human-readable text
+    Text,
+
+    /// One JSON object with the added and changed filegroups
+    Json
+}
+
 fn main() -> anyhow::Result<()>
 {
-    let cli = CLI::parse();
+    let cli = Cli::parse();
+    let result = run(cli.command);
+    if cli.print_config_errors_as_json
+        && let Err(e) = &result
+        && let Some(config_error) = e.downcast_ref::<ConfigError>()
+    {
+        eprintln!("{}", output::config_error_json(config_error));
+        process::exit(1);
+    }
+    result
+}
 
-    match cli.command
+/// Runs the given command.
+fn run(command: Command) -> anyhow::Result<()>
+{
+    match command
     {
-        Command::Create { path } =>
+        Command::Create { path, init_from, annotated, no_default_extensions } =>
             {
                 let mut path = path_or_default_toml(path);
                 if !path.ends_with("docwen.toml") { path = path.join("docwen.toml"); }
-                toml_manager::create_default(&path)?;
-                println!("Created default docwen.toml at {:?}", path);
+                let options = CreateOptions{no_default_extensions};
+                match init_from
+                {
+                    Some(dir) =>
+                        {
+                            toml_manager::create_from_with(&path, &dir, &options)?;
+                            println!("Created docwen.toml for {:?} at {:?}", dir, path);
+                        },
+                    None if annotated =>
+                        {
+                            toml_manager::create_annotated_with(&path, &options)?;
+                            println!("Created annotated default docwen.toml at {:?}", path);
+                        },
+                    None =>
+                        {
+                            toml_manager::create_default_with(&path, &options)?;
+                            println!("Created default docwen.toml at {:?}", path);
+                        }
+                }
+            }
+        Command::Schema =>
+            {
+                println!("{}", serde_json::to_string_pretty(&Docfig::json_schema())?);
             }
-        Command::Update { path } =>
+        Command::Tui { path, overrides } =>
             {
-                let path = path_or_default_toml(path);
-                toml_manager::update_toml(&path)?;
-                println!("Updated {:?} successfully", path);
+                let path = path_or_found_toml(path)?;
+                let report = docwen_check::check_with_options(&path, &CheckOptions{overrides, ..Default::default()})?;
+                report.warnings.iter().for_each(|w| eprintln!("Warning: {}", w));
+                tui::run(tui::App::new(report))?;
             }
-        Command::Check { path } =>
+        Command::Lsp =>
             {
-                let path = path_or_default_toml(path);
-                let mismatches: Vec<String> = docwen_check::check(path)?;
-                match mismatches.len()
+                lsp::run(io::stdin().lock(), io::stdout().lock())?;
+            }
+        Command::Update { path, ignore_generated, overrides, check, format } =>
+            {
+                let path = path_or_found_toml(path)?;
+                let options = UpdateOptions{ignore_generated, overrides};
+                let (docfig, delta) = toml_manager::plan_update(&path, &options)?;
+                if check
                 {
-                    0 => {println!("Found no mismatches!"); process::exit(0); }
-                    _ =>
-                        {
-                            for m in &mismatches
-                            {
-                                println!("Mismatch in: {}\n", m);
-                            }
-                            process::exit(1);
-                        }
+                    match format
+                    {
+                        UpdateFormat::Json => println!("{}", delta.to_json()),
+                        UpdateFormat::Text if delta.is_empty() => println!("{:?} is up to date", path),
+                        UpdateFormat::Text => print!("{}", delta.diff()),
+                    }
+                    if delta.is_empty() { return Ok(()); }
+                    eprintln!("{:?} is out of date, run 'docwen update' to apply these changes", path);
+                    process::exit(1);
+                }
+                docfig.write_file(&path)?;
+                match format
+                {
+                    UpdateFormat::Json => println!("{}", delta.to_json()),
+                    UpdateFormat::Text => println!("Updated {:?} successfully", path),
                 }
             }
+        Command::Check { path, cache, format, format_template, stdin_file, report_all_groups,
+                         only, pre_commit, blame, assert_clean, changed_config, stats_json, ignore_generated, watch, clear, overrides, fix, dry_run, assume_language, files, max_file_size, no_hints, parse_only, bench } =>
+            {
+                let mut overrides = overrides;
+                if let Some(size) = max_file_size
+                {
+                    overrides.push(format!("max_file_size={size}"));
+                }
+
+                if assert_clean
+                {
+                    let code = match is_clean(path, cache, assume_language, files, ignore_generated, overrides)
+                    {
+                        Ok(true) => 0,
+                        Ok(false) => 1,
+                        Err(_) => 2,
+                    };
+                    process::exit(code);
+                }
+
+                let template = format_template.as_deref().map(Template::parse).transpose()?;
+                let path = path_or_found_toml(path)?;
+                let stdin_file = match stdin_file
+                {
+                    Some(file) => Some((file, io::read_to_string(io::stdin()).context("Failed to read stdin")?)),
+                    None => None,
+                };
+                let options = CheckOptions{
+                    cache: cache.then(|| cache::default_cache_path(&path)),
+                    stdin_file,
+                    assume_language,
+                    files,
+                    changed_config,
+                    ignore_generated,
+                    overrides
+                };
+                if watch
+                {
+                    return watch_check(&path, &options, &only, blame, report_all_groups, clear);
+                }
+                if parse_only
+                {
+                    let mut iter = docwen_check::check_iter(&path, &options)?;
+                    let display_base = iter.display_base()?;
+                    let failures = iter.parse_failures()?;
+                    iter.warnings().iter().for_each(|w| eprintln!("Warning: {}", w));
+                    if failures.is_empty()
+                    {
+                        println!("All files parse without errors");
+                        return Ok(());
+                    }
+                    failures.iter().for_each(|f| println!("{}:{}:{}: parse error",
+                                                          output::relative_path(&f.path, &display_base), f.row, f.column));
+                    process::exit(1);
+                }
+
+                let started = Instant::now();
+                if format == Format::Ndjson
+                {
+                    let mut iter = docwen_check::check_iter(&path, &options)?;
+                    let display_base = iter.display_base()?;
+                    let mut stdout = io::stdout().lock();
+                    let mut found = false;
+                    for m in iter.by_ref()
+                    {
+                        let m = m?;
+                        found = true;
+                        if !only.is_empty() && !only.contains(&m.kind) { continue; }
+                        writeln!(stdout, "{}", output::ndjson_line(&m, &display_base))?;
+                        stdout.flush()?;
+                    }
+                    if bench { eprintln!("Check took {:.3?}", started.elapsed()); }
+                    if report_all_groups
+                    {
+                        iter.group_summaries().iter().for_each(|g| println!("{}", output::ndjson_group_line(g)));
+                    }
+                    iter.warnings().iter().for_each(|w| eprintln!("Warning: {}", w));
+                    process::exit(if found { 1 } else { 0 });
+                }
+
+                let mut report = docwen_check::check_with_options(&path, &options)?;
+                if bench { eprintln!("Check took {:.3?}", started.elapsed()); }
+                let found = !report.mismatches.is_empty();
+                report.retain_kinds(&only);
+                report.warnings.iter().for_each(|w| eprintln!("Warning: {}", w));
+
+                if stats_json
+                {
+                    println!("{}", serde_json::to_string_pretty(&output::stats_json(&report.groups))?);
+                    process::exit(if found { 1 } else { 0 });
+                }
+
+                if fix
+                {
+                    let settings = Docfig::from_file_with_overrides(&path, &options.overrides)?.settings;
+                    let (fixes, unfixed) = fix::compute_fixes_with_unfixed(&report.mismatches, &settings)?;
+                    if dry_run
+                    {
+                        fixes.iter().for_each(|f| print!("{}", f.unified_diff(&report.target)));
+                    }
+                    else
+                    {
+                        fix::apply_fixes(&fixes)?;
+                        println!("Fixed {} file(s)", fixes.len());
+                    }
+
+                    // Mismatches that cannot be fixed automatically still fail the check
+                    for m in &unfixed
+                    {
+                        eprintln!("Not fixed: {}", docwen_check::format_mismatch(&m.line, &m.positions, &report.display_base));
+                    }
+                    process::exit(if unfixed.is_empty() { 0 } else { 1 });
+                }
+
+                if pre_commit
+                {
+                    output::pre_commit_lines(&report)?.iter().for_each(|l| println!("{}", l));
+                    process::exit(if found { 1 } else { 0 });
+                }
+
+                if format == Format::Xml
+                {
+                    print!("{}", output::xml_document(&report, report_all_groups));
+                    process::exit(if found { 1 } else { 0 });
+                }
+
+                if let Some(template) = template
+                {
+                    for m in &report.mismatches
+                    {
+                        template.render(m, &report.display_base).iter().for_each(|l| println!("{}", l));
+                    }
+                    process::exit(if found { 1 } else { 0 });
+                }
+
+                print_report(&path, &options.overrides, &report, blame, report_all_groups)?;
+                if !report.mismatches.is_empty() && !no_hints
+                {
+                    output::MISMATCH_HINTS.iter().for_each(|h| eprintln!("{}", h));
+                }
+                process::exit(if found { 1 } else { 0 });
+            }
     }
 
     Ok(())
 }
 
+/// Implements *check --assert-clean*: returns whether the check of the given (or nearest) *docwen.toml*
+/// finds no mismatches. Stops at the first mismatch.
+fn is_clean(path: Option<PathBuf>, cache: bool, assume_language: Option<Language>, files: Option<Vec<PathBuf>>,
+            ignore_generated: bool, overrides: Vec<String>) -> anyhow::Result<bool>
+{
+    let path = path_or_found_toml(path)?;
+    let options = CheckOptions{
+        cache: cache.then(|| cache::default_cache_path(&path)),
+        stdin_file: None,
+        assume_language,
+        files,
+        changed_config: None,
+        ignore_generated,
+        overrides
+    };
+    let mut iter = docwen_check::check_iter(&path, &options)?;
+    Ok(iter.next().transpose()?.is_none())
+}
+
+/// Prints the given report of the *docwen.toml* at the given path (with the given setting overrides) like *check*
+/// does without a format option.
+fn print_report(path: &Path, overrides: &[String], report: &CheckReport, blame: bool, report_all_groups: bool)
+    -> anyhow::Result<()>
+{
+    let settings = Docfig::from_file_with_overrides(path, overrides)?.settings;
+    let lines = if blame { output::report_lines_with_blame(report, &settings) }
+                else { output::report_lines(report, &settings) };
+    lines.iter().for_each(|l| println!("{}", l));
+    if report_all_groups
+    {
+        output::group_lines(&report.groups).iter().for_each(|l| println!("{}", l));
+    }
+    Ok(())
+}
+
+/// Implements *check --watch*: checks the *docwen.toml* at the given path and prints the report (see print_report)
+/// below a timestamp header again whenever one of its files changes. With 'clear', the terminal is cleared
+/// before each run (if stdout is one). Errors of a run are printed and do not stop watching.
+fn watch_check(path: &Path, options: &CheckOptions, only: &[MismatchKind], blame: bool, report_all_groups: bool,
+               clear: bool) -> anyhow::Result<()>
+{
+    let mut snapshot = watch::snapshot(path);
+    loop
+    {
+        if clear && io::stdout().is_terminal() { print!("{}", watch::CLEAR_SCREEN); }
+        println!("{}", watch::run_header(SystemTime::now()));
+
+        let result = docwen_check::check_with_options(path, options).and_then(|mut report| {
+            report.retain_kinds(only);
+            report.warnings.iter().for_each(|w| eprintln!("Warning: {}", w));
+            print_report(path, &options.overrides, &report, blame, report_all_groups)
+        });
+        if let Err(e) = result { eprintln!("Error: {e:#}"); }
+        io::stdout().flush()?;
+
+        snapshot = watch::wait_for_change(path, &snapshot);
+    }
+}
+
 /// Unwraps the given path option or defaults to the default *docwen.toml* path.
 fn path_or_default_toml(path: Option<PathBuf>) -> PathBuf
 {
     path.unwrap_or_else(|| PathBuf::from("./docwen.toml"))
-}
\ No newline at end of file
+}
+
+/// Unwraps the given path option or defaults to the nearest *docwen.toml* in the current directory
+/// or any of its parent directories.
+fn path_or_found_toml(path: Option<PathBuf>) -> anyhow::Result<PathBuf>
+{
+    match path
+    {
+        Some(path) => Ok(path),
+        None => toml_manager::find_toml(std::env::current_dir().context("Failed to get current directory")?),
+    }
+}
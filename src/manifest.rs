@@ -0,0 +1,133 @@
+//! Handles pairing manifests, which define filegroups outside of the *docwen.toml* (see the 'pairing_manifest' setting)
+//!
+//! JSON format (*.json*):
+//! ```text
+//! [
+//!     {"name": "vec", "files": ["include/vec.h", "bindings/vec.py.h"], "canonical": "include/vec.h"}
+//! ]
+//! ```
+//! CSV format (any other extension), one file per row, rows of the same group are merged:
+//! ```text
+//! # group,file[,canonical]
+//! vec,include/vec.h,canonical
+//! vec,bindings/vec.py.h
+//! ```
+//! Files are relative to the target. The optional canonical file of a group is checked first, so its docs are
+//! the ones 'check --fix' copies to the other files.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use crate::docfig::FileGroup;
+
+/// A single group of a pairing manifest
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestGroup
+{
+    pub name: String,
+    pub files: Vec<PathBuf>,
+    #[serde(default)]
+    pub canonical: Option<PathBuf> // File whose docs the others should follow (one of 'files')
+}
+
+/// The groups defined by a pairing manifest
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PairingManifest
+{
+    pub groups: Vec<ManifestGroup>
+}
+
+impl PairingManifest
+{
+    /// Reads and parses the pairing manifest at the given path (as JSON if its extension is *.json*, else as CSV).
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self>
+    {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read pairing manifest {}", path.display()))?;
+        let manifest = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")) { Self::parse_json(&text) }
+                       else { Self::parse_csv(&text) };
+        manifest.with_context(|| format!("Failed to parse pairing manifest {}", path.display()))
+    }
+
+    /// Parses the given JSON manifest (an array of groups).
+    /// Returns an error for invalid JSON, duplicate group names and canonical files that are not part of their group.
+    pub fn parse_json(text: &str) -> anyhow::Result<Self>
+    {
+        let manifest = PairingManifest{groups: serde_json::from_str(text)?};
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Parses the given CSV manifest ('group,file[,canonical]' rows, '#' starts a comment line).
+    /// Returns an error for rows with a missing file or an unknown third column and for groups with several canonical files.
+    pub fn parse_csv(text: &str) -> anyhow::Result<Self>
+    {
+        let mut manifest = PairingManifest::default();
+        for (i, line) in text.lines().enumerate()
+        {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+
+            let columns: Vec<&str> = line.split(',').map(str::trim).collect();
+            let (name, file, mark) = match columns[..]
+            {
+                [name, file] if !file.is_empty() => (name, PathBuf::from(file), ""),
+                [name, file, mark] if !file.is_empty() => (name, PathBuf::from(file), mark),
+                _ => bail!("Line {}: Expected 'group,file[,canonical]', found '{line}'", i + 1),
+            };
+            if !matches!(mark, "" | "canonical") { bail!("Line {}: Unknown column '{mark}' (expected 'canonical')", i + 1); }
+
+            let group = match manifest.groups.iter_mut().position(|g| g.name == name)
+            {
+                Some(index) => &mut manifest.groups[index],
+                None =>
+                    {
+                        manifest.groups.push(ManifestGroup{name: name.to_string(), files: Vec::new(), canonical: None});
+                        manifest.groups.last_mut().context("Group was just added")?
+                    },
+            };
+            if !mark.is_empty()
+            {
+                if group.canonical.is_some() { bail!("Line {}: Group '{name}' already has a canonical file", i + 1); }
+                group.canonical = Some(file.clone());
+            }
+            group.files.push(file);
+        }
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Returns the groups as FileGroups, with the canonical file (if any) first.
+    pub fn file_groups(&self) -> Vec<FileGroup>
+    {
+        self.groups.iter()
+            .map(|g| {
+                let mut files = g.files.clone();
+                if let Some(canonical) = &g.canonical && let Some(index) = files.iter().position(|f| f == canonical)
+                {
+                    let canonical = files.remove(index);
+                    files.insert(0, canonical);
+                }
+                FileGroup{name: g.name.clone(), files}
+            })
+            .collect()
+    }
+
+    fn validate(&self) -> anyhow::Result<()>
+    {
+        let mut seen = HashSet::new();
+        for g in &self.groups
+        {
+            if !seen.insert(&g.name) { bail!("Duplicate group name: {}", g.name); }
+            if let Some(canonical) = &g.canonical && !g.files.contains(canonical)
+            {
+                bail!("Canonical file {} of group '{}' is not one of its files", canonical.display(), g.name);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+//! Handles formatting the results of 'docwen check' for output
+
+use std::path::Path;
+use anyhow::bail;
+use crate::blame;
+use crate::docfig::{ConfigError, Settings};
+use crate::docwen_check::{format_mismatch_with_doc_rows, CheckReport, FilePosition, GroupSummary, Mismatch};
+use crate::toml_manager::normalize_path;
+
+/// Placeholders that can be used in a Template
+pub const PLACEHOLDERS: [&str; 7] = ["path", "row", "col", "func", "line", "kind", "doc_row"];
+
+/// Template of the lines printed by 'check --pre-commit'
+pub const PRE_COMMIT_TEMPLATE: &str = "{path}:{row}:{col}: {kind}: {func}: {line}";
+
+/// Footer printed (to stderr) by 'check' after its mismatches, unless '--no-hints' is given
+pub const MISMATCH_HINTS: [&str; 3] = [
+    "Hint: Run 'docwen check --fix --dry-run' to see how the docs would be unified, or '--fix' to apply it",
+    "Hint: Lines that belong to only one file (e.g. '// NOLINT') can be excluded via the 'suppression_prefixes' setting",
+    "Hint: Run with --no-hints to hide these hints",
+];
+
+/// A single part of a parsed Template
+#[derive(Debug, PartialEq, Eq)]
+enum Segment
+{
+    Literal(String),
+    Placeholder(String)
+}
+
+/// A user-supplied one-line output format (e.g. "{path}:{row}: {line}").
+/// Rendered once per position of a mismatch. Literal braces are written as '{{' and '}}'.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Template
+{
+    segments: Vec<Segment>
+}
+
+impl Template
+{
+    /// Parses the given template string.
+    /// Returns an error for unknown placeholders and unbalanced braces.
+    pub fn parse(template: &str) -> anyhow::Result<Self>
+    {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next()
+        {
+            match c
+            {
+                '{' if chars.peek() == Some(&'{') => { chars.next(); literal.push('{'); },
+                '}' if chars.peek() == Some(&'}') => { chars.next(); literal.push('}'); },
+                '{' =>
+                    {
+                        let mut name = String::new();
+                        let mut closed = false;
+                        for c in chars.by_ref()
+                        {
+                            if c == '}' { closed = true; break; }
+                            name.push(c);
+                        }
+                        if !closed { bail!("Unterminated '{{' in format template (use '{{{{' for a literal brace)"); }
+                        if !PLACEHOLDERS.contains(&name.as_str())
+                        {
+                            bail!("Unknown placeholder '{{{}}}' in format template (available: {})",
+                                name, PLACEHOLDERS.map(|p| format!("{{{p}}}")).join(", "));
+                        }
+
+                        if !literal.is_empty() { segments.push(Segment::Literal(std::mem::take(&mut literal))); }
+                        segments.push(Segment::Placeholder(name));
+                    },
+                '}' => bail!("Unmatched '}}' in format template (use '}}}}' for a literal brace)"),
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() { segments.push(Segment::Literal(literal)); }
+        Ok(Template{segments})
+    }
+
+    /// Renders the template once for each position of the given mismatch.
+    /// Uses the given (absolute!) target_path to display the paths relative to it if possible.
+    pub fn render(&self, mismatch: &Mismatch, abs_target_path: impl AsRef<Path>) -> Vec<String>
+    {
+        mismatch.positions.iter().zip(&mismatch.doc_rows)
+            .map(|(pos, doc_row)| self.render_position(mismatch, pos, *doc_row, abs_target_path.as_ref()))
+            .collect()
+    }
+
+    /// Renders the template for a single position (and its doc row) of the given mismatch.
+    fn render_position(&self, mismatch: &Mismatch, pos: &FilePosition, doc_row: usize,
+                       abs_target_path: &Path) -> String
+    {
+        let mut out = String::new();
+        for segment in &self.segments
+        {
+            match segment
+            {
+                Segment::Literal(l) => out.push_str(l),
+                Segment::Placeholder(p) => match p.as_str()
+                {
+                    "path" => out.push_str(&relative_path(&pos.path, abs_target_path)),
+                    "row" => out.push_str(&pos.row.to_string()),
+                    "col" => out.push_str(&pos.column.to_string()),
+                    "func" => out.push_str(&mismatch.function.name),
+                    "line" => out.push_str(&mismatch.line),
+                    "kind" => out.push_str(mismatch.kind.as_str()),
+                    "doc_row" => out.push_str(&doc_row.to_string()),
+                    _ => {} // Rejected by Template::parse
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Returns the lines 'docwen check' prints for the given report without a Template,
+/// using the messages of the given settings.
+pub fn report_lines(report: &CheckReport, settings: &Settings) -> Vec<String>
+{
+    annotated_report_lines(report, settings, |_| String::new())
+}
+
+/// Like 'report_lines', but lists the last author (see blame::last_author) of the doc row of each
+/// position below its mismatch. Positions whose author cannot be found are left out.
+pub fn report_lines_with_blame(report: &CheckReport, settings: &Settings) -> Vec<String>
+{
+    annotated_report_lines(report, settings, |m| {
+        m.positions.iter().zip(&m.doc_rows)
+            .filter_map(|(pos, doc_row)| blame::last_author(&pos.path, *doc_row).map(|author|
+                format!("   doc row {} of {:?}: last changed by {}\n", doc_row,
+                        relative_path(&pos.path, &report.display_base), author)))
+            .collect()
+    })
+}
+
+/// Returns the lines of 'report_lines' with the text returned by 'annotate' appended to each mismatch.
+fn annotated_report_lines(report: &CheckReport, settings: &Settings, annotate: impl Fn(&Mismatch) -> String)
+    -> Vec<String>
+{
+    if report.mismatches.is_empty()
+    {
+        return if settings.msg_no_mismatch.is_empty() { Vec::new() } else { vec![settings.msg_no_mismatch.clone()] };
+    }
+
+    report.mismatches.iter()
+        .map(|m| format!("{}{}\n{}", settings.msg_mismatch_prefix, format_mismatch_with_doc_rows(m, &report.display_base),
+                         annotate(m)))
+        .collect()
+}
+
+/// Returns the lines 'docwen check --pre-commit' prints for the given report: nothing if it is clean,
+/// otherwise one PRE_COMMIT_TEMPLATE line per mismatch (at its first position).
+pub fn pre_commit_lines(report: &CheckReport) -> anyhow::Result<Vec<String>>
+{
+    let template = Template::parse(PRE_COMMIT_TEMPLATE)?;
+    Ok(report.mismatches.iter()
+        .filter_map(|m| m.positions.first().zip(m.doc_rows.first())
+            .map(|(pos, doc_row)| template.render_position(m, pos, *doc_row, &report.display_base)))
+        .collect())
+}
+
+/// Returns the given mismatch as a single line of JSON (for '--format ndjson').
+/// Uses the given (absolute!) target_path to display the paths relative to it if possible.
+pub fn ndjson_line(mismatch: &Mismatch, abs_target_path: impl AsRef<Path>) -> String
+{
+    let positions: Vec<serde_json::Value> = mismatch.positions.iter().zip(&mismatch.doc_rows).zip(&mismatch.doc_spans)
+        .map(|((pos, doc_row), doc_span)| serde_json::json!({
+            "path": relative_path(&pos.path, abs_target_path.as_ref()),
+            "row": pos.row,
+            "col": pos.column,
+            "doc_row": doc_row,
+            "doc_span": doc_span
+        }))
+        .collect();
+
+    serde_json::json!({
+        "func": mismatch.function.name,
+        "params": mismatch.function.params,
+        "kind": mismatch.kind.as_str(),
+        "line": mismatch.line,
+        "positions": positions
+    }).to_string()
+}
+
+/// Returns the given report as an XML document (for '--format xml'): a '<docwen>' root with one
+/// '<mismatch>' element per mismatch and, if 'include_groups' is set, one '<group>' element per checked group.
+/// Paths are displayed relative to the display base of the report if possible.
+pub fn xml_document(report: &CheckReport, include_groups: bool) -> String
+{
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<docwen>\n");
+    for m in &report.mismatches
+    {
+        out.push_str(&format!("  <mismatch kind=\"{}\">\n", m.kind.as_str()));
+        out.push_str(&format!("    <function params=\"{}\">{}</function>\n",
+                              xml_escape(&m.function.params), xml_escape(&m.function.name)));
+        out.push_str(&format!("    <line>{}</line>\n", xml_escape(&m.line)));
+        for (pos, doc_row) in m.positions.iter().zip(&m.doc_rows)
+        {
+            out.push_str(&format!("    <position path=\"{}\" row=\"{}\" col=\"{}\" doc_row=\"{}\"/>\n",
+                                  xml_escape(&relative_path(&pos.path, &report.display_base)),
+                                  pos.row, pos.column, doc_row));
+        }
+        out.push_str("  </mismatch>\n");
+    }
+
+    if include_groups
+    {
+        for g in &report.groups
+        {
+            out.push_str(&format!("  <group name=\"{}\" status=\"{}\" files=\"{}\" functions=\"{}\" mismatches=\"{}\"/>\n",
+                                  xml_escape(&g.name), g.status(), g.files, g.functions, g.mismatches));
+        }
+    }
+    out.push_str("</docwen>\n");
+    out
+}
+
+/// Escapes the characters of the given text that are not allowed in XML text and attribute values.
+/// Control characters that XML 1.0 does not allow at all are dropped.
+fn xml_escape(text: &str) -> String
+{
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars()
+    {
+        match c
+        {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c.is_control() && (c as u32) < 0x20 => {},
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Returns one line per given group summary (for '--report-all-groups').
+pub fn group_lines(groups: &[GroupSummary]) -> Vec<String>
+{
+    groups.iter()
+        .map(|g| format!("{}: {} ({} files, {} functions, {} mismatches)",
+                         g.name, g.status(), g.files, g.functions, g.mismatches))
+        .collect()
+}
+
+/// Returns the given group summary as a single line of JSON (for '--format ndjson --report-all-groups').
+pub fn ndjson_group_line(group: &GroupSummary) -> String
+{
+    serde_json::json!({
+        "group": group.name,
+        "status": group.status(),
+        "files": group.files,
+        "functions": group.functions,
+        "mismatches": group.mismatches
+    }).to_string()
+}
+
+/// Returns the extraction statistics of the given checked groups as JSON (for '--stats-json'):
+/// the number of files and functions, how many functions are shared (compared) or singletons and
+/// the percentage of shared functions, in total and per group.
+pub fn stats_json(groups: &[GroupSummary]) -> serde_json::Value
+{
+    let coverage_pct = |shared: usize, functions: usize| {
+        if functions == 0 { 0.0 } else { (shared as f64 * 10000.0 / functions as f64).round() / 100.0 }
+    };
+    let stats = |files: usize, functions: usize, shared: usize| serde_json::json!({
+        "files": files,
+        "functions": functions,
+        "shared": shared,
+        "singletons": functions - shared,
+        "coverage_pct": coverage_pct(shared, functions)
+    });
+
+    let files = groups.iter().map(|g| g.files).sum();
+    let functions = groups.iter().map(|g| g.functions).sum();
+    let shared = groups.iter().map(|g| g.shared).sum();
+    let mut json = stats(files, functions, shared);
+    json["per_group"] = groups.iter()
+        .map(|g| {
+            let mut group = stats(g.files, g.functions, g.shared);
+            group["group"] = serde_json::json!(g.name);
+            group
+        })
+        .collect();
+    json
+}
+
+/// Returns the given config error as a single line of JSON (for '--print-config-errors-as-json').
+pub fn config_error_json(error: &ConfigError) -> String
+{
+    serde_json::json!({
+        "kind": error.kind.as_str(),
+        "message": error.message,
+        "path": error.path.display().to_string(),
+        "line": error.line,
+        "column": error.column
+    }).to_string()
+}
+
+/// Returns the given path relative to the given (absolute!) target_path if possible.
+pub fn relative_path(path: &Path, abs_target_path: &Path) -> String
+{
+    let path = normalize_path(path);
+    let abs_target_path = normalize_path(abs_target_path);
+    path.strip_prefix(&abs_target_path).unwrap_or(&path).display().to_string()
+}
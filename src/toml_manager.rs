@@ -2,13 +2,19 @@
 
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
+use std::fs;
 use std::fs::OpenOptions;
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use anyhow::Context;
+use regex::Regex;
 use walkdir::WalkDir;
+use crate::c_parse;
 use crate::docfig::{Docfig, FileGroup, Settings};
 
+/// The 'match_extensions' line of the built-in defaults
+const DEFAULT_EXTENSIONS: &str = r#"match_extensions = ["h", "c", "hpp", "cc", "cpp"]"#;
+
 pub const DEFAULT_TOML: &str = r#"[settings]
 target = "src"
 match_extensions = ["h", "c", "hpp", "cc", "cpp"]
@@ -16,10 +22,158 @@ mode = "MATCH_FUNCTION_DOCS"
 manual = []
 "#;
 
+/// DEFAULT_TOML with comments describing each field (for *create --annotated*)
+pub const ANNOTATED_DEFAULT_TOML: &str = r#"# The settings used by docwen (see README.md for all optional settings)
+[settings]
+# Directory that is checked (relative to this file)
+target = "src"
+# Files with any of these extensions are grouped together by 'update' if their names match (an empty list groups nothing)
+match_extensions = ["h", "c", "hpp", "cc", "cpp"]
+# MATCH_FUNCTION_DOCS, MATCH_FUNCTION_DOCS_UNQUALIFIED, MATCH_MACRO_DOCS, MATCH_STRUCT_DOCS or MATCH_ENUM_DOCS
+mode = "MATCH_FUNCTION_DOCS"
+# File names (without extension) that 'update' ignores, so their filegroups can be managed manually
+manual = []
+"#;
+
 /// Implements the docwen *create* command.
-/// Creates a default *docwen.toml* file at the given path.
-/// Returns an error if the path is invalid or already exists.
+/// Creates a default *docwen.toml* file at the given path. Uses the user template
+/// (see user_template_path) if it exists, otherwise DEFAULT_TOML.
+/// Returns an error if the path is invalid or already exists or if the template is invalid.
 pub fn create_default(path: impl AsRef<Path>) -> anyhow::Result<()>
+{
+    create_from_template(path, user_template_path().as_deref())
+}
+
+/// Options that modify the behavior of 'docwen create'.
+#[derive(Debug, Default)]
+pub struct CreateOptions
+{
+    /// Whether the created file has an empty 'match_extensions' list (also if it is created from the user
+    /// template), so 'update' groups nothing until extensions are added explicitly
+    pub no_default_extensions: bool,
+}
+
+/// Like create_default, but with the given CreateOptions.
+pub fn create_default_with(path: impl AsRef<Path>, options: &CreateOptions) -> anyhow::Result<()>
+{
+    match user_template_path().filter(|t| t.exists())
+    {
+        Some(template) => create_from_template_with(path, Some(&template), options),
+        None => create_with_content(path, &default_content(DEFAULT_TOML, options)),
+    }
+}
+
+/// Creates a *docwen.toml* file at the given path with the content of the given template file.
+/// Falls back to DEFAULT_TOML if no template is given or it does not exist.
+/// Returns an error if the path is invalid or already exists or if the template is no valid *docwen.toml*.
+pub fn create_from_template(path: impl AsRef<Path>, template: Option<&Path>) -> anyhow::Result<()>
+{
+    create_from_template_with(path, template, &CreateOptions::default())
+}
+
+/// Like create_from_template, but with the given CreateOptions.
+pub fn create_from_template_with(path: impl AsRef<Path>, template: Option<&Path>, options: &CreateOptions) -> anyhow::Result<()>
+{
+    let Some(template) = template.filter(|t| t.exists()) else {
+        return create_with_content(path, &default_content(DEFAULT_TOML, options));
+    };
+
+    Docfig::from_file(template).with_context(|| format!("Invalid template {:?}", template.display()))?;
+    let content = fs::read_to_string(template)
+        .with_context(|| format!("Failed to read template {:?}", template.display()))?;
+    match options.no_default_extensions
+    {
+        true => create_with_content(path, &without_extensions(&content)?),
+        false => create_with_content(path, &content),
+    }
+}
+
+/// Implements the docwen *create --annotated* command.
+/// Creates a *docwen.toml* file at the given path with the content of ANNOTATED_DEFAULT_TOML.
+/// Returns an error if the path is invalid or already exists.
+pub fn create_annotated(path: impl AsRef<Path>) -> anyhow::Result<()>
+{
+    create_annotated_with(path, &CreateOptions::default())
+}
+
+/// Like create_annotated, but with the given CreateOptions.
+pub fn create_annotated_with(path: impl AsRef<Path>, options: &CreateOptions) -> anyhow::Result<()>
+{
+    create_with_content(path, &default_content(ANNOTATED_DEFAULT_TOML, options))
+}
+
+/// Returns the given built-in default content with the given CreateOptions applied.
+fn default_content(content: &str, options: &CreateOptions) -> String
+{
+    if !options.no_default_extensions { return content.to_string(); }
+    content.replacen(DEFAULT_EXTENSIONS, "match_extensions = []", 1)
+}
+
+/// Returns the given template content with an empty 'match_extensions' list.
+/// Only the value is replaced, so the comments and layout of the template are kept.
+fn without_extensions(content: &str) -> anyhow::Result<String>
+{
+    let table = toml::de::DeTable::parse(content)?;
+    let span = table.get_ref().get("settings")
+        .and_then(|settings| settings.get_ref().get("match_extensions"))
+        .map(|extensions| extensions.span());
+    Ok(match span
+    {
+        Some(span) => format!("{}[]{}", &content[..span.start], &content[span.end..]),
+        None => content.to_string(), // No extensions are matched by default
+    })
+}
+
+/// Returns the path of the user template for *create*: 'docwen/default.toml' in $XDG_CONFIG_HOME
+/// or, if that is not set, in '~/.config'.
+pub fn user_template_path() -> Option<PathBuf>
+{
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::home_dir().map(|home| home.join(".config")))?;
+    Some(config_dir.join("docwen").join("default.toml"))
+}
+
+/// Implements the docwen *create --init-from* command.
+/// Creates a default *docwen.toml* file at the given path that targets the given target_dir
+/// and immediately populates its file groups like the *update* command.
+/// The target is stored relative to the *docwen.toml* if it is inside its directory.
+/// Returns an error if the path is invalid or already exists or if target_dir is not a directory.
+pub fn create_from(path: impl AsRef<Path>, target_dir: impl AsRef<Path>) -> anyhow::Result<()>
+{
+    create_from_with(path, target_dir, &CreateOptions::default())
+}
+
+/// Like create_from, but with the given CreateOptions.
+pub fn create_from_with(path: impl AsRef<Path>, target_dir: impl AsRef<Path>, options: &CreateOptions) -> anyhow::Result<()>
+{
+    let target_dir = target_dir.as_ref();
+    if !target_dir.is_dir()
+    {
+        anyhow::bail!("Cannot initialize docwen.toml from {:?}: not a directory", target_dir.display());
+    }
+
+    let abs_target = normalize_path(std::path::absolute(target_dir)?);
+    let abs_toml_dir = normalize_path(std::path::absolute(path.as_ref())?
+        .parent()
+        .with_context(|| format!("Could not access parent of {:?}", path.as_ref()))?);
+    let target = match abs_target.strip_prefix(&abs_toml_dir)
+    {
+        Ok(relative) if relative.as_os_str().is_empty() => PathBuf::from("."),
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => abs_target.clone(),
+    };
+
+    let target_value = toml::Value::from(target.to_string_lossy().replace('\\', "/"));
+    let content = default_content(DEFAULT_TOML, options).replacen("target = \"src\"", &format!("target = {target_value}"), 1);
+    create_with_content(&path, &content)?;
+    update_toml(&path)
+}
+
+/// Creates a new file at the given path with the given content.
+/// Returns an error if the path is invalid or already exists.
+fn create_with_content(path: impl AsRef<Path>, content: &str) -> anyhow::Result<()>
 {
     let mut file = OpenOptions::new()
         .write(true)
@@ -28,66 +182,225 @@ pub fn create_default(path: impl AsRef<Path>) -> anyhow::Result<()>
         .with_context(||
             format!("Failed to create new docwen.toml at {:?}", path.as_ref().display()))?;
 
-    file.write_all(DEFAULT_TOML.as_bytes()).with_context(||
+    file.write_all(content.as_bytes()).with_context(||
         format!("Failed to write to docwen.toml at {:?}", path.as_ref().display()))?;
     Ok(())
 }
 
+/// Options that modify the behavior of 'docwen update'.
+#[derive(Debug, Default)]
+pub struct UpdateOptions
+{
+    /// Whether generated files (see c_parse::is_generated and the 'generated_markers' setting) are not added
+    pub ignore_generated: bool,
+
+    /// 'key=value' overrides of the settings used for the update (see Settings::apply_overrides).
+    /// They are not written to the docwen.toml
+    pub overrides: Vec<String>,
+}
+
 /// Implements the docwen *update* command.
 /// Parses the *docwen.toml* at the given path and updates it based on the
-/// settings it specifies.
+/// settings it specifies. A single file target becomes a group of its own.
 /// Returns an error if the file cannot be parsed or updated.
 pub fn update_toml(path: impl AsRef<Path>) -> anyhow::Result<()>
+{
+    update_toml_with(path, &UpdateOptions::default())
+}
+
+/// Like update_toml, but with the given UpdateOptions.
+pub fn update_toml_with(path: impl AsRef<Path>, options: &UpdateOptions) -> anyhow::Result<()>
+{
+    let (docfig, _) = plan_update(&path, options)?;
+    docfig.write_file(&path)
+}
+
+/// The changes 'docwen update' makes to the filegroups of a *docwen.toml*
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UpdateDelta
+{
+    pub added_groups: Vec<FileGroup>,
+    pub changed_groups: Vec<GroupChange>
+}
+
+/// The files 'docwen update' adds to or removes from an existing filegroup
+#[derive(Debug, PartialEq, Eq)]
+pub struct GroupChange
+{
+    pub name: String,
+    pub added_files: Vec<PathBuf>,
+    pub removed_files: Vec<PathBuf>
+}
+
+impl UpdateDelta
+{
+    /// Returns the changes from the given old to the given new filegroups (groups are matched by case-insensitive name).
+    pub fn between(old: &[FileGroup], new: &[FileGroup]) -> Self
+    {
+        let mut delta = UpdateDelta::default();
+        for group in new
+        {
+            let Some(previous) = old.iter().find(|g| g.name.eq_ignore_ascii_case(&group.name)) else {
+                delta.added_groups.push(group.clone());
+                continue;
+            };
+            let added_files: Vec<PathBuf> = group.files.iter().filter(|f| !previous.files.contains(f)).cloned().collect();
+            let removed_files: Vec<PathBuf> = previous.files.iter().filter(|f| !group.files.contains(f)).cloned().collect();
+            if !added_files.is_empty() || !removed_files.is_empty()
+            {
+                delta.changed_groups.push(GroupChange{name: group.name.clone(), added_files, removed_files});
+            }
+        }
+        delta
+    }
+
+    /// Returns whether the update changes nothing.
+    pub fn is_empty(&self) -> bool
+    {
+        self.added_groups.is_empty() && self.changed_groups.is_empty()
+    }
+
+    /// Returns the changes as diff lines, e.g. '+ filegroup 'b': "b.h", "b.c"' for an added group
+    /// and '~ filegroup 'a': +"a_impl.c" -"old.c"' for a changed one.
+    pub fn diff(&self) -> String
+    {
+        let quoted = |f: &PathBuf| format!("{:?}", f.to_string_lossy().replace('\\', "/"));
+        let mut out = String::new();
+        for g in &self.added_groups
+        {
+            let files: Vec<String> = g.files.iter().map(quoted).collect();
+            out.push_str(&format!("+ filegroup '{}': {}\n", g.name, files.join(", ")));
+        }
+        for c in &self.changed_groups
+        {
+            let files: Vec<String> = c.added_files.iter().map(|f| format!("+{}", quoted(f)))
+                .chain(c.removed_files.iter().map(|f| format!("-{}", quoted(f))))
+                .collect();
+            out.push_str(&format!("~ filegroup '{}': {}\n", c.name, files.join(" ")));
+        }
+        out
+    }
+
+    /// Returns the changes as a JSON object with the 'added_groups' (name and files) and the 'changed_groups'
+    /// (name, added and removed files).
+    pub fn to_json(&self) -> serde_json::Value
+    {
+        let paths = |files: &[PathBuf]| files.iter().map(|f| f.to_string_lossy().replace('\\', "/")).collect::<Vec<_>>();
+        serde_json::json!({
+            "added_groups": self.added_groups.iter()
+                .map(|g| serde_json::json!({"name": g.name, "files": paths(&g.files)}))
+                .collect::<Vec<_>>(),
+            "changed_groups": self.changed_groups.iter()
+                .map(|c| serde_json::json!({
+                    "name": c.name,
+                    "added_files": paths(&c.added_files),
+                    "removed_files": paths(&c.removed_files)
+                }))
+                .collect::<Vec<_>>()
+        })
+    }
+}
+
+/// Computes what 'docwen update' would write to the *docwen.toml* at the given path without writing it.
+/// Returns the updated Docfig and its changes to the filegroups.
+pub fn plan_update(path: impl AsRef<Path>, options: &UpdateOptions) -> anyhow::Result<(Docfig, UpdateDelta)>
 {
     let mut docfig = Docfig::from_file(&path)?;
+    let old_groups = docfig.file_groups.clone();
+    let settings = Docfig::from_file_with_overrides(&path, &options.overrides)?.settings;
+
+    // A single file target forms its own group, so its declarations and definitions are compared
+    let target = get_absolute_root(&path, &settings.target)?;
+    let root = existing_root(&path, &settings.target)?;
+    if target.is_file()
+    {
+        let file = target.strip_prefix(&root).map(Path::to_path_buf).unwrap_or(target.clone());
+        let name = file.file_stem().and_then(OsStr::to_str).unwrap_or_default().to_owned();
+        let group = FileGroup{name, files: vec![file]};
+        match docfig.file_groups.iter_mut().find(|x| x.name.eq_ignore_ascii_case(&group.name))
+        {
+            Some(slot) => *slot = group,
+            None => docfig.file_groups.push(group),
+        }
+        let delta = UpdateDelta::between(&old_groups, &docfig.file_groups);
+        return Ok((docfig, delta));
+    }
 
     // Get all file paths
-    let root = get_absolute_root(&path, &docfig.settings.target)?;
+    // Files reached through several symlinks are only grouped once
+    let mut seen = HashSet::new();
     let paths: Vec<PathBuf> = WalkDir::new(&root)
+        .follow_links(settings.follow_symlinks)
+        .sort_by_file_name()
         .into_iter()
         .filter_map(|entry| entry.ok())
         .filter(|e| e.file_type().is_file())
+        .filter(|e| seen.insert(fs::canonicalize(e.path()).unwrap_or_else(|_| e.path().to_path_buf())))
         .filter_map(|e|
             e.path()
                 .strip_prefix(&root) // as relative paths
                 .ok()
                 .map(Path::to_path_buf)
         )
+        .filter(|p| !docfig.is_linked(p)) // Linked files are grouped by their link
         .collect();
 
-    let mut groups: Vec<FileGroup> = group_by_stem(paths, &docfig.settings);
+    let mut groups: Vec<FileGroup> = group_files(paths, &settings)?;
+    if options.ignore_generated
+    {
+        let is_generated = |f: &PathBuf| fs::read_to_string(root.join(f))
+            .is_ok_and(|src| c_parse::is_generated(&src, &settings.generated_markers));
+        groups.iter_mut().for_each(|g| g.files.retain(|f| !is_generated(f)));
+    }
     groups.retain(|g| g.files.len() > 1);
 
     // Merge (overwrite existing with new versions but do not delete non-existing)
     for g in groups
     {
-        // Replace old group with new one (equals only considers name, so different file list
-        // gets updated)
-        if let Some(slot) = docfig.file_groups.iter_mut().find(|x| **x == g)
+        // Replace old group with new one (matched by case-insensitive name, so different file list
+        // and name casing get updated)
+        if let Some(slot) = docfig.file_groups.iter_mut().find(|x| x.name.eq_ignore_ascii_case(&g.name))
         {
             *slot = g;
         }
-
         else
         {
             docfig.file_groups.push(g);
         }
     }
-    docfig.write_file(&path)?;
-
-    Ok(())
+    let delta = UpdateDelta::between(&old_groups, &docfig.file_groups);
+    Ok((docfig, delta))
 }
 
-/// Groups all files defined by the given paths by matching name (stem)
-/// based on the given settings.
-pub fn group_by_stem<I>(paths: I, settings: &Settings) -> Vec<FileGroup>
+/// Groups all files defined by the given paths by their group key based on the given settings.
+/// The key is the name (stem) of the file or, if the 'group_regex' setting is given, the first capture
+/// group of the regex matched against the file name (files that do not match are not grouped).
+/// Keys listed in the 'aliases' setting are grouped under their canonical name instead.
+/// Keys listed in the 'manual' setting are not grouped (entries with glob metacharacters, e.g. 'test_*',
+/// are matched as globs).
+/// Returns an error if the 'group_regex' or a 'manual' glob is invalid.
+pub fn group_files<I>(paths: I, settings: &Settings) -> anyhow::Result<Vec<FileGroup>>
 where
     I: IntoIterator<Item = PathBuf>,
 {
+    let group_regex = settings.group_regex.as_deref()
+        .map(|r| Regex::new(r).with_context(|| format!("Invalid group_regex: {r}")))
+        .transpose()?;
+    let manual_globs = settings.manual.iter()
+        .filter(|m| is_glob(m))
+        .map(|m| glob::Pattern::new(m).with_context(|| format!("Invalid glob in manual: {m}")))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let glob_options = glob::MatchOptions{case_sensitive: false, ..Default::default()};
+    let is_manual = |stem: &str| settings.manual.iter().any(|m| m == stem)
+        || manual_globs.iter().any(|g| g.matches_with(stem, glob_options));
     let match_extensions: HashSet<String> =
         settings.match_extensions.clone().into_iter().map(|e| e.to_ascii_lowercase()).collect();
+    let aliases: HashMap<String, &str> = settings.aliases.iter()
+        .map(|(stem, group)| (stem.to_ascii_lowercase(), group.as_str()))
+        .collect();
 
-    let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    // Files are matched by their lowercased key, the group is named after the first file's casing of it
+    let mut groups: HashMap<String, (String, Vec<PathBuf>)> = HashMap::new();
     for path in paths
     {
         match path.extension().and_then(OsStr::to_str)
@@ -96,22 +409,52 @@ where
             _ => continue,
         };
 
-        let stem = match path.file_stem().and_then(OsStr::to_str)
+        let key = match &group_regex
         {
-            Some(s) => s.to_owned().to_ascii_lowercase(),
-            None => continue,
+            Some(regex) => path.file_name()
+                .and_then(OsStr::to_str)
+                .and_then(|name| regex.captures(name))
+                .and_then(|c| c.get(1))
+                .map(|key| key.as_str()),
+            None => path.file_stem().and_then(OsStr::to_str),
         };
+        let Some(name) = key else { continue; };
+
+        // Aliased stems join their canonical group
+        let name = aliases.get(&name.to_ascii_lowercase()).copied().unwrap_or(name).to_owned();
+        let stem = name.to_ascii_lowercase();
 
-        if !settings.manual.contains(&stem)
+        if !is_manual(&stem)
         {
-            groups.entry(stem).or_default().push(path);
+            groups.entry(stem).or_insert_with(|| (name, Vec::new())).1.push(path);
         }
     }
 
-    groups
-        .into_iter()
+    Ok(groups
+        .into_values()
         .map(|(name, files)| { FileGroup { name, files } })
-        .collect()
+        .collect())
+}
+
+/// Returns whether the given entry (e.g. of the 'manual' setting or a filegroup) contains glob metacharacters ('*', '?' or '[').
+pub fn is_glob(entry: &str) -> bool
+{
+    entry.contains(['*', '?', '['])
+}
+
+/// Returns the path of the nearest *docwen.toml* in the given start directory or any of its ancestors
+/// (like git finds its repository).
+/// Returns an error if there is none up to the file system root.
+pub fn find_toml(start_dir: impl AsRef<Path>) -> anyhow::Result<PathBuf>
+{
+    let start_dir = std::path::absolute(start_dir.as_ref())
+        .with_context(|| format!("Failed to resolve {}", start_dir.as_ref().display()))?;
+
+    start_dir.ancestors()
+        .map(|dir| dir.join("docwen.toml"))
+        .find(|path| path.is_file())
+        .with_context(|| format!("No docwen.toml found in {} or any of its parent directories",
+                                 start_dir.display()))
 }
 
 /// Returns the absolute root target path defined by the given toml_path and the
@@ -125,4 +468,47 @@ pub fn get_absolute_root(toml_path: impl AsRef<Path>, target: impl AsRef<Path>)
             .join(target.as_ref())
     };
     Ok(path)
+}
+
+/// Like get_absolute_root, but returns an error if the root does not exist.
+/// If the target is a single file, its parent directory is returned (the files of the groups are relative to it).
+pub fn existing_root(toml_path: impl AsRef<Path>, target: impl AsRef<Path>) -> anyhow::Result<PathBuf>
+{
+    let root = get_absolute_root(toml_path, target)?;
+    if root.is_file()
+    {
+        return root.parent().map(Path::to_path_buf)
+            .with_context(|| format!("Could not access parent of {}", root.display()));
+    }
+    if !root.is_dir()
+    {
+        anyhow::bail!("Target root {} does not exist (see 'target' in the docwen.toml)", root.display());
+    }
+    Ok(root)
+}
+
+/// Lexically normalizes the given path by removing '.' components and resolving '..'
+/// components against their preceding component where possible.
+/// Does not access the file system (no symlink resolution).
+pub fn normalize_path(path: impl AsRef<Path>) -> PathBuf
+{
+    let mut normalized = PathBuf::new();
+    for component in path.as_ref().components()
+    {
+        match component
+        {
+            Component::CurDir => {},
+            Component::ParentDir =>
+                {
+                    match normalized.components().next_back()
+                    {
+                        Some(Component::Normal(_)) => { normalized.pop(); },
+                        Some(Component::RootDir) | Some(Component::Prefix(_)) => {},
+                        _ => normalized.push(".."),
+                    }
+                },
+            c => normalized.push(c),
+        }
+    }
+    normalized
 }
\ No newline at end of file
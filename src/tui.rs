@@ -0,0 +1,196 @@
+//! Handles 'docwen tui': an interactive, read-only view of the results of 'docwen check'
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+use crate::docwen_check::{CheckReport, GroupSummary, Mismatch};
+use crate::output::relative_path;
+
+/// Key bindings shown in the footer
+const HELP: &str = "n/j/↓: next mismatch  p/k/↑: previous  ]/[: next/previous group  g/G: first/last  q/Esc: quit";
+
+/// The doc block of one position of a mismatch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocPane
+{
+    pub title: String, // Path (relative to the target) and 1-based row of the function
+    pub lines: Vec<String>, // Doc lines followed by the function line
+    pub highlight: Option<usize> // Index of the mismatching doc line in 'lines'
+}
+
+/// State of the TUI: the check results and the selected mismatch
+pub struct App
+{
+    pub mismatches: Vec<Mismatch>,
+    pub groups: Vec<GroupSummary>,
+    pub selected: usize, // Index of the selected mismatch
+    panes: Vec<Vec<DocPane>>, // Doc panes of each mismatch
+    group_starts: Vec<usize> // Index of the first mismatch of each group
+}
+
+impl App
+{
+    /// Creates the App for the given report, reading the doc blocks of all mismatches from their files.
+    /// Files that cannot be read are shown as empty panes.
+    pub fn new(report: CheckReport) -> Self
+    {
+        let mut sources: HashMap<PathBuf, Vec<String>> = HashMap::new();
+        let panes = report.mismatches.iter()
+            .map(|m| m.positions.iter().enumerate().map(|(i, pos)| {
+                let lines = sources.entry(pos.path.clone()).or_insert_with(|| {
+                    fs::read_to_string(&pos.path).unwrap_or_default().lines().map(str::to_string).collect()
+                });
+                let (first, last) = m.doc_spans[i].unwrap_or((pos.row, pos.row.saturating_sub(1)));
+                let pane_lines: Vec<String> = (first..=last.max(pos.row))
+                    .filter_map(|row| lines.get(row).cloned())
+                    .collect();
+                let doc_row = m.doc_rows[i];
+                DocPane{
+                    title: format!("{}:{}", relative_path(&pos.path, &report.display_base), pos.row + 1),
+                    lines: pane_lines,
+                    highlight: (m.doc_spans[i].is_some() && doc_row >= first && doc_row <= last).then(|| doc_row - first)
+                }
+            }).collect())
+            .collect();
+
+        // Mismatches are reported group by group
+        let mut group_starts = Vec::with_capacity(report.groups.len());
+        let mut start = 0;
+        for g in &report.groups
+        {
+            group_starts.push(start);
+            start += g.mismatches;
+        }
+        App{mismatches: report.mismatches, groups: report.groups, selected: 0, panes, group_starts}
+    }
+
+    /// Returns the doc panes of the selected mismatch (one per position).
+    pub fn selected_panes(&self) -> &[DocPane]
+    {
+        self.panes.get(self.selected).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Returns the index of the group of the selected mismatch.
+    pub fn selected_group(&self) -> Option<usize>
+    {
+        if self.mismatches.is_empty() { return None; }
+        (0..self.groups.len()).rev()
+            .find(|g| self.group_starts[*g] <= self.selected && self.groups[*g].mismatches > 0)
+    }
+
+    /// Applies the given key. Returns false if the TUI should quit.
+    pub fn handle_key(&mut self, key: KeyCode) -> bool
+    {
+        let last = self.mismatches.len().saturating_sub(1);
+        match key
+        {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('n') | KeyCode::Char('j') | KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::Char('p') | KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('g') | KeyCode::Home => self.selected = 0,
+            KeyCode::Char('G') | KeyCode::End => self.selected = last,
+            KeyCode::Char(']') =>
+                {
+                    let next = self.group_starts.iter().zip(&self.groups)
+                        .find(|(start, g)| **start > self.selected && g.mismatches > 0);
+                    if let Some((start, _)) = next { self.selected = *start; }
+                },
+            KeyCode::Char('[') =>
+                {
+                    let current = self.selected_group().map_or(0, |g| self.group_starts[g]);
+                    let previous = self.group_starts.iter().zip(&self.groups)
+                        .rfind(|(start, g)| **start < current && g.mismatches > 0);
+                    self.selected = previous.map_or(current, |(start, _)| *start);
+                },
+            _ => {},
+        }
+        true
+    }
+
+    /// Draws the groups, the mismatches and the doc blocks of the selected mismatch side by side.
+    pub fn draw(&self, frame: &mut Frame)
+    {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Min(3), Constraint::Length(1)])
+            .split(frame.area());
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .split(rows[0]);
+        let highlighted = Style::default().add_modifier(Modifier::REVERSED);
+
+        // GROUPS
+        let groups: Vec<ListItem> = self.groups.iter()
+            .map(|g| {
+                let style = if g.mismatches == 0 { Style::default().fg(Color::Green) } else { Style::default().fg(Color::Red) };
+                ListItem::new(Line::from(vec![Span::raw(g.name.clone()), Span::styled(format!(" ({})", g.mismatches), style)]))
+            })
+            .collect();
+        let mut group_state = ListState::default().with_selected(self.selected_group());
+        frame.render_stateful_widget(List::new(groups).highlight_style(highlighted)
+                                         .block(Block::default().borders(Borders::ALL).title("Groups")),
+                                     top[0], &mut group_state);
+
+        // MISMATCHES
+        let mismatches: Vec<ListItem> = self.mismatches.iter()
+            .map(|m| ListItem::new(format!("[{}] {}{}: {}", m.kind.as_str(), m.function.name, m.function.params, m.line)))
+            .collect();
+        let title = format!("Mismatches ({})", self.mismatches.len());
+        let mut mismatch_state = ListState::default().with_selected((!self.mismatches.is_empty()).then_some(self.selected));
+        frame.render_stateful_widget(List::new(mismatches).highlight_style(highlighted)
+                                         .block(Block::default().borders(Borders::ALL).title(title)),
+                                     top[1], &mut mismatch_state);
+
+        // DOC BLOCKS SIDE BY SIDE
+        let panes = self.selected_panes();
+        if panes.is_empty()
+        {
+            frame.render_widget(Paragraph::new("Found no mismatches!").block(Block::default().borders(Borders::ALL)), rows[1]);
+        }
+        else
+        {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Ratio(1, panes.len() as u32); panes.len()])
+                .split(rows[1]);
+            for (pane, area) in panes.iter().zip(columns.iter())
+            {
+                let lines: Vec<Line> = pane.lines.iter().enumerate()
+                    .map(|(i, l)| {
+                        let style = if Some(i) == pane.highlight { Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD) }
+                                    else { Style::default() };
+                        Line::styled(l.clone(), style)
+                    })
+                    .collect();
+                frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(pane.title.clone())), *area);
+            }
+        }
+
+        frame.render_widget(Paragraph::new(HELP).style(Style::default().fg(Color::DarkGray)), rows[2]);
+    }
+}
+
+/// Runs the TUI for the given App in the terminal until the user quits.
+pub fn run(mut app: App) -> anyhow::Result<()>
+{
+    let mut terminal = ratatui::init();
+    let result = (|| -> anyhow::Result<()> {
+        loop
+        {
+            terminal.draw(|frame| app.draw(frame))?;
+            if let Event::Key(key) = event::read()? && key.kind == KeyEventKind::Press && !app.handle_key(key.code)
+            {
+                return Ok(());
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,62 @@
+//! Handles watching the files of a *docwen.toml* for changes (for 'check --watch')
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+use crate::cache::CACHE_FILE_NAME;
+use crate::docfig::Docfig;
+use crate::toml_manager;
+
+/// Interval in which the watched files are polled for changes
+pub const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// ANSI sequence that clears the terminal and moves the cursor to its top left corner
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Modification times of all watched files
+pub type Snapshot = HashMap<PathBuf, SystemTime>;
+
+/// Returns the modification times of the *docwen.toml* at the given path and of all files of its target.
+/// Files that cannot be read are left out, so an invalid *docwen.toml* is still watched.
+pub fn snapshot(toml_path: &Path) -> Snapshot
+{
+    let mut files = vec![toml_path.to_path_buf()];
+    let root = Docfig::from_file(toml_path).ok()
+        .and_then(|d| toml_manager::existing_root(toml_path, &d.settings.target).ok());
+    if let Some(root) = root
+    {
+        files.extend(WalkDir::new(root).into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path()));
+    }
+
+    // The cache of 'check --cache' is written by the check itself
+    files.into_iter()
+        .filter(|f| f.file_name().is_none_or(|n| n != CACHE_FILE_NAME))
+        .filter_map(|f| fs::metadata(&f).and_then(|m| m.modified()).ok().map(|t| (f, t)))
+        .collect()
+}
+
+/// Blocks until a file of the given snapshot is changed or removed or a new file is added to the target
+/// of the *docwen.toml* at the given path. Returns the new snapshot.
+pub fn wait_for_change(toml_path: &Path, previous: &Snapshot) -> Snapshot
+{
+    loop
+    {
+        thread::sleep(POLL_INTERVAL);
+        let current = snapshot(toml_path);
+        if current != *previous { return current; }
+    }
+}
+
+/// Returns the header printed above each run of 'check --watch' with the given time (UTC), e.g.
+/// '=== docwen check at 14:03:59 UTC ==='.
+pub fn run_header(time: SystemTime) -> String
+{
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) % 86400;
+    format!("=== docwen check at {:02}:{:02}:{:02} UTC ===", secs / 3600, secs / 60 % 60, secs % 60)
+}
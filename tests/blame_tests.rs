@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod blame_tests
+{
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::tempdir;
+    use docwen::blame::last_author;
+    use docwen::docfig::Docfig;
+    use docwen::docwen_check::{self, CheckOptions};
+    use docwen::output::report_lines_with_blame;
+
+    /// Runs git with the given args in the given directory and asserts that it succeeds.
+    fn git(dir: &Path, args: &[&str])
+    {
+        let status = Command::new("git").arg("-C").arg(dir)
+            .args(["-c", "user.name=Jane Doe", "-c", "user.email=jane@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn blame_shows_author_of_mismatching_doc_lines()
+    {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.h"), "// Doc A\nint foo();\n").unwrap();
+        fs::write(dir.path().join("a.c"), "// Doc B\nint foo() {}\n").unwrap();
+        fs::write(dir.path().join("docwen.toml"),
+                  "[settings]\ntarget = \".\"\n\n[[filegroup]]\nname = \"a\"\nfiles = [\"a.h\", \"a.c\"]\n").unwrap();
+        git(dir.path(), &["init", "-q"]);
+        git(dir.path(), &["add", "-A"]);
+        git(dir.path(), &["commit", "-q", "-m", "Add a"]);
+
+        assert_eq!(last_author(&dir.path().join("a.h"), 0).as_deref(), Some("Jane Doe"));
+
+        let toml = dir.path().join("docwen.toml");
+        let report = docwen_check::check_with_options(&toml, &CheckOptions::default()).unwrap();
+        let settings = Docfig::from_file(&toml).unwrap().settings;
+        let lines = report_lines_with_blame(&report, &settings);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].matches("last changed by Jane Doe").count(), 2, "Output was: {}", lines[0]);
+    }
+
+    #[test]
+    fn blame_is_omitted_outside_of_git_repositories()
+    {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.h"), "// Doc A\nint foo();\n").unwrap();
+        assert_eq!(last_author(&dir.path().join("a.h"), 0), None);
+    }
+}
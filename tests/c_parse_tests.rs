@@ -2,11 +2,12 @@
 mod c_parse_tests
 {
     use std::{fs, io::Write};
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use tempfile::tempdir;
     use tree_sitter::{Node, Parser, Tree};
-    use docwen::c_parse::{find_declarator, find_function_positions, get_function_id, get_name_and_params, has_definition_ancestor, mask_preprocessor, visit_all_nodes};
-    use docwen::docwen_check::FunctionID;
+    use docwen::c_parse::{Language, normalize_name, normalize_params, param_count, mask_inactive_branches, evaluate_condition, MarkdownExtractor, canonicalize_builtin_types, find_function_positions_with, CppExtractor, ExtractOptions, Extractor, ExtractorRegistry};
+    use docwen::c_parse::{find_unterminated_comment, find_declarator, find_function_positions, find_function_positions_with_sources, get_function_id, get_name_and_params, has_definition_ancestor, mask_preprocessor, visit_all_nodes};
+    use docwen::docwen_check::{DocRules, FunctionID, LineSource};
     use once_cell::sync::Lazy;
     use rand::{distr::Alphanumeric, Rng};
 
@@ -35,7 +36,7 @@ mod c_parse_tests
     }
 
     /// Finds and returns the first function_declarator in the given tree.
-    fn first_decl(tree: &Tree) -> Node
+    fn first_decl(tree: &Tree) -> Node<'_>
     {
         let mut stack = vec![tree.root_node()];
         while let Some(n) = stack.pop()
@@ -85,6 +86,14 @@ mod c_parse_tests
         assert!(get_function_id(first_decl(&tree), &masked, true).is_some());
     }
 
+    #[test]
+    fn crlf_macro_continuations_are_masked()
+    {
+        const SRC: &str = "#define SWAP(a, b) \\\r\n    do { int t = a; a = b; b = t; } while (0)\r\nvoid foo();\r\n";
+        let masked = mask_preprocessor(SRC);
+        assert_eq!(masked, format!("{}\r\n{}\r\nvoid foo();\r\n", " ".repeat(20), " ".repeat(45)));
+    }
+
     #[test]
     fn tab_indented_macro_masked()
     {
@@ -194,6 +203,24 @@ mod c_parse_tests
         assert!(map.is_empty(), "Map should be empty, got {map:?}");
     }
 
+    #[test]
+    fn same_name_different_qualifiers_not_considered_duplicates()
+    {
+        let tmp = tempdir().unwrap();
+        let p1 = write(&tmp, "a.hpp", "struct A { void f(); int g() &; };");
+        let p2 = write(&tmp, "a.cpp", "void A::f() const {}\nint A::g() && { return 0; }\n");
+        let map = find_function_positions([p1, p2], true).unwrap();
+        assert!(map.is_empty(), "Map should be empty, got {map:?}");
+
+        let tmp = tempdir().unwrap();
+        let p1 = write(&tmp, "a.hpp", "struct A { void f() const volatile; int g() const &&; };");
+        let p2 = write(&tmp, "a.cpp", "void A::f() const volatile {}\nint A::g() const && { return 0; }\n");
+        let map = find_function_positions([p1, p2], true).unwrap();
+        assert_eq!(map.len(), 2, "Got {map:?}");
+        assert!(map.contains_key(&FunctionID { name: "A::f".into(), params: "() const volatile".into() }));
+        assert!(map.contains_key(&FunctionID { name: "A::g".into(), params: "() const &&".into() }));
+    }
+
     #[test]
     fn declaration_and_definition_flagged_as_duplicate()
     {
@@ -232,7 +259,7 @@ mod c_parse_tests
         let id = get_function_id(decl, CODE, true).unwrap();
 
         assert_eq!(id.name, "util::A::bar");
-        assert_eq!(compact(&id.params), "()");
+        assert_eq!(compact(&id.params), "()const");
     }
 
     #[test]
@@ -266,6 +293,25 @@ mod c_parse_tests
         assert!(find_declarator(tree.root_node()).is_some());
     }
 
+    #[test]
+    fn function_returning_function_pointer_keeps_its_name_and_params()
+    {
+        const CODE: &str = "void (*get_handler(int))(int);";
+        let tree = parse_tree(CODE);
+        let decl = find_declarator(tree.root_node()).unwrap();
+        let (name, params) = get_name_and_params(decl, CODE);
+        assert_eq!(name.as_deref(), Some("get_handler"));
+        assert_eq!(params.as_deref(), Some("(int)"));
+
+        let tmp = tempdir().unwrap();
+        let h = write(&tmp, "a.h", CODE);
+        let c = write(&tmp, "a.c", "void (*get_handler(int))(int)\n{\n    return 0;\n}\n");
+        let map = find_function_positions([h, c], true).unwrap();
+        assert_eq!(map.len(), 1);
+        let id = FunctionID{ name: "get_handler".into(), params: "(int)".into() };
+        assert_eq!(map[&id].len(), 2);
+    }
+
     #[test]
     fn duplicate_functions_across_files_are_detected()
     {
@@ -483,9 +529,21 @@ mod c_parse_tests
         "#;
         let tree = parse_tree(SRC);
         let id = get_function_id(first_decl(&tree), SRC, true).unwrap();
-        assert_eq!(id.name, "W::friend_fn");
+        assert_eq!(id.name, "friend_fn"); // Not a member of W
         assert_eq!(compact(&id.params), "(W&)");
     }
+
+    #[test]
+    fn friend_declaration_pairs_with_namespace_definition()
+    {
+        let tmp = tempdir().unwrap();
+        let p1 = write(&tmp, "w.h", "namespace n {\n struct W {\n  friend void friend_fn(W& w);\n  friend bool operator==(const W&, const W&) { return true; }\n };\n}\n");
+        let p2 = write(&tmp, "w.cpp", "namespace n {\n void friend_fn(W& w) {}\n}\n");
+
+        let map = find_function_positions([p1, p2], true).unwrap();
+        let fid = FunctionID { name: "n::friend_fn".into(), params: "(W &w)".into() };
+        assert_eq!(map.get(&fid).map(Vec::len), Some(2), "Map: {map:?}");
+    }
     
     #[test]
     fn user_defined_literal_operator()
@@ -553,4 +611,321 @@ mod c_parse_tests
         };
         assert_eq!(map[&fid].len(), 4);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn asm_label_matches_header_declaration()
+    {
+        let tmp = tempdir().unwrap();
+        let h = write(&tmp, "stub.h", "// Adds two ints\nint add(int a, int b);\n");
+        let s = write(&tmp, "stub.s", "\t.text\n\t.globl add\n// Adds two ints\nadd:\n\tret\n.L1:\n");
+
+        let map = find_function_positions([h, s], true).unwrap();
+        assert_eq!(map.len(), 1, "Map was {map:?}");
+        let fid = FunctionID {
+            name: "add".into(),
+            params: "(int a, int b)".into(),
+        };
+        let positions = &map[&fid];
+        assert_eq!(positions.len(), 2);
+        assert!(positions.iter().any(|p| p.path.ends_with("stub.s") && p.row == 3));
+    }
+
+    #[test]
+    fn max_scope_depth_skips_deeply_nested_methods()
+    {
+        const SRC: &str = r#"
+            void top();
+            class A {
+                void member();
+                class B {
+                    class C {
+                        void nested();
+                    };
+                };
+            };
+            void A::B::C::nested() {}
+        "#;
+
+        let options = ExtractOptions { use_qualifiers: true, max_scope_depth: Some(1), ..Default::default() };
+        let mut extractor = CppExtractor::with_options(options).unwrap();
+        let names: Vec<String> = extractor.extract(SRC, Path::new("a.cpp")).unwrap()
+            .into_iter()
+            .map(|(id, _)| id.name)
+            .collect();
+        assert_eq!(names, vec!["top", "A::member"]);
+
+        let mut unlimited = CppExtractor::new(true).unwrap();
+        assert_eq!(unlimited.extract(SRC, Path::new("a.cpp")).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn parse_file_functions_from_crate_root()
+    {
+        let tmp = tempdir().unwrap();
+        let path = write(&tmp, "a.cpp", "namespace ns { int f(int a); }\nint g() { return 0; }\n");
+
+        let qualified = docwen::parse_file_functions(&path, true).unwrap();
+        let ids: Vec<&docwen::FunctionID> = qualified.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![
+            &docwen::FunctionID{name: "ns::f".into(), params: "(int a)".into()},
+            &docwen::FunctionID{name: "g".into(), params: "()".into()},
+        ]);
+        assert!(!qualified[0].1.is_definition);
+        assert_eq!((qualified[1].1.row, qualified[1].1.is_definition), (1, true));
+
+        let unqualified = docwen::parse_file_functions(&path, false).unwrap();
+        assert_eq!(unqualified[0].0.name, "f");
+    }
+
+    #[test]
+    fn multi_line_commented_params_match_compact_definition()
+    {
+        let tmp = tempdir().unwrap();
+        let p1 = write(&tmp, "a.h", "int f(\n    int a, // first\n    char *b /* second */\n);\n");
+        let p2 = write(&tmp, "a.c", "int f(int a, char *b) { return a; }\n");
+
+        let map = find_function_positions([p1, p2], true).unwrap();
+        let fid = FunctionID { name: "f".into(), params: "(int a, char *b)".into() };
+        assert_eq!(map.get(&fid).map(Vec::len), Some(2), "Map: {map:?}");
+    }
+
+    #[test]
+    fn canonicalizes_builtin_type_spellings()
+    {
+        assert_eq!(canonicalize_builtin_types("(unsigned a, long int b)"), "(unsigned int a, long b)");
+        assert_eq!(canonicalize_builtin_types("(signed, long unsigned int *p, short int)"), "(int, unsigned long *p, short)");
+        assert_eq!(canonicalize_builtin_types("(unsigned long long int, long double d)"), "(unsigned long long, long double d)");
+        assert_eq!(canonicalize_builtin_types("(signed char c, const unsigned char *s)"), "(signed char c, const unsigned char *s)");
+        assert_eq!(canonicalize_builtin_types("(integer x, int_t y)"), "(integer x, int_t y)");
+    }
+
+    #[test]
+    fn equivalent_builtin_type_spellings_group_when_enabled()
+    {
+        let tmp = tempdir().unwrap();
+        let p1 = write(&tmp, "a.h", "unsigned f(long, unsigned b);\n");
+        let p2 = write(&tmp, "a.c", "unsigned int f(long int, unsigned int b) { return 0; }\n");
+        let sources = [fs::read_to_string(&p1).unwrap(), fs::read_to_string(&p2).unwrap()];
+        let run = |canonicalize_builtin_types: bool| {
+            let options = ExtractOptions { use_qualifiers: true, canonicalize_builtin_types, ..Default::default() };
+            let mut registry = ExtractorRegistry::with_options(&options).unwrap();
+            find_function_positions_with([(p1.as_path(), sources[0].as_str()), (p2.as_path(), sources[1].as_str())],
+                                         &mut registry, None).unwrap()
+        };
+
+        assert!(run(false).is_empty());
+        let map = run(true);
+        let fid = FunctionID { name: "f".into(), params: "(long, unsigned int b)".into() };
+        assert_eq!(map.get(&fid).map(Vec::len), Some(2), "Map: {map:?}");
+    }
+
+    #[test]
+    fn specifiers_do_not_affect_grouping()
+    {
+        let cases = [
+            ("inline int f();", "int f() {}", "f", "()"),
+            ("static int f(int a);", "int f(int a) {}", "f", "(int a)"),
+            ("constexpr int f();", "inline constexpr int f() { return 1; }", "f", "()"),
+            ("class C\n{\n    virtual void v(int x);\n};", "void C::v(int x) {}", "C::v", "(int x)"),
+            ("class C\n{\n    explicit C(int x);\n};", "C::C(int x) {}", "C::C", "(int x)"),
+        ];
+
+        for (decl, def, name, params) in cases
+        {
+            let tmp = tempdir().unwrap();
+            let p1 = write(&tmp, "a.hpp", decl);
+            let p2 = write(&tmp, "a.cpp", def);
+            let map = find_function_positions([p1, p2], true).unwrap();
+
+            let fid = FunctionID { name: name.into(), params: params.into() };
+            assert_eq!(map.get(&fid).map(Vec::len), Some(2), "'{decl}' and '{def}' should group, got {map:?}");
+        }
+    }
+
+    #[test]
+    fn finds_unterminated_block_comment()
+    {
+        assert_eq!(find_unterminated_comment("int a();\n  /* open\nint foo();\n"), Some((1, 2)));
+        assert_eq!(find_unterminated_comment("/* a */ int b; /** c\n */\n"), None);
+        assert_eq!(find_unterminated_comment("const char* s = \"/*\"; // /*\nchar c = '\"'; int n = 1'000; /* x */\n"), None);
+        assert_eq!(find_unterminated_comment("#include \"a/*.h\"\nint a();\n"), None);
+    }
+
+    #[test]
+    fn markdown_extractor_finds_function_headers()
+    {
+        let src = "# API reference\n\n## `ns::add(int a, int b)`\ntext\n  ### sub ###\n```\n### in_code\n```\n### Not a function\n";
+        let functions = MarkdownExtractor.extract(src, Path::new("foo.md")).unwrap();
+        let found: Vec<(&str, usize, usize)> = functions.iter()
+            .map(|(id, pos)| (id.name.as_str(), pos.row, pos.column))
+            .collect();
+        assert_eq!(found, vec![("ns::add", 2, 0), ("sub", 4, 2)]);
+        assert!(functions.iter().all(|(id, _)| id.params.is_empty()));
+    }
+
+    #[test]
+    fn operator_spacing_does_not_affect_grouping()
+    {
+        let tmp = tempdir().unwrap();
+        let p1 = write(&tmp, "a.hpp", "struct T\n{\n    bool operator ==(const T&);\n    int operator [] (int i);\n};");
+        let p2 = write(&tmp, "a.cpp", "bool T::operator==(const T&) {}\nint T :: operator[](int i) {}");
+        let map = find_function_positions([p1, p2], true).unwrap();
+
+        for name in ["T::operator==", "T::operator[]"]
+        {
+            let positions = map.iter().find(|(id, _)| id.name == name).map(|(_, v)| v.len());
+            assert_eq!(positions, Some(2), "'{name}' should group, got {map:?}");
+        }
+    }
+
+    #[test]
+    fn normalize_name_keeps_spaces_between_words()
+    {
+        assert_eq!(normalize_name("operator  new [ ]"), "operator new[]");
+        assert_eq!(normalize_name("ns :: A :: operator <<"), "ns::A::operator<<");
+        assert_eq!(normalize_name("operator delete"), "operator delete");
+    }
+
+    #[test]
+    fn normalize_params_uses_canonical_spacing()
+    {
+        assert_eq!(normalize_params("(int  x)"), "(int x)");
+        assert_eq!(normalize_params("( const  int a ,unsigned long\n b )"), "(const int a, unsigned long b)");
+        for pointer in ["(int *p)", "(int* p)", "(int * p)", "(int*p)"]
+        {
+            assert_eq!(normalize_params(pointer), "(int *p)", "{pointer}");
+        }
+        assert_eq!(normalize_params("(const std::vector<int>& v, char ** argv, T&& t)"), "(const std::vector<int> &v, char **argv, T &&t)");
+        assert_eq!(normalize_params("(char* const p, void (* cb)(int,char))"), "(char *const p, void(*cb)(int, char))");
+        assert_eq!(normalize_params("(int x=0, /* flags */ int y = 1 // trailing\n)"), "(int x = 0, int y = 1)");
+        assert_eq!(normalize_params("( )const&"), "() const &");
+    }
+
+    #[test]
+    fn inactive_platform_branches_are_masked()
+    {
+        let src = "#if defined(_WIN32)\n// Opens a handle\nint open_file(HANDLE h);\n#elif defined(__linux__) && !defined(NO_FD)\n// Opens a descriptor\nint open_file(int fd);\n#else\nint open_file(FILE *f);\n#endif\n";
+        let defines = vec!["__linux__".to_string()];
+        let masked = mask_inactive_branches(src, &defines);
+        let lines: Vec<&str> = masked.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert!(lines[1].trim().is_empty() && lines[2].trim().is_empty() && lines[7].trim().is_empty());
+        assert_eq!(lines[5], "int open_file(int fd);");
+        assert_eq!(lines[2].len(), "int open_file(HANDLE h);".len());
+
+        // Unknown conditions keep all branches
+        let src = "#if VERSION > 2\nint a();\n#else\nint b();\n#endif\n";
+        assert_eq!(mask_inactive_branches(src, &defines), src);
+
+        // Comments after the name of '#ifdef'/'#ifndef' are ignored
+        let src = "#ifdef __linux__ // linux\nint a();\n#endif\n#ifndef __linux__ /* others */\nint b();\n#endif\n";
+        let masked = mask_inactive_branches(src, &defines);
+        let lines: Vec<&str> = masked.lines().collect();
+        assert_eq!((lines[1], lines[4].trim()), ("int a();", ""));
+
+        assert_eq!(evaluate_condition("defined FOO || (BAR && !defined(BAZ)) // note", &["BAR".into()]), Some(true));
+        assert_eq!(evaluate_condition("0", &[]), Some(false));
+        assert_eq!(evaluate_condition("defined(FOO", &[]), None);
+    }
+
+    #[test]
+    fn struct_extraction_skips_forward_declarations()
+    {
+        let src = "struct Vec;\n// A vector\nstruct Vec { int x; };\nvoid len(struct Vec *v);\nnamespace ns {\n// Outer\nclass Outer {\n    // Inner\n    union Inner { int i; };\n};\n}\n// Pair\ntemplate<class T>\nstruct Pair { T a; };\ntypedef struct { int r; } Color;\nstruct { int unnamed; } instance;\n";
+        let options = ExtractOptions { use_qualifiers: true, structs: true, ..Default::default() };
+        let mut extractor = CppExtractor::with_options(options).unwrap();
+        let structs: Vec<(String, usize)> = extractor.extract(src, Path::new("a.h")).unwrap().into_iter()
+            .map(|(id, pos)| (format!("{}{}", id.name, id.params), pos.row))
+            .collect();
+
+        assert_eq!(structs, vec![("Vec".to_string(), 2), ("ns::Outer".to_string(), 6), ("ns::Outer::Inner".to_string(), 8),
+                                 ("Pair".to_string(), 12), ("Color".to_string(), 14)]);
+    }
+
+    #[test]
+    fn enum_extraction_includes_enumerators()
+    {
+        let options = ExtractOptions { use_qualifiers: true, enums: true, ..Default::default() };
+        let extract = |src: &str, language: Language| -> Vec<(String, usize)> {
+            CppExtractor::with_language(options.clone(), language).unwrap()
+                .extract(src, Path::new("a.h")).unwrap().into_iter()
+                .map(|(id, pos)| (format!("{}{}", id.name, id.params), pos.row))
+                .collect()
+        };
+
+        let cpp = "enum Fwd : int;\nnamespace gfx {\n// A color\nenum class Color : unsigned char\n{\n    Red = 1,\n    Green, // Implicitly 2\n};\n}\n";
+        assert_eq!(extract(cpp, Language::Cpp), vec![("gfx::Color".to_string(), 3), ("gfx::Color::Red".to_string(), 5),
+                                                     ("gfx::Color::Green".to_string(), 6)]);
+
+        let c = "typedef enum\n{\n    LOW,\n    HIGH\n} Level;\nenum Mode { ON, OFF };\nvoid set(enum Mode m);\n";
+        assert_eq!(extract(c, Language::C), vec![("Level".to_string(), 0), ("Level::LOW".to_string(), 2), ("Level::HIGH".to_string(), 3),
+                                                 ("Mode".to_string(), 5), ("Mode::ON".to_string(), 5), ("Mode::OFF".to_string(), 5)]);
+    }
+
+    #[test]
+    fn docs_are_read_from_the_source_the_positions_refer_to()
+    {
+        let dir = tempdir().unwrap();
+        let h = dir.path().join("a.h");
+        let c = dir.path().join("a.c");
+        fs::write(&h, "#include <stdio.h>\n#define TWICE(x) \\\n    ((x) * 2)\n// Adds a and b\nint add(int a, int b);\n").unwrap();
+        fs::write(&c, "#include \"a.h\"\r\n// Adds a and b\r\nint add(int a, int b) { return a + b; }\r\n").unwrap();
+
+        let (positions, sources) = find_function_positions_with_sources([h.clone(), c.clone()], true).unwrap();
+        let add = &positions[&FunctionID { name: "add".into(), params: "(int a, int b)".into() }];
+        assert_eq!(add.iter().map(|p| p.row).collect::<Vec<_>>(), vec![4, 2]);
+        for pos in add
+        {
+            let line_source = LineSource { src: sources[&pos.path].clone(), init_row: pos.row };
+            let block = line_source.doc_block(&DocRules::default());
+            assert_eq!(block.line(0), "// Adds a and b", "{}", pos.path.display());
+            assert_eq!(block.offset(0), -1);
+        }
+    }
+
+    #[test]
+    fn param_count_ignores_nested_commas()
+    {
+        assert_eq!(param_count("()"), 0);
+        assert_eq!(param_count("(void)"), 0);
+        assert_eq!(param_count("(int a)"), 1);
+        assert_eq!(param_count("(int a) const &&"), 1);
+        assert_eq!(param_count("(std::map<int, int> m,void (*cb)(int, char),int n[2])"), 3);
+    }
+
+    #[test]
+    fn extractor_parses_files_as_assumed_language()
+    {
+        let ipp = "template<typename T>\nT Box<T>::get(int index) const\n{\n    return items[index];\n}\n";
+        let mut cpp = CppExtractor::with_language(ExtractOptions { use_qualifiers: true, ..Default::default() }, Language::Cpp).unwrap();
+        let functions = cpp.extract(ipp, Path::new("box.ipp")).unwrap();
+        assert_eq!(functions.iter().map(|(id, _)| id.name.as_str()).collect::<Vec<_>>(), vec!["Box<T>::get"]);
+
+        // 'class' and 'new' are plain identifiers in C
+        let c_src = "int create(int class, char *new);\n";
+        let mut c = CppExtractor::with_language(ExtractOptions::default(), Language::C).unwrap();
+        let functions = c.extract(c_src, Path::new("create")).unwrap();
+        assert_eq!(functions.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>(),
+                   vec![FunctionID { name: "create".into(), params: "(int class, char *new)".into() }]);
+
+        assert_eq!("CPP".parse::<Language>().unwrap(), Language::Cpp);
+        assert!("rust".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn macro_declarations_are_ignored_if_enabled()
+    {
+        let src = "class A\n{\n    DECLARE_API(foo);\n    int get(int a);\n};\nMY_MACRO(int) other(int b);\nint MAX_OF(int a, int b);\n";
+        let names = |ignore_macro_declarations: bool| {
+            let options = ExtractOptions { use_qualifiers: true, ignore_macro_declarations, ..Default::default() };
+            let mut extractor = CppExtractor::with_options(options).unwrap();
+            let mut names: Vec<String> = extractor.extract(src, Path::new("a.h")).unwrap()
+                .into_iter().map(|(id, _)| id.name).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(false), vec!["A::DECLARE_API", "A::get", "MAX_OF", "MY_MACRO", "other"]);
+        assert_eq!(names(true), vec!["A::get", "MAX_OF", "other"]);
+    }
+}
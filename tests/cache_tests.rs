@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod cache_tests
+{
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+    use docwen::cache::ExtractionCache;
+    use docwen::c_parse::{find_function_positions_with, ExtractorRegistry};
+    use docwen::docfig::Mode::MatchFunctionDocsUnqualified;
+    use docwen::docfig::Settings;
+
+    fn make_settings() -> Settings
+    {
+        Settings
+        {
+            match_extensions: vec!["h".into(), "c".into()],
+            ..Default::default()
+        }
+    }
+
+    /// Extracts the given files through the given cache and returns the number of found matches.
+    fn run(paths: &[PathBuf], cache: &mut ExtractionCache) -> usize
+    {
+        let sources: Vec<String> = paths.iter().map(|p| fs::read_to_string(p).unwrap()).collect();
+        let mut registry = ExtractorRegistry::new(true).unwrap();
+        find_function_positions_with(paths.iter().map(PathBuf::as_path).zip(sources.iter().map(String::as_str)),
+                                     &mut registry, Some(cache))
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn unchanged_file_is_reused_and_changed_file_is_reparsed()
+    {
+        let dir = tempdir().unwrap();
+        let h = dir.path().join("a.h");
+        let c = dir.path().join("a.c");
+        let cache_path = dir.path().join("cache.toml");
+        fs::write(&h, "int foo();\n").unwrap();
+        fs::write(&c, "int foo() { return 0; }\n").unwrap();
+        let paths = [h, c.clone()];
+        let settings = make_settings();
+
+        let mut cache = ExtractionCache::load(&cache_path, &settings);
+        assert_eq!(run(&paths, &mut cache), 1);
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+        cache.save(&cache_path).unwrap();
+
+        fs::write(&c, "int foo() { return 1; }\nint bar() {}\n").unwrap();
+        let mut cache = ExtractionCache::load(&cache_path, &settings);
+        assert_eq!(run(&paths, &mut cache), 1);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+    }
+
+    #[test]
+    fn settings_change_invalidates_cache()
+    {
+        let dir = tempdir().unwrap();
+        let h = dir.path().join("a.h");
+        let c = dir.path().join("a.c");
+        let cache_path = dir.path().join("cache.toml");
+        fs::write(&h, "int foo();\n").unwrap();
+        fs::write(&c, "int foo() { return 0; }\n").unwrap();
+        let paths = [h, c];
+
+        let mut settings = make_settings();
+        let mut cache = ExtractionCache::load(&cache_path, &settings);
+        run(&paths, &mut cache);
+        cache.save(&cache_path).unwrap();
+
+        settings.mode = MatchFunctionDocsUnqualified.into();
+        let mut cache = ExtractionCache::load(&cache_path, &settings);
+        run(&paths, &mut cache);
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+    }
+
+    #[test]
+    fn corrupt_cache_file_is_ignored()
+    {
+        let dir = tempdir().unwrap();
+        let cache_path = dir.path().join("cache.toml");
+        fs::write(&cache_path, "not [valid").unwrap();
+
+        let cache = ExtractionCache::load(&cache_path, &make_settings());
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
+    }
+}
@@ -81,7 +81,7 @@ mod docfig_tests
         // SETTINGS
         assert_eq!(docfig.settings.target, PathBuf::from("src"));
         assert_eq!(docfig.settings.match_extensions, vec!["h", "c"]);
-        matches!(docfig.settings.mode, Mode::MatchFunctionDocs);
+        matches!(docfig.settings.mode.primary(), Mode::MatchFunctionDocs);
         assert_eq!(docfig.settings.manual, vec!["some", "thing"]);
 
         // FILE_GROUPS
@@ -136,15 +136,34 @@ mod docfig_tests
     }
 
     #[test]
-    fn fails_on_missing_mode()
+    fn config_errors_carry_toml_location_as_json()
+    {
+        let toml = "[settings]\nmode = \"MATCH_FUNCTION_DOCS\"\ntarget == \"src\"\n";
+
+        let path = write_temp_toml(toml);
+        let err = Docfig::from_file(&path).unwrap_err();
+        let config_error = err.downcast_ref::<ConfigError>().expect("Expected a ConfigError");
+        assert_eq!(config_error.kind, ConfigErrorKind::Parse);
+        assert_eq!((config_error.line, config_error.column), (Some(3), Some(9)));
+
+        let json: serde_json::Value = serde_json::from_str(&docwen::output::config_error_json(config_error)).unwrap();
+        assert_eq!(json["kind"], "parse");
+        assert_eq!(json["line"], 3);
+        assert!(!json["message"].as_str().unwrap().is_empty());
+    }
+
+    #[test]
+    fn parses_config_with_only_target()
     {
         let toml = r#"
         [settings]
         target = "src"
         "#;
         let path = write_temp_toml(toml);
-        let docfig = Docfig::from_file(&path);
-        let Err(_) = docfig else { panic!("Config::from_file unexpectedly succeeded"); };
+        let docfig = Docfig::from_file(&path).unwrap();
+
+        assert_eq!(docfig.settings, Settings { target: PathBuf::from("src"), ..Default::default() });
+        assert_eq!(docfig.settings.mode, Mode::MatchFunctionDocs);
     }
 
     #[test]
@@ -196,6 +215,27 @@ mod docfig_tests
         let Err(_) = docfig else { panic!("Config::from_file unexpectedly succeeded"); };
     }
 
+    #[test]
+    fn fails_on_duplicate_file_in_group()
+    {
+        let toml = r#"
+        [settings]
+        target = "src"
+
+        [[filegroup]]
+        name = "a"
+        files = ["a.h", "a.c", "./a.c"]
+        "#;
+
+        let path = write_temp_toml(toml);
+        let err = Docfig::from_file(&path).unwrap_err();
+        assert!(err.to_string().contains("Filegroup 'a' lists ./a.c more than once"), "Error was: {err}");
+
+        // Groups that bypass validation are still only parsed once per file
+        let group = FileGroup { name: "a".into(), files: vec!["a.h".into(), "a.c".into(), "./a.c".into()] };
+        assert_eq!(group.resolved_paths(std::path::Path::new("/project")).unwrap().len(), 2);
+    }
+
     #[test]
     fn fails_on_unknown_fields()
     {
@@ -292,4 +332,130 @@ mod docfig_tests
         let Err(e) = Docfig::from_file(&path) else { panic!("Expected error"); };
         assert!(e.to_string().contains("Failed to read"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parses_links_as_additional_groups()
+    {
+        let toml = r#"
+        [settings]
+        target = "src"
+
+        [[filegroup]]
+        name = "a"
+        files = ["a.h", "a.c"]
+
+        [[link]]
+        header = "include/ifoo.h"
+        source = "src/foo.c"
+        "#;
+
+        let path = write_temp_toml(toml);
+        let docfig = Docfig::from_file(&path).unwrap();
+
+        assert_eq!(docfig.links, vec![Link { header: "include/ifoo.h".into(), source: "src/foo.c".into() }]);
+        let groups = docfig.all_groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].files, vec![PathBuf::from("include/ifoo.h"), PathBuf::from("src/foo.c")]);
+        assert!(docfig.is_linked("./src/foo.c"));
+    }
+
+    #[test]
+    fn json_schema_validates_minimal_config_and_rejects_unknown_fields()
+    {
+        let validator = jsonschema::validator_for(&Docfig::json_schema()).unwrap();
+
+        let minimal: serde_json::Value = toml::from_str(MINIMAL_VALID_TOML).unwrap();
+        assert!(validator.is_valid(&minimal), "Minimal config was rejected: {minimal}");
+
+        let default: serde_json::Value = toml::from_str(docwen::toml_manager::DEFAULT_TOML).unwrap();
+        assert!(validator.is_valid(&default), "Default config was rejected: {default}");
+
+        let unknown_setting = serde_json::json!({"settings": {"target": "src", "bogus": 1}});
+        assert!(!validator.is_valid(&unknown_setting));
+
+        let unknown_mode = serde_json::json!({"settings": {"target": "src", "mode": "BOGUS"}});
+        assert!(!validator.is_valid(&unknown_mode));
+    }
+
+    #[test]
+    fn resolved_files_are_absolute_and_deduplicated()
+    {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/a.h"), "").unwrap();
+        std::fs::write(dir.path().join("a.c"), "").unwrap();
+
+        let group = FileGroup { name: "a".into(), files: vec!["src/a.h".into(), "src/../a.c".into(), "a.c".into()] };
+        let files = group.resolved_files(dir.path()).unwrap();
+        assert_eq!(files, vec![dir.path().join("src").join("a.h"), dir.path().join("a.c")]);
+        assert!(files.iter().all(|f| f.is_absolute()));
+    }
+
+    #[test]
+    fn resolved_files_expand_globs()
+    {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        for file in ["src/b.h", "src/a.h", "src/a.c", "main.h"]
+        {
+            std::fs::write(dir.path().join(file), "").unwrap();
+        }
+
+        let group = FileGroup { name: "a".into(), files: vec!["src/*.h".into(), "src/a.h".into(), "src/a.?".into()] };
+        let src = dir.path().join("src");
+        assert_eq!(group.resolved_files(dir.path()).unwrap(), vec![src.join("a.h"), src.join("b.h"), src.join("a.c")]);
+
+        let group = FileGroup { name: "a".into(), files: vec!["src/*.cpp".into()] };
+        let err = group.resolved_files(dir.path()).unwrap_err().to_string();
+        assert!(err.contains("src/*.cpp") && err.contains("matches no file"), "Error was: {err}");
+    }
+
+    #[test]
+    fn resolved_files_reports_missing_file()
+    {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.h"), "").unwrap();
+
+        let group = FileGroup { name: "a".into(), files: vec!["a.h".into(), "a.c".into()] };
+        let err = group.resolved_files(dir.path()).unwrap_err().to_string();
+        assert!(err.contains("a.c") && err.contains("group 'a'") && err.contains("does not exist"), "Error was: {err}");
+
+        // Missing files are kept by resolved_paths
+        assert_eq!(group.resolved_paths(dir.path()).unwrap(), vec![dir.path().join("a.h"), dir.path().join("a.c")]);
+    }
+
+    #[test]
+    fn mode_accepts_a_string_or_an_array()
+    {
+        let parse = |mode: &str| Docfig::from_file(write_temp_toml(&format!("[settings]\ntarget = \"src\"\nmode = {mode}\n")));
+        assert_eq!(parse("\"MATCH_MACRO_DOCS\"").unwrap().settings.mode, Mode::MatchMacroDocs);
+
+        let docfig = parse("[\"MATCH_FUNCTION_DOCS\", \"MATCH_STRUCT_DOCS\", \"MATCH_FUNCTION_DOCS\"]").unwrap();
+        assert_eq!(docfig.settings.mode.iter().collect::<Vec<_>>(), vec![Mode::MatchFunctionDocs, Mode::MatchStructDocs]);
+        assert!(toml::to_string(&docfig.settings).unwrap().contains("mode = [\"MATCH_FUNCTION_DOCS\", \"MATCH_STRUCT_DOCS\"]"));
+
+        let err = parse("[]").unwrap_err();
+        assert!(format!("{err:#}").contains("non-empty array of modes"), "Error was: {err:#}");
+        let err = parse("[\"MATCH_FUNCTION_DOCS\", \"REQUIRE_DOCS\"]").unwrap_err();
+        assert!(format!("{err:#}").contains("unknown variant `REQUIRE_DOCS`"), "Error was: {err:#}");
+    }
+
+    #[test]
+    fn overrides_patch_known_settings()
+    {
+        let path = write_temp_toml(MINIMAL_VALID_TOML);
+        let overrides = ["mode=MATCH_MACRO_DOCS", "trim_doc_block = true", "match_extensions=[\"h\", \"c\"]"].map(String::from);
+        let docfig = Docfig::from_file_with_overrides(&path, &overrides).unwrap();
+        assert_eq!(docfig.settings.mode, Mode::MatchMacroDocs);
+        assert!(docfig.settings.trim_doc_block);
+        assert_eq!(docfig.settings.match_extensions, vec!["h".to_string(), "c".to_string()]);
+        assert_eq!(Docfig::from_file(&path).unwrap().settings.mode, Mode::MatchFunctionDocs);
+
+        let err = Docfig::from_file_with_overrides(&path, &["match_case=true".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("Unknown setting 'match_case'"), "Error was: {err}");
+        let err = Docfig::from_file_with_overrides(&path, &["trim_doc_block".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("expected <key>=<value>"), "Error was: {err}");
+        let err = Docfig::from_file_with_overrides(&path, &["mode=REQUIRE_DOCS".to_string()]).unwrap_err();
+        assert!(format!("{err:#}").contains("REQUIRE_DOCS"), "Error was: {err:#}");
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod docref_tests
+{
+    use std::path::Path;
+
+    use docwen::c_parse::{CppExtractor, Extractor};
+    use docwen::docref::DocReference;
+    use docwen::docwen_check::FunctionID;
+
+    #[test]
+    fn parses_entries_with_normalized_ids()
+    {
+        let text = "# Public API\n\n[ns :: add( int a,  int b )]\n/// Adds a and b\n/// @return The sum\n\n\n[reset()]\n// Resets all state\n";
+        let reference = DocReference::parse(text).unwrap();
+        assert_eq!(reference.len(), 2);
+
+        let add = FunctionID { name: "ns::add".into(), params: "(int a, int b)".into() };
+        assert_eq!(reference.get(&add).unwrap(), ["/// Adds a and b", "/// @return The sum"]);
+        let reset = FunctionID { name: "reset".into(), params: "()".into() };
+        assert_eq!(reference.get(&reset).unwrap(), ["// Resets all state"]);
+        assert!(reference.get(&FunctionID { name: "add".into(), params: "(int a, int b)".into() }).is_none());
+    }
+
+    #[test]
+    fn const_methods_match_extracted_ids()
+    {
+        let reference = DocReference::parse("[A::size()const]\n/// Returns the size\n").unwrap();
+        let mut extractor = CppExtractor::new(true).unwrap();
+        let ids = extractor.extract("struct A\n{\n    int size() const;\n};\n", Path::new("a.hpp")).unwrap();
+        let size = ids.iter().find(|(id, _)| id.name == "A::size").map(|(id, _)| id).unwrap();
+        assert_eq!(size.params, "() const");
+        assert_eq!(reference.get(size).unwrap(), ["/// Returns the size"]);
+    }
+
+    #[test]
+    fn invalid_references_are_errors()
+    {
+        let err = DocReference::parse("/// Adds a and b\n[add(int a, int b)]\n").unwrap_err();
+        assert!(err.to_string().contains("Line 1"), "Error was: {err}");
+
+        let err = DocReference::parse("[add]\n/// Adds\n").unwrap_err();
+        assert!(err.to_string().contains("Expected '[name(params)]'"), "Error was: {err}");
+
+        let err = DocReference::parse("[add()]\n// A\n[add( )]\n// B\n").unwrap_err();
+        assert!(err.to_string().contains("Line 3: Duplicate entry for add()"), "Error was: {err}");
+    }
+}
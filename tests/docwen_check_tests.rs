@@ -7,7 +7,11 @@ mod docwen_check_tests
     use std::io::Write;
     use tempfile::tempdir;
     use docwen::docwen_check;
-    use docwen::docwen_check::{format_mismatch, FilePosition, FunctionID, LineSource};
+    use docwen::docwen_check::{format_mismatch, format_mismatch_with_doc_rows, trailing_block_comment, CheckOptions, DocRules, FilePosition, FunctionID, GroupSummary, LineSource, MismatchKind, Warning};
+    use docwen::docwen_check::strip_comment_markers;
+    use docwen::docwen_check::{compare_docs, CompareOptions, DocDiff};
+    use docwen::toml_manager::normalize_path;
+    use docwen::c_parse::Language;
 
     /// Creates a FilePosition from the arguments
     fn fp(path: &str, row: usize, column: usize) -> FilePosition
@@ -16,6 +20,8 @@ mod docwen_check_tests
             path: PathBuf::from(path),
             row,
             column,
+            is_definition: false,
+            needs_declaration: false,
         }
     }
 
@@ -43,15 +49,23 @@ mod docwen_check_tests
     /// Creates a throw-away workspace on disk:
     ///   * `file_specs` (`relative path`, `file contents`)
     ///   * `groups` slice of slices grouping the files
+    ///
     /// Returns the absolute path to the new `docwen.toml`.
     fn workspace(file_specs: &[(&str, &str)], groups: &[&[&str]], ) -> tempfile::TempDir {
+        workspace_with_settings(file_specs, groups, "")
+    }
+
+    /// Like 'workspace', but appends the given 'extra_settings' lines to the '[settings]' table.
+    fn workspace_with_settings(file_specs: &[(&str, &str)], groups: &[&[&str]], extra_settings: &str)
+        -> tempfile::TempDir
+    {
         let dir = tempdir().unwrap();
         for (file, contents) in file_specs
         {
             write_file(dir.path().join(file), contents);
         }
 
-        let mut toml = String::from("[settings]\ntarget = \".\"\n mode=\"MATCH_FUNCTION_DOCS\"\n\n");
+        let mut toml = format!("[settings]\ntarget = \".\"\n{extra_settings}\n\n");
         toml.extend(groups.iter().cloned().map(toml_group));
 
         write_file(dir.path().join("docwen.toml"), &toml);
@@ -79,7 +93,7 @@ mod docwen_check_tests
         let expected = format!(
             "\"{}\"\n-> [{}]",
             "needle",
-            format!("{:?}:42:7", PathBuf::from("src/lib.c"))
+            format_args!("{:?}:42:7", PathBuf::from("src/lib.c"))
         );
 
         assert_eq!(format_mismatch("needle", &positions, &target_path), expected);
@@ -95,7 +109,7 @@ mod docwen_check_tests
         let expected = format!(
             "\"{}\"\n-> [{}]",
             "token",
-            format!("{:?}:1:0", PathBuf::from(position_path))
+            format_args!("{:?}:1:0", PathBuf::from(position_path))
         );
 
         assert_eq!(format_mismatch("token", &positions, &target_path), expected);
@@ -110,7 +124,7 @@ mod docwen_check_tests
             fp("project/src/foo.c", 11, 2),
         ];
 
-        let expected_group = vec![
+        let expected_group = [
             format!("{:?}:10:1", PathBuf::from("src/foo.h")),
             format!("{:?}:11:2", PathBuf::from("src/foo.c")),
         ]
@@ -147,6 +161,20 @@ mod docwen_check_tests
         Ok(())
     }
 
+    #[test]
+    fn line_source_handles_crlf()
+    {
+        let src = "#define ONE \\\r\n    1\r\n\r\n// doc comment\r\nint foo();\r\n";
+        let ls = LineSource { src: src.into(), init_row: 4 };
+        assert_eq!(ls.trimmed_line_by_offset(-1), "// doc comment");
+        assert_eq!(ls.trimmed_line_by_offset(0), "int foo();");
+
+        let dir = workspace(&[("a.h", src), ("a.c", "// doc comment\nint foo() { return 1; }\n")], &[&["a.h", "a.c"]]);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert!(report.mismatches.is_empty(), "{:?}", report.mismatches);
+        assert_eq!(report.groups[0].shared, 1);
+    }
+
     #[test]
     fn line_source_out_of_bounds_is_empty()
     {
@@ -361,6 +389,19 @@ mod docwen_check_tests
         );
     }
 
+    #[test]
+    fn check_reports_extra_line_of_longer_doc_block()
+    {
+        let short = "int x;\n// brief\nint foo() {}\n";
+        let long = "\n// Details 1\n// Details 2\n// brief\nint foo() {}\n";
+        let dir = workspace(&[("a.c", short), ("b.c", long)], &[&["a.c", "b.c"]]);
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].kind, MismatchKind::Missing);
+        assert_eq!(report.mismatches[0].line, "// Details 2");
+    }
+
     #[test]
     fn check_detects_mismatch_with_three_files()
     {
@@ -404,4 +445,1224 @@ mod docwen_check_tests
             "Identical block comments must not be flagged"
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn check_compares_asm_label_docs_with_header()
+    {
+        let h = "\n// Adds two ints\nint add(int a, int b);\n";
+        let good = "\t.globl add\n// Adds two ints\nadd:\n\tret\n";
+        let bad = "\t.globl add\n// Adds two longs\nadd:\n\tret\n";
+
+        let dir = workspace(&[("add.h", h), ("add.s", good)], &[&["add.h", "add.s"]]);
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert!(mismatches.is_empty(), "Unexpected mismatches: {mismatches:?}");
+
+        let dir = workspace(&[("add.h", h), ("add.s", bad)], &[&["add.h", "add.s"]]);
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("add.s"));
+    }
+
+    #[test]
+    fn check_outputs_relative_paths_for_dot_target()
+    {
+        let dir = tempdir().unwrap();
+        write_file(dir.path().join("a.c"), "\n// one\nint foo() {}\n");
+        write_file(dir.path().join("b.c"), "\n// two\nint foo() {}\n");
+        write_file(
+            dir.path().join("docwen.toml"),
+            "[settings]\ntarget = \".\"\nmode = \"MATCH_FUNCTION_DOCS\"\n\n\
+             [[filegroup]]\nname = \"a\"\nfiles = [\"./a.c\", \"./b.c\"]\n",
+        );
+
+        let toml_path = dir.path().join(".").join("docwen.toml");
+        let mismatches = run_check!(toml_path);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("[\"a.c\":2:0, \"b.c\":2:0]"), "Paths not relative: {}", mismatches[0]);
+    }
+
+    #[test]
+    fn line_source_returns_trailing_block_comment_of_code_line()
+    {
+        let src = "// doc of x\nint x; /** doc of f\n * more */\nvoid f();\n";
+        let ls = LineSource { src: src.into(), init_row: 3 };
+
+        assert_eq!(ls.doc_line_by_offset(-1), "* more */");
+        assert_eq!(ls.doc_line_by_offset(-2), "/** doc of f");
+        assert_eq!(ls.doc_line_by_offset(-3), "", "Doc block must end at the mixed line");
+    }
+
+    #[test]
+    fn check_detects_doc_opened_on_code_line()
+    {
+        let a = "\nint x; /** Shared doc */\nvoid f();\n";
+        let b = "\n/** Shared doc */\nvoid f() {}\n";
+        let c = "\nint y; /** Other doc */\nvoid f() {}\n";
+
+        let dir = workspace(&[("a.h", a), ("a.c", b)], &[&["a.h", "a.c"]]);
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert!(mismatches.is_empty(), "Unexpected mismatches: {mismatches:?}");
+
+        let dir = workspace(&[("a.h", a), ("a.c", c)], &[&["a.h", "a.c"]]);
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("/** Shared doc */"), "Mismatch was: {}", mismatches[0]);
+    }
+
+    #[test]
+    fn trailing_block_comment_ignores_inline_comments()
+    {
+        assert_eq!(trailing_block_comment("int x; /* a */"), Some("/* a */"));
+        assert_eq!(trailing_block_comment("int x; /* a */ int y;"), None);
+        assert_eq!(trailing_block_comment("/* a */"), None);
+        assert_eq!(trailing_block_comment("int x;"), None);
+        assert_eq!(trailing_block_comment("int x; // old: /* legacy */"), None);
+        assert_eq!(trailing_block_comment("const char *s = \"/* not a comment */\";"), None);
+        assert_eq!(trailing_block_comment("char c = '\"'; /* quote */"), Some("/* quote */"));
+    }
+
+    #[test]
+    fn check_uses_stdin_file_content_and_only_its_groups()
+    {
+        let code = "\n// on disk\nint foo() {}\n";
+        let dir = workspace(
+            &[
+                ("a.c", code),
+                ("b.c", code),
+                ("x.c", "\n// X1\nint bar() {}\n"),
+                ("y.c", "\n// X2\nint bar() {}\n"),
+            ],
+            &[&["a.c", "b.c"], &["x.c", "y.c"]],
+        );
+
+        let options = CheckOptions {
+            stdin_file: Some((dir.path().join("a.c"), "\n// from buffer\nint foo() {}\n".into())),
+            ..Default::default()
+        };
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &options).unwrap();
+
+        assert_eq!(report.mismatches.len(), 1, "Only the group of a.c should be checked");
+        assert_eq!(report.mismatches[0].line, "// from buffer");
+    }
+
+    #[test]
+    fn check_ignores_suppressions_in_comparison()
+    {
+        let h = "\n// Does foo\nint foo();\n";
+        let c = "\n// Does foo\n// NOLINTNEXTLINE(readability-*)\nint foo() {}\n";
+        let files = [("a.h", h), ("a.c", c)];
+        let groups: &[&[&str]] = &[&["a.h", "a.c"]];
+
+        let dir = workspace(&files, groups);
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1, "Suppression should be compared by default");
+
+        let dir = workspace_with_settings(&files, groups, "suppression_prefixes = [\"// NOLINT\"]");
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert!(mismatches.is_empty(), "Unexpected mismatches: {mismatches:?}");
+    }
+
+    #[test]
+    fn check_flags_inconsistent_suppressions_in_strict_mode()
+    {
+        let h = "\n// Does foo\nint foo();\n";
+        let c = "\n// Does foo\n// NOLINTNEXTLINE(readability-*)\nint foo() {}\n";
+        let dir = workspace_with_settings(
+            &[("a.h", h), ("a.c", c)],
+            &[&["a.h", "a.c"]],
+            "suppression_prefixes = [\"// NOLINT\"]\nstrict_suppressions = true",
+        );
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].kind, MismatchKind::Suppression);
+        assert_eq!(report.mismatches[0].line, "// NOLINTNEXTLINE(readability-*)");
+
+        let dir = workspace_with_settings(
+            &[("a.h", c), ("a.c", c)],
+            &[&["a.h", "a.c"]],
+            "suppression_prefixes = [\"// NOLINT\"]\nstrict_suppressions = true",
+        );
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+    }
+
+    #[test]
+    fn check_reports_row_of_mismatching_doc_line()
+    {
+        let a = "\n// doc line 1\n// shared\n// shared\nint foo() {}\n";
+        let b = "int x;\n\n// doc line 2\n// shared\n// shared\nint foo() {}\n";
+        let dir = workspace(&[("a.c", a), ("b.c", b)], &[&["a.c", "b.c"]]);
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+
+        let m = &report.mismatches[0];
+        let offset = -3;
+        for (pos, doc_row) in m.positions.iter().zip(&m.doc_rows)
+        {
+            assert_eq!(*doc_row as isize, pos.row as isize + offset, "Wrong doc row for {pos:?}");
+        }
+
+        let formatted = format_mismatch_with_doc_rows(m, &report.target);
+        assert!(formatted.contains("\"a.c\":4:0 (doc row 1)"), "Output was: {formatted}");
+        assert!(formatted.contains("\"b.c\":5:0 (doc row 2)"), "Output was: {formatted}");
+    }
+
+    #[test]
+    fn check_compares_files_linked_across_directories()
+    {
+        let dir = tempdir().unwrap();
+        write_file(dir.path().join("include/ifoo.h"), "\n// Header doc\nint foo();\n");
+        write_file(dir.path().join("src/foo.c"), "\n// Source doc\nint foo() {}\n");
+        write_file(
+            dir.path().join("docwen.toml"),
+            "[settings]\ntarget = \".\"\n\n[[link]]\nheader = \"include/ifoo.h\"\nsource = \"src/foo.c\"\n",
+        );
+
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("ifoo.h") && mismatches[0].contains("foo.c"));
+    }
+
+    #[test]
+    fn check_iter_is_lazy_per_group()
+    {
+        // The second group references a directory, so checking it would fail
+        let dir = workspace(
+            &[("a.c", "\n// A\nint foo() {}\n"), ("b.c", "\n// B\nint foo() {}\n")],
+            &[&["a.c", "b.c"], &["b.c", "sub"]],
+        );
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let toml_path = dir.path().join("docwen.toml");
+        let options = CheckOptions::default();
+
+        let first: Vec<_> = docwen_check::check_iter(&toml_path, &options).unwrap().take(1).collect();
+        assert_eq!(first.len(), 1);
+        assert!(first[0].is_ok(), "First group should not touch the directory: {:?}", first[0]);
+
+        assert!(docwen_check::check_with_options(&toml_path, &options).is_err());
+    }
+
+    #[test]
+    fn doc_block_collects_line_comment_run()
+    {
+        let src = "int x;\n// line 1\n// line 2\nvoid f();\n";
+        let ls = LineSource { src: src.into(), init_row: 3 };
+
+        let block = ls.doc_block(&DocRules::default());
+        assert_eq!(block.doc_len, 2);
+        assert_eq!((block.line(0), block.line(1)), ("// line 2", "// line 1"));
+        assert!(!block.is_doc(2));
+        assert_eq!(block.line(2), "int x;");
+    }
+
+    #[test]
+    fn doc_block_stops_at_blank_line_by_default()
+    {
+        let src = "// far away\n\n// near\nvoid f();\n";
+        let ls = LineSource { src: src.into(), init_row: 3 };
+
+        let block = ls.doc_block(&DocRules::default());
+        assert_eq!(block.doc_len, 1);
+        assert_eq!(block.line(0), "// near");
+    }
+
+    #[test]
+    fn doc_block_skips_allowed_internal_blank_lines()
+    {
+        let src = "int x;\n// top\n\n\n// middle\n\n// bottom\nvoid f();\n";
+        let ls = LineSource { src: src.into(), init_row: 7 };
+
+        let one_blank = DocRules { max_blank_lines: 1, ..Default::default() };
+        let block = ls.doc_block(&one_blank);
+        assert_eq!(block.doc_len, 2, "Two consecutive blanks exceed the allowance");
+        assert_eq!((block.line(0), block.line(1)), ("// bottom", "// middle"));
+
+        let two_blanks = DocRules { max_blank_lines: 2, ..Default::default() };
+        let block = ls.doc_block(&two_blanks);
+        assert_eq!(block.doc_len, 3);
+        assert_eq!(block.line(2), "// top");
+        assert_eq!(block.line(3), "int x;");
+    }
+
+    #[test]
+    fn doc_block_respects_configured_prefixes()
+    {
+        let src = "/// doc\n// not doc\n/// doc 2\nvoid f();\n";
+        let ls = LineSource { src: src.into(), init_row: 3 };
+
+        let rules = DocRules { prefixes: vec!["///".into()], ..Default::default() };
+        let block = ls.doc_block(&rules);
+        assert_eq!(block.doc_len, 1);
+        assert_eq!(block.line(1), "// not doc");
+    }
+
+    /// Runs check_with_options on a workspace of 'a.h' and 'a.c' with the given doc_location_policy.
+    fn check_policy(h: &str, c: &str, policy: &str) -> Vec<docwen_check::Mismatch>
+    {
+        let dir = workspace_with_settings(
+            &[("a.h", h), ("a.c", c)],
+            &[&["a.h", "a.c"]],
+            &format!("doc_location_policy = \"{policy}\""),
+        );
+        docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default())
+            .unwrap()
+            .mismatches
+    }
+
+    #[test]
+    fn doc_location_policy_either_compares_all()
+    {
+        let documented_h = "\n// Does foo\nint foo();\n";
+        let documented_c = "\n// Does foo\nint foo() {}\n";
+        assert!(check_policy(documented_h, documented_c, "either").is_empty());
+
+        let mismatches = check_policy(documented_h, "\nint foo() {}\n", "either");
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].kind, MismatchKind::Missing);
+    }
+
+    #[test]
+    fn doc_location_policy_declaration_flags_documented_definition()
+    {
+        let documented_h = "\n// Does foo\nint foo();\n";
+        let documented_c = "\n// Does foo\nint foo() {}\n";
+
+        let mismatches = check_policy(documented_h, documented_c, "declaration");
+        assert_eq!(mismatches.len(), 1, "Mismatches: {mismatches:?}");
+        assert_eq!(mismatches[0].kind, MismatchKind::Location);
+        assert_eq!(mismatches[0].positions.len(), 1);
+        assert!(mismatches[0].positions[0].path.ends_with("a.c"));
+        assert!(mismatches[0].positions[0].is_definition);
+
+        assert!(check_policy(documented_h, "\nint foo() {}\n", "declaration").is_empty());
+    }
+
+    #[test]
+    fn doc_location_policy_definition_flags_documented_declaration()
+    {
+        let documented_h = "\n// Does foo\nint foo();\n";
+        let documented_c = "\n// Does foo\nint foo() {}\n";
+
+        let mismatches = check_policy(documented_h, documented_c, "definition");
+        assert_eq!(mismatches.len(), 1, "Mismatches: {mismatches:?}");
+        assert_eq!(mismatches[0].kind, MismatchKind::Location);
+        assert!(mismatches[0].positions[0].path.ends_with("a.h"));
+
+        assert!(check_policy("\nint foo();\n", documented_c, "definition").is_empty());
+    }
+
+    #[test]
+    fn far_away_license_comment_is_not_compared()
+    {
+        let h = "// Copyright A\n// License A\n\n// Does foo\nint foo();\n";
+        let c = "// Copyright B\n\n// Does foo\nint foo() {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        // Allowing a blank line inside doc blocks pulls the license comment into the block
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "doc_blank_lines = 1");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+    }
+
+    #[test]
+    fn macro_mode_compares_docs_of_multi_line_macros()
+    {
+        let h = "// Squares x\n#define SQUARE(x) \\\n    ((x) * \\\n     (x))\n\n// Not a macro\nint foo();\n";
+        let inl = "// Squares x (differs)\n#  define SQUARE(x) ((x) * (x))\n\n// Also not a macro\nint foo();\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.inl", inl)], &[&["a.h", "a.inl"]],
+                                          "mode = \"MATCH_MACRO_DOCS\"");
+
+        let mismatches = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default())
+            .unwrap()
+            .mismatches;
+        assert_eq!(mismatches.len(), 1, "Mismatches: {mismatches:?}");
+        assert_eq!(mismatches[0].function.name, "SQUARE");
+        assert_eq!(mismatches[0].line, "// Squares x");
+        assert_eq!(mismatches[0].positions.iter().map(|p| p.row).collect::<Vec<_>>(), vec![1, 1]);
+    }
+
+    #[test]
+    fn macro_mode_keys_function_like_macros_by_params()
+    {
+        let a = "// Larger of a and b\n#define MAX(a,b) ((a) > (b) ? (a) : (b))\n// Buffer size\n#define SIZE 64\n";
+        let b = "// Maximum of a and b\n#define MAX(a, b) \\\n    ((a) > (b) ? (a) : (b))\n// Buffer size\n#define SIZE (64)\n";
+        let dir = workspace_with_settings(&[("a.h", a), ("b.h", b)], &[&["a.h", "b.h"]], "mode = \"MATCH_MACRO_DOCS\"");
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1, "Mismatches: {:?}", report.mismatches);
+        assert_eq!(report.mismatches[0].function, FunctionID { name: "MAX".into(), params: "(a, b)".into() });
+        assert_eq!(report.mismatches[0].line, "// Larger of a and b");
+        assert_eq!(report.groups[0].shared, 2);
+    }
+
+    #[test]
+    fn missing_file_is_a_warning_not_a_mismatch()
+    {
+        let dir = workspace(
+            &[("a.h", "\n// Doc\nint foo();\n"), ("a.c", "\n// Doc\nint foo() {}\n")],
+            &[&["a.h", "a.c", "missing.c"]],
+        );
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert!(report.mismatches.is_empty(), "Mismatches: {:?}", report.mismatches);
+        assert_eq!(report.warnings, vec![Warning::MissingFile {
+            group: "a.h".into(),
+            path: normalize_path(dir.path().join("missing.c")),
+        }]);
+    }
+
+    #[test]
+    fn override_docs_are_compared_with_base_declaration()
+    {
+        let base = "class Base\n{\n    // Does f\n    virtual void f(int a);\n    // Does g\n    virtual void g();\n};\n";
+        let derived = "#include \"base.h\"\nclass Derived : public Base\n{\n    // Does f differently\n    void f(int a) override;\n    // Does g\n    void g() override;\n};\n";
+        let files = [("base.h", base), ("derived.h", derived)];
+
+        let dir = workspace_with_settings(&files, &[&["base.h", "derived.h"]], "");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let dir = workspace_with_settings(&files, &[&["base.h", "derived.h"]], "override_inherits_docs = true");
+        let mismatches = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default())
+            .unwrap()
+            .mismatches;
+        assert_eq!(mismatches.len(), 1, "Mismatches: {mismatches:?}");
+        assert_eq!(mismatches[0].function.name, "Derived::f");
+        assert_eq!(mismatches[0].line, "// Does f");
+        assert_eq!(mismatches[0].positions.iter().map(|p| p.row).collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    /// Runs check on a mixed-depth group (in a workspace inside the cwd) with the given display_paths setting
+    /// and returns the displayed paths of the single mismatch together with the workspace path.
+    fn displayed_paths(display_paths: &str) -> (String, tempfile::TempDir)
+    {
+        let dir = tempfile::tempdir_in(std::env::current_dir().unwrap()).unwrap();
+        write_file(dir.path().join("include/lib/foo.h"), "\n// A\nint foo();\n");
+        write_file(dir.path().join("src/foo.c"), "\n// B\nint foo() {}\n");
+        write_file(dir.path().join("docwen.toml"), &format!(
+            "[settings]\ntarget = \".\"\ndisplay_paths = \"{display_paths}\"\n\n{}",
+            toml_group(&["include/lib/foo.h", "src/foo.c"])));
+
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert_eq!(mismatches.len(), 1);
+        let displayed = mismatches[0].split_once("-> ").unwrap().1.to_string();
+        (displayed, dir)
+    }
+
+    #[test]
+    fn display_paths_relative_to_target()
+    {
+        let (displayed, _dir) = displayed_paths("relative");
+        let (h, c) = (Path::new("include/lib/foo.h"), Path::new("src/foo.c"));
+        assert_eq!(displayed, format!("[{h:?}:2:4, {c:?}:2:0]"));
+    }
+
+    #[test]
+    fn display_paths_absolute()
+    {
+        let (displayed, dir) = displayed_paths("absolute");
+        let (h, c) = (normalize_path(dir.path().join("include/lib/foo.h")), normalize_path(dir.path().join("src/foo.c")));
+        assert_eq!(displayed, format!("[{h:?}:2:4, {c:?}:2:0]"));
+    }
+
+    #[test]
+    fn display_paths_from_cwd()
+    {
+        let (displayed, dir) = displayed_paths("from-cwd");
+        let rel_dir = PathBuf::from(dir.path().file_name().unwrap());
+        let (h, c) = (rel_dir.join("include/lib/foo.h"), rel_dir.join("src/foo.c"));
+        assert_eq!(displayed, format!("[{h:?}:2:4, {c:?}:2:0]"));
+    }
+
+    #[test]
+    fn require_declaration_flags_undeclared_non_static_definitions()
+    {
+        let h = "// Does foo\nint foo();\n";
+        let c = "// Does foo\nint foo() { return 0; }\n\n// Does bar\nint bar() { return 1; }\n\
+                 static int baz() { return 2; }\nnamespace { int qux() { return 3; } }\n\
+                 struct S { int inline_method() { return 4; } };\n";
+        let files = [("a.h", h), ("a.c", c)];
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "require_declaration = true");
+        let mismatches = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default())
+            .unwrap()
+            .mismatches;
+        assert_eq!(mismatches.len(), 1, "Mismatches: {mismatches:?}");
+        assert_eq!(mismatches[0].kind, MismatchKind::Undeclared);
+        assert_eq!(mismatches[0].function.name, "bar");
+        assert_eq!(mismatches[0].line, "// Does bar");
+        assert_eq!((mismatches[0].positions[0].row, mismatches[0].doc_rows[0]), (4, 3));
+    }
+
+    #[test]
+    fn report_contains_clean_groups()
+    {
+        let dir = workspace(
+            &[("a.h", "// A\nint a();\nint only_h();\n"), ("a.c", "// A\nint a() {}\n"),
+              ("b.h", "// B\nint b();\n"), ("b.c", "// Not B\nint b() {}\n")],
+            &[&["a.h", "a.c"], &["b.h", "b.c"]],
+        );
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.groups, vec![
+            GroupSummary { name: "a.h".into(), files: 2, functions: 2, shared: 1, mismatches: 0 },
+            GroupSummary { name: "b.h".into(), files: 2, functions: 1, shared: 1, mismatches: 1 },
+        ]);
+        assert_eq!(report.groups[0].status(), "clean");
+        assert_eq!(report.groups[1].status(), "mismatch");
+    }
+
+    #[test]
+    fn strips_qt_and_doxygen_comment_markers()
+    {
+        for (line, stripped) in [("/*! brief */", "brief"), ("/** brief */", "brief"), ("//! brief", "brief"),
+                                 ("/// brief", "brief"), ("/*!< brief */", "brief"), ("///< brief", "brief"),
+                                 ("* brief", "brief"), ("*/", ""), ("// brief", "brief")]
+        {
+            assert_eq!(strip_comment_markers(line), stripped, "Line: {line}");
+        }
+    }
+
+    #[test]
+    fn comment_style_agnostic_matches_qt_and_javadoc_style()
+    {
+        let h = "\n/*! brief */\n//! details\nint foo();\n";
+        let c = "\n/** brief */\n/// details\nint foo() {}\n";
+        let files = [("a.h", h), ("a.c", c)];
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "comment_style_agnostic = true");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let c = "\n/** other brief */\n/// details\nint foo() {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "comment_style_agnostic = true");
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("/*! brief */"), "Mismatch: {}", mismatches[0]);
+    }
+
+    #[test]
+    fn check_reads_docs_below_preprocessor_lines()
+    {
+        let h = "#define LIMIT \\\n    10\n// Doc\nint foo();\n";
+        let c = "#include \"a.h\"\n// Doc\nint foo() {}\n";
+        let dir = workspace(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]]);
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let c = "#include \"a.h\"\n// Other doc\nint foo() {}\n";
+        let dir = workspace(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]]);
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("// Doc") && !mismatches[0].contains("define"), "Mismatch: {}", mismatches[0]);
+    }
+
+    #[test]
+    fn trim_doc_block_ignores_empty_lines_at_block_ends()
+    {
+        let h = "\n/**\n * brief\n *\n * details\n *\n */\nint foo();\n";
+        let c = "\n/** brief\n *\n * details */\nint foo() {}\n";
+        let files = [("a.h", h), ("a.c", c)];
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "comment_style_agnostic = true");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "comment_style_agnostic = true\ntrim_doc_block = true");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        // Empty lines inside the block are still compared
+        let c = "\n/** brief\n * details */\nint foo() {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "comment_style_agnostic = true\ntrim_doc_block = true");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+    }
+
+    #[test]
+    fn trim_doc_block_ignores_trailing_blank_doc_line()
+    {
+        let a = "\n// brief\n//\nint foo() {}\n";
+        let b = "\n// brief\nint foo() {}\n";
+        let files = [("a.c", a), ("b.c", b)];
+
+        let dir = workspace_with_settings(&files, &[&["a.c", "b.c"]], "");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let dir = workspace_with_settings(&files, &[&["a.c", "b.c"]], "trim_doc_block = true");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+    }
+
+    #[test]
+    fn unterminated_comment_is_a_warning_and_group_still_checks()
+    {
+        let broken = "int bar();\n/* never closed\n// Doc\nint foo();\n";
+        let dir = workspace(
+            &[("a.h", "\n// Doc\nint foo();\n"), ("a.c", "\n// Other doc\nint foo() {}\n"), ("b.c", broken)],
+            &[&["a.h", "a.c", "b.c"]],
+        );
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.warnings, vec![Warning::UnterminatedComment {
+            path: normalize_path(dir.path().join("b.c")),
+            row: 1,
+            column: 0,
+        }]);
+        assert_eq!(report.mismatches.len(), 1);
+        assert!(report.mismatches[0].positions.iter().any(|p| p.path.ends_with("a.c")));
+    }
+
+    #[test]
+    fn markdown_sections_are_compared_with_header_docs()
+    {
+        let h = "/**\n * Adds two numbers\n *\n * Returns the sum\n */\nint add(int a, int b);\n\n// Negates x\nint neg(int x);\n";
+        let md = "# API\n\n### `add()`\n\nAdds two numbers\n\nReturns the sum\n\n```c\n### neg\nint x = add(1, 2);\n```\n";
+        let dir = workspace(&[("foo.h", h), ("foo.md", md)], &[&["foo.h", "foo.md"]]);
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let md = "### add\nAdds two numbers\n\nReturns the difference\n\n### neg\n\nNegates x\n";
+        let dir = workspace(&[("foo.h", h), ("foo.md", md)], &[&["foo.h", "foo.md"]]);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].function.name, "add");
+        assert_eq!(report.mismatches[0].kind, MismatchKind::Differs);
+        assert_eq!(report.mismatches[0].doc_rows[1], 3);
+    }
+
+    #[test]
+    fn tag_values_are_case_insensitive_only_for_listed_tags()
+    {
+        let h = "\n/** @brief Adds numbers\n *  @details Uses the FPU */\nint add();\n";
+        let c = "\n/** @brief adds numbers\n *  @details Uses the FPU */\nint add() {}\n";
+        let settings = "tag_value_case_insensitive = [\"@brief\"]";
+
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], settings);
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let c = "\n/** @brief ADDS NUMBERS\n *  @details uses the FPU */\nint add() {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], settings);
+        let mismatches = run_check!(dir.path().join("docwen.toml"));
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("@details"), "Mismatch: {}", mismatches[0]);
+    }
+
+    #[test]
+    fn comparable_lowercases_only_tag_values()
+    {
+        let rules = DocRules { case_insensitive_tags: vec!["brief".into()], ..Default::default() };
+        assert_eq!(rules.comparable("/// \\brief Adds Numbers"), "/// \\brief adds numbers");
+        assert_eq!(rules.comparable("/** @brief Adds */"), "/** @brief adds */");
+        assert_eq!(rules.comparable("// @details Adds"), "// @details Adds");
+        assert_eq!(rules.comparable("// Uses @brief Adds"), "// Uses @brief Adds");
+    }
+
+    #[test]
+    fn assume_language_applies_to_unregistered_extensions()
+    {
+        let h = "\n// Creates an item\nint create(int class);\n";
+        let c = "\n// Creates a thing\nint create(int class) {}\n";
+        let dir = workspace(&[("item.h", h), ("item", c)], &[&["item.h", "item"]]);
+
+        let options = CheckOptions { assume_language: Some(Language::C), ..Default::default() };
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &options).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].function, FunctionID { name: "create".into(), params: "(int class)".into() });
+    }
+
+    #[test]
+    fn mismatch_reports_doc_block_spans()
+    {
+        let h = "int x;\n\n/**\n * Adds\n *\n * a and b\n */\nint add(int a, int b);\n";
+        let c = "// Adds a and b\nint add(int a, int b) {}\n\nint sub(int a) {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "");
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].doc_spans, vec![Some((2, 6)), Some((0, 0))]);
+
+        let c = "int sub(int a) {}\nint add(int a, int b) {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches[0].doc_spans, vec![Some((2, 6)), None]);
+    }
+
+    #[test]
+    fn explicit_files_are_checked_without_groups()
+    {
+        let cwd = std::env::current_dir().unwrap();
+        let dir = tempfile::tempdir_in(&cwd).unwrap();
+        write_file(dir.path().join("a.h"), "\n// Doc A\nint foo();\n");
+        write_file(dir.path().join("b.c"), "\n// Doc B\nint foo() {}\n");
+        write_file(dir.path().join("c.c"), "\n// Doc A\nint foo() {}\n");
+        write_file(dir.path().join("docwen.toml"), &format!("[settings]\ntarget = \".\"\n\n{}", toml_group(&["a.h", "c.c"])));
+        let toml = dir.path().join("docwen.toml");
+        assert!(run_check!(toml).is_empty());
+
+        // Resolved relative to the current directory
+        let relative = |f: &str| dir.path().join(f).strip_prefix(&cwd).unwrap().to_path_buf();
+        let options = CheckOptions { files: Some(vec![relative("a.h"), relative("b.c")]), ..Default::default() };
+        let report = docwen_check::check_with_options(&toml, &options).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.groups[0].files, 2);
+    }
+
+    #[test]
+    fn auto_doc_style_matches_block_and_line_comments()
+    {
+        let h = "\n/**\n * Adds a and b\n *\n * @return The sum\n */\nint add(int a, int b);\n";
+        let c = "\n// Adds a and b\n//\n// @return The sum\nint add(int a, int b) {}\n";
+        let files = [("a.h", h), ("a.c", c)];
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "comment_style_agnostic = true");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "doc_style = \"auto\"");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        // Only the markers of the detected style are ignored: '*' is content in a '//' block
+        let c = "\n// * Adds a and b\n//\n// @return The sum\nint add(int a, int b) {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "doc_style = \"auto\"");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+    }
+
+    #[test]
+    fn included_files_inherit_the_scope_of_their_include()
+    {
+        let h = "namespace ns\n{\n    // Adds a and b\n    template<typename T> T add(T a, T b);\n\n    #include \"foo.inl\"\n}\n";
+        let inl = "// Adds b to a\ntemplate<typename T> T add(T a, T b) { return a + b; }\n";
+        let files = [("foo.h", h), ("foo.inl", inl)];
+
+        // Without the setting 'ns::add' and 'add' are different functions
+        let dir = workspace(&files, &[&["foo.h", "foo.inl"]]);
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let dir = workspace_with_settings(&files, &[&["foo.h", "foo.inl"]], "inherit_include_scope = true");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].function.name, "ns::add");
+    }
+
+    #[test]
+    fn file_level_tags_are_not_compared()
+    {
+        let h = "/**\n * @file a.h\n * @author Alice\n * @date 2024-01-01\n */\nint add(int a, int b);\n";
+        let c = "/**\n * @file a.c\n * @author Bob\n * @copyright ACME\n */\nint add(int a, int b) { return a + b; }\n";
+        let dir = workspace(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]]);
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        // Other lines of the block are still compared
+        let c = "/**\n * @file a.c\n * Adds a and b\n */\nint add(int a, int b) { return a + b; }\n";
+        let dir = workspace(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]]);
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "ignore_file_level_tags = []");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+    }
+
+    #[test]
+    fn only_public_skips_functions_with_internal_linkage()
+    {
+        let h = "// Helper A\nstatic int helper(int a);\nnamespace\n{\n    // Anon A\n    void anon();\n}\n\
+                 struct S\n{\n    // Make A\n    static S make();\n};\n\n// Add A\nint add(int a, int b);\n";
+        let c = "// Helper B\nstatic int helper(int a) { return a; }\nnamespace\n{\n    // Anon B\n    void anon() {}\n}\n\
+                 // Make B\nS S::make() { return {}; }\n\n// Add B\nint add(int a, int b) { return a + b; }\n";
+        let files = [("a.h", h), ("a.c", c)];
+
+        let dir = workspace(&files, &[&["a.h", "a.c"]]);
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 4);
+
+        // Static class members are still public
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "only_public = true");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        let mut names: Vec<&str> = report.mismatches.iter().map(|m| m.function.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["S::make", "add"]);
+    }
+
+    #[test]
+    fn mixed_marker_blocks_are_compared_line_by_line()
+    {
+        // One block mixing '//' and '/* */' lines
+        let h = "// Adds a and b\n/* Overflow wraps */\nint add(int a, int b);\n";
+        let c = "// Adds a and b\n/* Overflow saturates */\nint add(int a, int b) {}\n";
+        let dir = workspace(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]]);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].line, "/* Overflow wraps */");
+        assert_eq!(report.mismatches[0].kind, MismatchKind::Differs);
+
+        // With the auto doc style, the markers of each line are stripped according to its own style
+        let c = "/** Adds a and b */\n// Overflow wraps\nint add(int a, int b) {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "doc_style = \"auto\"");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let c = "/**\n * Adds a and b\n */\n// Overflow saturates\nint add(int a, int b) {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "doc_style = \"auto\"");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].line, "/* Overflow wraps */");
+    }
+
+    #[test]
+    fn ignore_tag_order_compares_tag_sections_in_any_order()
+    {
+        let h = "/**\n * Adds a and b.\n *\n * @param a First summand,\n *        may be negative\n * @param b Second summand\n * @return The sum\n */\nint add(int a, int b);\n";
+        let c = "/**\n * Adds a and b.\n *\n * @return The sum\n * @param b Second summand\n * @param a First summand,\n *        may be negative\n */\nint add(int a, int b) { return a + b; }\n";
+        let dir = workspace(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]]);
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "ignore_tag_order = true");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        // Sections keep their continuation lines and untagged text is still compared in order
+        let options = CompareOptions { ignore_tag_order: true, ..Default::default() };
+        let split = "/// Adds\n/// @param a First summand,\n/// @param b may be negative\n/// Second summand\n";
+        let merged = "/// Adds\n/// @param b Second summand\n/// @param a First summand,\n/// may be negative\n";
+        assert!(compare_docs(split, merged, &options).is_some());
+        assert!(compare_docs("/// Adds\n/// a and b\n", "/// a and b\n/// Adds\n", &options).is_some());
+
+        // A missing section is still reported
+        let c = "/**\n * Adds a and b.\n *\n * @return The sum\n * @param a First summand,\n *        may be negative\n */\nint add(int a, int b) { return a + b; }\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "ignore_tag_order = true");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].kind, MismatchKind::Differs);
+        assert_eq!(report.mismatches[0].doc_spans, vec![Some((0, 7)), Some((0, 6))]);
+    }
+
+    #[test]
+    fn reflow_insensitive_matches_reflowed_block_comments()
+    {
+        let h = "/**\n * Adds a and b and returns\n * the sum.\n */\nint add(int a, int b);\n";
+        let c = "/** Adds a and b\n *  and returns the   sum. */\nint add(int a, int b) {}\n";
+        let files = [("a.h", h), ("a.c", c)];
+
+        let dir = workspace(&files, &[&["a.h", "a.c"]]);
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "reflow_insensitive = true");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        // Different words are still reported
+        let c = "/** Adds a and b\n *  and returns the product. */\nint add(int a, int b) {}\n";
+        let dir = workspace_with_settings(&[("a.h", h), ("a.c", c)], &[&["a.h", "a.c"]], "reflow_insensitive = true");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+    }
+
+    #[test]
+    fn single_file_target_is_checked_against_itself()
+    {
+        let dir = tempdir().unwrap();
+        write_file(dir.path().join("include/foo.h"),
+                   "// Adds a and b\nint add(int a, int b);\n\n// Adds b to a\ninline int add(int a, int b) { return a + b; }\n");
+        write_file(dir.path().join("include/bar.h"), "// Unrelated\nint bar();\n");
+        write_file(dir.path().join("docwen.toml"), "[settings]\ntarget = \"include/foo.h\"\nmatch_extensions = [\"h\"]\n");
+        let toml = dir.path().join("docwen.toml");
+
+        docwen::toml_manager::update_toml(&toml).unwrap();
+        let docfig = docwen::docfig::Docfig::from_file(&toml).unwrap();
+        assert_eq!(docfig.file_groups.len(), 1);
+        assert_eq!(docfig.file_groups[0].files, vec![PathBuf::from("foo.h")]);
+
+        let report = docwen_check::check_with_options(&toml, &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].function.name, "add");
+        assert_eq!(report.mismatches[0].positions.len(), 2);
+    }
+
+    #[test]
+    fn specializations_are_compared_with_primary_template()
+    {
+        let h = "namespace ns\n{\n    // Converts value to a string\n    template<class T> std::string str(T value);\n\n\
+                 // Converts value to a string\n    template<> std::string str<int>(int value);\n\n\
+                 // Formats the bool as 'true' or 'false'\n    template<> std::string str<bool>(bool value);\n}\n";
+        let files = [("a.h", h), ("a.cpp", "")];
+
+        let dir = workspace(&files, &[&["a.h", "a.cpp"]]);
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.cpp"]], "template_specialization_inherits_docs = true");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].function.name, "str<bool>");
+        assert_eq!(report.mismatches[0].line, "// Converts value to a string");
+    }
+
+    #[test]
+    fn changed_config_only_checks_new_or_changed_groups()
+    {
+        let files = [("a.h", "// Old\nvoid a();\n"), ("a.cpp", "// New\nvoid a() {}\n"),
+                     ("b.h", "// Old\nvoid b();\n"), ("b.cpp", "// New\nvoid b() {}\n")];
+        let dir = workspace(&files, &[&["a.h", "a.cpp"], &["b.h", "b.cpp"]]);
+        let toml = dir.path().join("docwen.toml");
+        write_file(dir.path().join("old.toml"), &format!("[settings]\ntarget = \".\"\n\n{}", toml_group(&["a.h", "a.cpp"])));
+
+        assert_eq!(run_check!(&toml).len(), 2);
+
+        let options = CheckOptions { changed_config: Some(dir.path().join("old.toml")), ..Default::default() };
+        let report = docwen_check::check_with_options(&toml, &options).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].function.name, "b");
+    }
+
+    #[test]
+    fn merge_redeclarations_only_compares_declaration_with_definition()
+    {
+        let files = [("a.h", "// Adds a and b\nint add(int a, int b);\n"),
+                     ("a_fwd.h", "// Forward declaration\nint add(int a, int b);\n"),
+                     ("a.c", "// Adds a and b\nint add(int a, int b) { return a + b; }\n")];
+        let groups: &[&[&str]] = &[&["a.h", "a_fwd.h", "a.c"]];
+
+        let dir = workspace(&files, groups);
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let dir = workspace_with_settings(&files, groups, "merge_redeclarations = true");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let files = [files[0], files[1], ("a.c", "// Sums a and b\nint add(int a, int b) { return a + b; }\n")];
+        let dir = workspace_with_settings(&files, groups, "merge_redeclarations = true");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].positions.len(), 2);
+    }
+
+    #[test]
+    fn arity_fallback_compares_functions_with_drifting_param_types()
+    {
+        let files = [("a.h", "// Scales v\nvoid scale(vec_t v, float f);\n// Resets v\nvoid reset(vec_t v);\n"),
+                     ("a.c", "// Scales v by f\nvoid scale(struct vec *v, float f) {}\n// Resets v\nvoid reset(struct vec *v, int n) {}\n")];
+        let groups: &[&[&str]] = &[&["a.h", "a.c"]];
+
+        let dir = workspace(&files, groups);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert!(report.mismatches.is_empty() && report.warnings.is_empty());
+
+        let dir = workspace_with_settings(&files, groups, "fallback_identity = \"arity\"");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].function.name, "scale");
+        assert_eq!(report.mismatches[0].positions.len(), 2);
+        assert_eq!(report.warnings, vec![Warning::TypeDrift {
+            function: "scale".into(),
+            signatures: vec!["(vec_t v, float f)".into(), "(struct vec *v, float f)".into()]
+        }]);
+    }
+
+    #[test]
+    fn platform_defines_select_one_branch()
+    {
+        let h = "#ifdef _WIN32\n// Opens a handle\nint open_file(int h);\n#else\n// Opens a descriptor\nint open_file(int h);\n#endif\n";
+        let files = [("a.h", h), ("a.c", "// Opens a descriptor\nint open_file(int h) { return h; }\n")];
+        let groups: &[&[&str]] = &[&["a.h", "a.c"]];
+
+        let dir = workspace(&files, groups);
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let dir = workspace_with_settings(&files, groups, "platform_defines = [\"__linux__\"]");
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let dir = workspace_with_settings(&files, groups, "platform_defines = [\"_WIN32\"]");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].doc_rows, vec![1, 0]);
+    }
+
+    #[test]
+    fn pairing_manifest_groups_are_checked_with_filegroups()
+    {
+        let files = [("include/vec.h", "// Adds two vectors\nvec add(vec a, vec b);\n"),
+                     ("bindings/vec_py.h", "// Adds vectors\nvec add(vec a, vec b);\n")];
+        let json = r#"[{"name": "vec", "files": ["bindings/vec_py.h", "include/vec.h"], "canonical": "include/vec.h"}]"#;
+        let dir = workspace_with_settings(&files, &[], "pairing_manifest = \"pairs.json\"");
+        write_file(dir.path().join("pairs.json"), json);
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].line, "// Adds two vectors", "The canonical file is checked first");
+        assert_eq!(report.groups[0].name, "vec");
+    }
+
+    #[test]
+    fn files_above_max_file_size_are_skipped_with_a_warning()
+    {
+        let big = format!("// Doc B\nint foo();\n{}", "int filler();\n".repeat(100));
+        let files = [("a.h", "// Doc A\nint foo();\n"), ("a.c", "// Doc A\nint foo() {}\n"), ("a_gen.h", big.as_str())];
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c", "a_gen.h"]], "max_file_size = 1000");
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert!(report.mismatches.is_empty());
+        assert_eq!(report.groups[0].files, 2);
+        assert_eq!(report.groups[0].shared, 1, "The smaller files are still parsed");
+        assert_eq!(report.warnings, vec![Warning::FileTooLarge {
+            path: normalize_path(dir.path().join("a_gen.h")),
+            size: big.len() as u64,
+            limit: 1000
+        }]);
+    }
+
+    #[test]
+    fn non_doc_markers_skip_todo_lines()
+    {
+        let files = [("a.h", "// Adds a and b\n// TODO: handle overflow\nint add(int a, int b);\n"),
+                     ("a.c", "// Adds a and b\nint add(int a, int b) { return a + b; }\n")];
+        let dir = workspace(&files, &[&["a.h", "a.c"]]);
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], "non_doc_markers = []");
+        assert_eq!(run_check!(dir.path().join("docwen.toml")).len(), 1);
+
+        let rules = DocRules::default();
+        assert!(rules.is_non_doc("/* FIXME */") && rules.is_non_doc("/// XXX(me): later"));
+        assert!(!rules.is_non_doc("// TODOs are tracked elsewhere") && !rules.is_non_doc("// Adds a TODO"));
+    }
+
+    #[test]
+    fn struct_mode_compares_struct_definitions()
+    {
+        let settings = "mode = \"MATCH_STRUCT_DOCS\"";
+        let files = [("a.h", "// Forward declared\nstruct Vec;\n// A 2D vector\nstruct Vec { int x, y; };\n// A color\ntypedef struct { int r, g, b; } Color;\n"),
+                     ("a_impl.h", "// A vector\nstruct Vec { int x, y; };\n// A color\ntypedef struct { int r, g, b; } Color;\n// Adds vectors\nvoid add(struct Vec a);\n"),
+                     ("a.c", "// Sums vectors\nvoid add(struct Vec a) {}\n")];
+        let dir = workspace_with_settings(&files, &[&["a.h", "a_impl.h", "a.c"]], settings);
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1, "Only the struct definitions are compared: {:?}", report.mismatches);
+        assert_eq!(report.mismatches[0].function.name, "Vec");
+        assert_eq!(report.mismatches[0].doc_rows, vec![2, 0]);
+        assert_eq!(report.groups[0].functions, 2);
+    }
+
+    #[test]
+    fn enum_mode_compares_enum_and_enumerator_docs()
+    {
+        let settings = "mode = \"MATCH_ENUM_DOCS\"";
+        let files = [("a.h", "// A color\nenum class Color\n{\n    Red, ///< The color red\n    // The color green\n    Green,\n    Blue /**< The color blue */\n};\n"),
+                     ("a_impl.h", "// A color\nenum class Color\n{\n    Red = 0, ///< Red\n    // The color green\n    Green = 1,\n    Blue = 2 /**< The color blue */\n};\n")];
+        let dir = workspace_with_settings(&files, &[&["a.h", "a_impl.h"]], settings);
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1, "{:?}", report.mismatches);
+        assert_eq!(report.mismatches[0].function.name, "Color::Red");
+        assert_eq!(report.mismatches[0].line, "Red: ///< The color red");
+        assert_eq!(report.mismatches[0].doc_rows, vec![3, 3]);
+        assert_eq!(report.groups[0].functions, 4);
+
+        let files = [("a.h", "// Log levels\ntypedef enum { LOW, HIGH } Level;\n"), ("a.c", "// Levels\ntypedef enum { LOW, HIGH } Level;\n")];
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], settings);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        let mut lines: Vec<&str> = report.mismatches.iter().map(|m| m.line.as_str()).collect();
+        lines.sort();
+        assert_eq!(lines, vec!["// Log levels", "HIGH: // Log levels", "LOW: // Log levels"]);
+    }
+
+    #[test]
+    fn enum_mode_reports_plain_trailing_comments_once()
+    {
+        let settings = "mode = \"MATCH_ENUM_DOCS\"";
+        let files = [("a.h", "enum Color\n{\n    Red, /* the red one */\n    Green\n};\n"),
+                     ("b.h", "enum Color\n{\n    Red,\n    Green\n};\n")];
+        let dir = workspace_with_settings(&files, &[&["a.h", "b.h"]], settings);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1, "{:?}", report.mismatches);
+        assert_eq!(report.mismatches[0].function.name, "Color::Red");
+        assert_eq!(report.mismatches[0].line, "Red: /* the red one */");
+    }
+
+    #[test]
+    fn several_modes_are_checked_in_one_run()
+    {
+        let files = [("a.h", "// A vector\nstruct Vec { int x; };\n// Adds a and b\nint add(int a, int b);\n"),
+                     ("a.c", "// A 2D vector\nstruct Vec { int x; };\n// Sums a and b\nint add(int a, int b) { return a + b; }\n")];
+        let settings = "mode = [\"MATCH_FUNCTION_DOCS\", \"MATCH_STRUCT_DOCS\"]";
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], settings);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        let names: Vec<&str> = report.mismatches.iter().map(|m| m.function.name.as_str()).collect();
+        assert_eq!(names, vec!["add", "Vec"]);
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!((report.groups[0].functions, report.groups[0].mismatches), (2, 2));
+
+        // Both function modes flag the same line at the same positions
+        let settings = "mode = [\"MATCH_FUNCTION_DOCS\", \"MATCH_FUNCTION_DOCS_UNQUALIFIED\"]";
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], settings);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1, "{:?}", report.mismatches);
+        assert_eq!(report.mismatches[0].line, "// Adds a and b");
+
+        // Also for namespaced functions, which the unqualified mode names without their namespace
+        let files = [("a.h", "namespace m\n{\n// Adds a and b\nint add(int a, int b);\n}\n"),
+                     ("a.c", "namespace m\n{\n// Sums a and b\nint add(int a, int b) { return a + b; }\n}\n")];
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], settings);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1, "{:?}", report.mismatches);
+        assert_eq!(report.groups[0].mismatches, 1);
+    }
+
+    #[test]
+    fn require_doc_marker_skips_ordinary_comments()
+    {
+        let settings = "require_doc_marker = \"///\"";
+        let files = [("a.h", "/// Adds a and b\n// note: header only\nint add(int a, int b);\n"),
+                     ("a.c", "/// Adds a and b\nint add(int a, int b) { return a + b; }\n")];
+        let dir = workspace(&files, &[&["a.h", "a.c"]]);
+        assert!(!run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], settings);
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let files = [files[0], ("a.c", "/// Sums a and b\nint add(int a, int b) { return a + b; }\n")];
+        let dir = workspace_with_settings(&files, &[&["a.h", "a.c"]], settings);
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].kind, MismatchKind::Differs);
+    }
+
+    #[test]
+    fn ignore_generated_skips_files_with_generated_marker()
+    {
+        let files = [("a.h", "// @generated by protoc\n\n// Parses a\nint parse(int a);\n"),
+                     ("a.c", "// Parses the input\nint parse(int a) { return a; }\n")];
+        let dir = workspace(&files, &[&["a.h", "a.c"]]);
+        let toml = dir.path().join("docwen.toml");
+        assert_eq!(run_check!(&toml).len(), 1);
+
+        let options = CheckOptions { ignore_generated: true, ..Default::default() };
+        let report = docwen_check::check_with_options(&toml, &options).unwrap();
+        assert!(report.mismatches.is_empty());
+        assert_eq!(report.groups[0].files, 1);
+
+        write_file(&toml, "[settings]\ntarget = \".\"\nmatch_extensions = [\"h\", \"c\"]\n");
+        let options = docwen::toml_manager::UpdateOptions { ignore_generated: true, ..Default::default() };
+        docwen::toml_manager::update_toml_with(&toml, &options).unwrap();
+        assert!(docwen::docfig::Docfig::from_file(&toml).unwrap().file_groups.is_empty());
+        docwen::toml_manager::update_toml(&toml).unwrap();
+        assert_eq!(docwen::docfig::Docfig::from_file(&toml).unwrap().file_groups.len(), 1);
+    }
+
+    /// Returns CompareOptions with the given DocRules.
+    fn compare_with(rules: DocRules) -> CompareOptions
+    {
+        CompareOptions { rules, ..Default::default() }
+    }
+
+    #[test]
+    fn compare_docs_reports_first_differing_or_missing_line()
+    {
+        let options = CompareOptions::default();
+        assert_eq!(compare_docs("// Adds a\n// and b", "// Adds a\n// and b", &options), None);
+        assert_eq!(compare_docs("// Adds a\n// and b", "// Adds a\n// and c", &options), Some(DocDiff {
+            kind: MismatchKind::Differs, index: 0, a: Some("// and b".into()), b: Some("// and c".into())
+        }));
+        assert_eq!(compare_docs("// Adds a\n// and b", "// and b", &options), Some(DocDiff {
+            kind: MismatchKind::Missing, index: 1, a: Some("// Adds a".into()), b: None
+        }));
+        assert_eq!(compare_docs("int x;\n\n// Adds", "// Adds", &options), None);
+    }
+
+    #[test]
+    fn compare_docs_applies_each_normalization_option()
+    {
+        let rules = DocRules { trim_doc_block: true, ..Default::default() };
+        assert!(compare_docs("/**\n * Adds\n */", "/** Adds */", &CompareOptions::default()).is_some());
+        assert_eq!(compare_docs("/**\n * Adds\n */", "/**\n * Adds\n", &compare_with(rules)), None);
+
+        let rules = DocRules { comment_style_agnostic: true, ..Default::default() };
+        assert_eq!(compare_docs("/*! Adds */", "/** Adds */", &compare_with(rules)), None);
+
+        let auto = CompareOptions { auto_style: true, rules: DocRules { trim_doc_block: true, ..Default::default() }, ..Default::default() };
+        assert_eq!(compare_docs("/**\n * Adds\n */", "/// Adds", &auto), None);
+        assert!(compare_docs("/**\n * Adds\n */", "/// Sums", &auto).is_some());
+
+        let reflow = CompareOptions { reflow_insensitive: true, ..Default::default() };
+        assert_eq!(compare_docs("/* Adds a\n * and b */", "/* Adds a and\n * b */", &reflow), None);
+        assert!(compare_docs("/* Adds a\n * and b */", "/* Adds a and\n * b */", &CompareOptions::default()).is_some());
+
+        let rules = DocRules { case_insensitive_tags: vec!["brief".into()], ..Default::default() };
+        assert_eq!(compare_docs("// @brief Adds", "// @brief adds", &compare_with(rules)), None);
+
+        let rules = DocRules { suppression_prefixes: vec!["// NOLINT".into()], ..Default::default() };
+        assert_eq!(compare_docs("// Adds\n// NOLINTNEXTLINE", "// Adds", &compare_with(rules)), None);
+
+        assert_eq!(compare_docs("// @author Me\n// Adds", "// Adds", &CompareOptions::default()), None);
+
+        let rules = DocRules { max_blank_lines: 1, ..Default::default() };
+        assert_eq!(compare_docs("// Adds\n\n// a and b", "// Adds\n// a and b", &compare_with(rules)), None);
+
+        let rules = DocRules { doc_marker: Some("///".into()), ..Default::default() };
+        assert_eq!(compare_docs("/// Adds\n// note", "/// Adds", &compare_with(rules)), None);
+    }
+
+    #[test]
+    fn agreeing_docs_are_compared_with_doc_reference()
+    {
+        let files = [("a.h", "namespace ns\n{\n// Adds a and b\nint add(int a, int b);\n\n// Resets\nvoid reset();\n}\n"),
+                     ("a.c", "namespace ns\n{\n// Adds a and b\nint add(int a, int b) { return a + b; }\n\n// Resets\nvoid reset() {}\n}\n"),
+                     ("api.docref", "[ns::add(int a, int b)]\n// Returns the sum of a and b\n\n[ns::reset()]\n// Resets\n")];
+        let groups: &[&[&str]] = &[&["a.h", "a.c"]];
+
+        let dir = workspace(&files, groups);
+        assert!(run_check!(dir.path().join("docwen.toml")).is_empty());
+
+        let dir = workspace_with_settings(&files, groups, "doc_reference = \"api.docref\"");
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        let mismatch = &report.mismatches[0];
+        assert_eq!(mismatch.kind, MismatchKind::Reference);
+        assert_eq!(mismatch.function.name, "ns::add");
+        assert_eq!(mismatch.line, "// Adds a and b");
+        assert_eq!(mismatch.positions.len(), 2);
+        assert_eq!(mismatch.doc_rows, vec![2, 2]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_duplicates_are_counted_once()
+    {
+        let files = [("include/foo.h", "// Adds a and b\nint add(int a, int b);\n"),
+                     ("src/foo.c", "// Adds a and b\nint add(int a, int b) { return a + b; }\n")];
+        let dir = workspace(&files, &[&["include/foo.h", "src/foo.c", "compat/foo.h"]]);
+        fs::create_dir(dir.path().join("compat")).unwrap();
+        std::os::unix::fs::symlink("../include/foo.h", dir.path().join("compat/foo.h")).unwrap();
+        let toml = dir.path().join("docwen.toml");
+
+        let report = docwen_check::check_with_options(&toml, &CheckOptions::default()).unwrap();
+        assert!(report.mismatches.is_empty());
+        assert_eq!(report.groups[0].files, 2);
+        assert_eq!(report.groups[0].shared, 1);
+
+        let paths = ["include/foo.h", "compat/foo.h", "src/foo.c"].map(|f| dir.path().join(f));
+        let map = docwen::c_parse::find_function_positions(paths, true).unwrap();
+        assert_eq!(map.values().next().unwrap().len(), 2);
+
+        for (follow, expected) in [(false, vec!["include/foo.h", "src/foo.c"]), (true, vec!["compat/foo.h", "src/foo.c"])]
+        {
+            write_file(&toml, &format!("[settings]\ntarget = \".\"\nmatch_extensions = [\"h\", \"c\"]\nfollow_symlinks = {follow}\n"));
+            docwen::toml_manager::update_toml(&toml).unwrap();
+            let docfig = docwen::docfig::Docfig::from_file(&toml).unwrap();
+            assert_eq!(docfig.file_groups.len(), 1);
+            let mut files = docfig.file_groups[0].files.clone();
+            files.sort();
+            assert_eq!(files, expected.iter().map(PathBuf::from).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn setting_overrides_change_the_check()
+    {
+        let files = [("a.h", "/**\n * Adds a and b\n */\nint add(int a, int b);\n"),
+                     ("a.c", "/** Adds a and b */\nint add(int a, int b) { return a + b; }\n")];
+        let dir = workspace(&files, &[&["a.h", "a.c"]]);
+        let toml = dir.path().join("docwen.toml");
+        assert_eq!(run_check!(&toml).len(), 1);
+
+        let options = CheckOptions { overrides: vec!["doc_style=auto".into()], ..Default::default() };
+        assert!(docwen_check::check_with_options(&toml, &options).unwrap().mismatches.is_empty());
+
+        let options = CheckOptions { overrides: vec!["mode=MATCH_MACRO_DOCS".into()], ..Default::default() };
+        let report = docwen_check::check_with_options(&toml, &options).unwrap();
+        assert!(report.mismatches.is_empty());
+        assert_eq!(report.groups[0].functions, 0);
+    }
+}
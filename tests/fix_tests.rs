@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod fix_tests
+{
+    use std::fs;
+    use tempfile::tempdir;
+    use docwen::docfig::Docfig;
+    use docwen::docwen_check::{check_with_options, CheckOptions};
+    use docwen::fix::{apply_fixes, compute_fixes};
+
+    const HEADER: &str = "namespace n\n{\n    /**\n     * Does foo\n     */\n    int foo();\n\n    // Does bar\n    int bar();\n}\n";
+    const SOURCE: &str = "namespace n\n{\n// Old foo\nint foo() {}\n\nint bar() {}\n}\n";
+
+    /// Creates a workspace with a header and source file in one group and returns its directory.
+    fn workspace() -> tempfile::TempDir
+    {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.h"), HEADER).unwrap();
+        fs::write(dir.path().join("a.cpp"), SOURCE).unwrap();
+        fs::write(dir.path().join("docwen.toml"),
+                  "[settings]\ntarget = \".\"\n\n[[filegroup]]\nname = \"a\"\nfiles = [\"a.h\", \"a.cpp\"]\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn preview_prints_diff_without_changing_files()
+    {
+        let dir = workspace();
+        let toml_path = dir.path().join("docwen.toml");
+        let report = check_with_options(&toml_path, &CheckOptions::default()).unwrap();
+        let settings = Docfig::from_file(&toml_path).unwrap().settings;
+
+        let fixes = compute_fixes(&report.mismatches, &settings).unwrap();
+        let diff: String = fixes.iter().map(|f| f.unified_diff(&report.target)).collect();
+
+        assert_eq!(diff, "--- a/a.cpp\n+++ b/a.cpp\n@@ -1,7 +1,10 @@\n namespace n\n {\n-// Old foo\n\
+                          +/**\n+ * Does foo\n+ */\n int foo() {}\n \n+// Does bar\n int bar() {}\n }\n");
+        assert_eq!(fs::read_to_string(dir.path().join("a.h")).unwrap(), HEADER);
+        assert_eq!(fs::read_to_string(dir.path().join("a.cpp")).unwrap(), SOURCE);
+    }
+
+    #[test]
+    fn applied_fixes_resolve_mismatches()
+    {
+        let dir = workspace();
+        let toml_path = dir.path().join("docwen.toml");
+        let report = check_with_options(&toml_path, &CheckOptions::default()).unwrap();
+        assert_eq!(report.mismatches.len(), 2);
+
+        let settings = Docfig::from_file(&toml_path).unwrap().settings;
+        apply_fixes(&compute_fixes(&report.mismatches, &settings).unwrap()).unwrap();
+
+        assert_eq!(fs::read_to_string(dir.path().join("a.h")).unwrap(), HEADER);
+        assert!(check_with_options(&toml_path, &CheckOptions::default()).unwrap().mismatches.is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod lsp_tests
+{
+    use std::fs;
+    use std::io::Cursor;
+    use serde_json::{json, Value};
+    use tempfile::tempdir;
+    use docwen::lsp::{self, path_from_uri, uri_from_path};
+
+    /// Returns the given messages as client input with 'Content-Length' headers.
+    fn input(messages: &[Value]) -> Cursor<Vec<u8>>
+    {
+        let mut input = Vec::new();
+        messages.iter().for_each(|m| lsp::write_message(&mut input, m).unwrap());
+        Cursor::new(input)
+    }
+
+    /// Runs the server on the given messages and returns all messages it sent.
+    fn responses(messages: &[Value]) -> Vec<Value>
+    {
+        let mut output = Vec::new();
+        lsp::run(input(messages), &mut output).unwrap();
+        let mut output = Cursor::new(output);
+        let mut responses = Vec::new();
+        while let Some(message) = lsp::read_message(&mut output).unwrap() { responses.push(message); }
+        responses
+    }
+
+    #[test]
+    fn uris_and_paths_round_trip()
+    {
+        let path = std::path::Path::new("/tmp/my dir/a#b.h");
+        let uri = uri_from_path(path);
+        assert_eq!(uri, "file:///tmp/my%20dir/a%23b.h");
+        assert_eq!(path_from_uri(&uri).unwrap(), path);
+        assert!(path_from_uri("untitled:Untitled-1").is_none());
+    }
+
+    #[test]
+    fn did_save_publishes_mismatches_as_diagnostics()
+    {
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::write(root.join("a.h"), "// Doc A\nint foo();\n").unwrap();
+        fs::write(root.join("a.c"), "#include \"a.h\"\n\n// Doc B\nint foo() {}\n").unwrap();
+        fs::write(root.join("docwen.toml"),
+                  "[settings]\ntarget = \".\"\n\n[[filegroup]]\nname = \"a\"\nfiles = [\"a.h\", \"a.c\"]\n").unwrap();
+        let h_uri = uri_from_path(&root.join("a.h"));
+        let c_uri = uri_from_path(&root.join("a.c"));
+
+        let save = |text: &str| json!({"jsonrpc": "2.0", "method": "textDocument/didSave",
+            "params": {"textDocument": {"uri": c_uri}, "text": text}});
+        let responses = responses(&[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+            json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+            save("#include \"a.h\"\n\n// Doc B\nint foo() {}\n"),
+            save("#include \"a.h\"\n\n// Doc A\nint foo() {}\n"),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {}}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "shutdown"}),
+            json!({"jsonrpc": "2.0", "method": "exit"}),
+        ]);
+
+        // INITIALIZE
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"]["capabilities"]["textDocumentSync"]["save"]["includeText"], true);
+
+        // FIRST SAVE: ONE DIAGNOSTIC PER FILE AT THE DOC ROW
+        let published: Vec<&Value> = responses[1..3].iter().map(|r| &r["params"]).collect();
+        assert!(responses[1..3].iter().all(|r| r["method"] == "textDocument/publishDiagnostics"));
+        let h = published.iter().find(|p| p["uri"] == h_uri.as_str()).unwrap();
+        let c = published.iter().find(|p| p["uri"] == c_uri.as_str()).unwrap();
+        assert_eq!(h["diagnostics"][0]["range"]["start"]["line"], 0);
+        assert_eq!(c["diagnostics"][0]["range"]["start"]["line"], 2);
+        assert_eq!(c["diagnostics"][0]["source"], "docwen");
+        assert!(c["diagnostics"][0]["message"].as_str().unwrap().contains("foo"));
+
+        // SECOND SAVE: FIXED FILES ARE CLEARED
+        let cleared: Vec<&Value> = responses[3..5].iter().map(|r| &r["params"]).collect();
+        assert!(cleared.iter().all(|p| p["diagnostics"] == json!([])));
+        assert!(cleared.iter().any(|p| p["uri"] == h_uri.as_str()) && cleared.iter().any(|p| p["uri"] == c_uri.as_str()));
+
+        // UNSUPPORTED REQUEST AND SHUTDOWN
+        assert_eq!(responses[5]["id"], 2);
+        assert_eq!(responses[5]["error"]["code"], -32601);
+        assert_eq!(responses[6]["id"], 3);
+        assert_eq!(responses[6]["result"], Value::Null);
+        assert_eq!(responses.len(), 7);
+    }
+
+    #[test]
+    fn did_save_keeps_diagnostics_of_other_groups()
+    {
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::write(root.join("a.h"), "// Doc A\nint foo();\n").unwrap();
+        fs::write(root.join("a.c"), "// Doc B\nint foo() {}\n").unwrap();
+        fs::write(root.join("b.h"), "// Doc A\nint bar();\n").unwrap();
+        fs::write(root.join("b.c"), "// Doc B\nint bar() {}\n").unwrap();
+        fs::write(root.join("docwen.toml"), "[settings]\ntarget = \".\"\n\n[[filegroup]]\nname = \"a\"\nfiles = [\"a.h\", \"a.c\"]\n\n\
+                                              [[filegroup]]\nname = \"b\"\nfiles = [\"b.h\", \"b.c\"]\n").unwrap();
+
+        let save = |file: &str, text: &str| json!({"jsonrpc": "2.0", "method": "textDocument/didSave",
+            "params": {"textDocument": {"uri": uri_from_path(&root.join(file))}, "text": text}});
+        let responses = responses(&[
+            save("a.c", "// Doc B\nint foo() {}\n"),
+            save("b.c", "// Doc B\nint bar() {}\n"),
+            save("b.c", "// Doc A\nint bar() {}\n"),
+        ]);
+
+        // Each save only publishes the files of its own group
+        let published: Vec<(String, usize)> = responses.iter()
+            .map(|r| (r["params"]["uri"].as_str().unwrap().rsplit('/').next().unwrap().to_string(),
+                      r["params"]["diagnostics"].as_array().unwrap().len()))
+            .collect();
+        let expected = [("a.c", 1), ("a.h", 1), ("b.c", 1), ("b.h", 1), ("b.c", 0), ("b.h", 0)];
+        assert_eq!(published, expected.map(|(f, n)| (f.to_string(), n)));
+    }
+}
@@ -0,0 +1,217 @@
+#[cfg(test)]
+mod main_tests
+{
+    use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::path::Path;
+    use std::process::{Command, Output, Stdio};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    /// Runs the docwen binary with the given args.
+    fn docwen(args: &[&str]) -> Output
+    {
+        Command::new(env!("CARGO_BIN_EXE_docwen")).args(args).output().unwrap()
+    }
+
+    /// Writes a docwen.toml with a single group 'a.h', 'a.c' and the given file contents to the given directory.
+    fn write_group(dir: &Path, h: &str, c: &str)
+    {
+        fs::write(dir.join("a.h"), h).unwrap();
+        fs::write(dir.join("a.c"), c).unwrap();
+        fs::write(dir.join("docwen.toml"),
+                  "[settings]\ntarget = \".\"\n\n[[filegroup]]\nname = \"a\"\nfiles = [\"a.h\", \"a.c\"]\n").unwrap();
+    }
+
+    #[test]
+    fn assert_clean_prints_nothing_and_sets_exit_code()
+    {
+        let dir = tempdir().unwrap();
+        let toml = dir.path().join("docwen.toml");
+        let toml = toml.to_str().unwrap();
+
+        write_group(dir.path(), "// Doc A\nint foo();\n", "// Doc A\nint foo() {}\n");
+        let output = docwen(&["check", "--assert-clean", toml]);
+        assert_eq!(output.status.code(), Some(0));
+        assert!(output.stdout.is_empty() && output.stderr.is_empty());
+
+        write_group(dir.path(), "// Doc A\nint foo();\n", "// Doc B\nint foo() {}\n");
+        let output = docwen(&["check", "--assert-clean", toml]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty() && output.stderr.is_empty());
+
+        let output = docwen(&["check", "--assert-clean", dir.path().join("missing.toml").to_str().unwrap()]);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(output.stdout.is_empty() && output.stderr.is_empty());
+    }
+
+    #[test]
+    fn hints_follow_mismatches_unless_disabled()
+    {
+        let dir = tempdir().unwrap();
+        let toml = dir.path().join("docwen.toml");
+        let toml = toml.to_str().unwrap();
+
+        write_group(dir.path(), "// Doc A\nint foo();\n", "// Doc B\nint foo() {}\n");
+        let output = docwen(&["check", toml]);
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("Hint: Run 'docwen check --fix --dry-run'"), "{stderr}");
+        assert!(!String::from_utf8_lossy(&output.stdout).contains("Hint:"));
+
+        let output = docwen(&["check", "--no-hints", toml]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stderr.is_empty());
+
+        write_group(dir.path(), "// Doc A\nint foo();\n", "// Doc A\nint foo() {}\n");
+        assert!(docwen(&["check", toml]).stderr.is_empty());
+    }
+
+    #[test]
+    fn stats_json_reports_coverage_of_shared_functions()
+    {
+        let dir = tempdir().unwrap();
+        let toml = dir.path().join("docwen.toml");
+        write_group(dir.path(), "// Doc A\nint foo();\nint bar();\nint baz();\n", "// Doc A\nint foo() {}\n");
+
+        let output = docwen(&["check", "--stats-json", toml.to_str().unwrap()]);
+        assert_eq!(output.status.code(), Some(0));
+        let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(stats["files"], 2);
+        assert_eq!(stats["functions"], 3);
+        assert_eq!(stats["shared"], 1);
+        assert_eq!(stats["singletons"], 2);
+        assert_eq!(stats["coverage_pct"], 33.33);
+        assert_eq!(stats["per_group"][0]["group"], "a");
+        assert_eq!(stats["per_group"][0]["coverage_pct"], 33.33);
+    }
+
+    #[test]
+    fn update_check_fails_for_stale_config_without_writing()
+    {
+        let dir = tempdir().unwrap();
+        let toml = dir.path().join("docwen.toml");
+        let toml = toml.to_str().unwrap();
+        write_group(dir.path(), "int foo();\n", "int foo() {}\n");
+        fs::write(dir.path().join("docwen.toml"),
+                  "[settings]\ntarget = \".\"\nmatch_extensions = [\"h\", \"c\"]\n\n[[filegroup]]\nname = \"a\"\nfiles = [\"a.h\", \"a.c\"]\n").unwrap();
+        let output = docwen(&["update", "--check", toml]);
+        assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+
+        fs::write(dir.path().join("b.h"), "int bar();\n").unwrap();
+        fs::write(dir.path().join("b.c"), "int bar() {}\n").unwrap();
+        let before = fs::read_to_string(toml).unwrap();
+        let output = docwen(&["update", "--check", toml]);
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "+ filegroup 'b': \"b.c\", \"b.h\"\n");
+        assert_eq!(fs::read_to_string(toml).unwrap(), before);
+
+        assert_eq!(docwen(&["update", toml]).status.code(), Some(0));
+        assert_eq!(docwen(&["update", "--check", toml]).status.code(), Some(0));
+    }
+
+    #[test]
+    fn update_json_lists_added_groups_and_files()
+    {
+        let dir = tempdir().unwrap();
+        let toml = dir.path().join("docwen.toml");
+        let toml = toml.to_str().unwrap();
+        write_group(dir.path(), "int foo();\n", "int foo() {}\n");
+        fs::write(dir.path().join("docwen.toml"),
+                  "[settings]\ntarget = \".\"\nmatch_extensions = [\"h\", \"c\", \"cpp\"]\n\n[[filegroup]]\nname = \"a\"\nfiles = [\"a.h\", \"a.c\"]\n").unwrap();
+        fs::write(dir.path().join("a.cpp"), "int foo() {}\n").unwrap();
+        fs::write(dir.path().join("b.h"), "int bar();\n").unwrap();
+        fs::write(dir.path().join("b.c"), "int bar() {}\n").unwrap();
+
+        let output = docwen(&["update", "--format", "json", toml]);
+        assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["added_groups"], serde_json::json!([{"name": "b", "files": ["b.c", "b.h"]}]));
+        assert_eq!(json["changed_groups"], serde_json::json!([{"name": "a", "added_files": ["a.cpp"], "removed_files": []}]));
+        assert!(fs::read_to_string(toml).unwrap().contains("a.cpp"));
+
+        let output = docwen(&["update", "--format", "json", toml]);
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json, serde_json::json!({"added_groups": [], "changed_groups": []}));
+    }
+
+    #[test]
+    fn fix_fails_if_some_mismatches_are_not_fixed()
+    {
+        let dir = tempdir().unwrap();
+        let toml = dir.path().join("docwen.toml");
+        let toml = toml.to_str().unwrap();
+
+        write_group(dir.path(), "// Doc A\nint foo();\n", "// Doc B\nint foo() {}\n");
+        let output = docwen(&["check", "--fix", toml]);
+        assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(docwen(&["check", toml]).status.code(), Some(0));
+
+        // Docs sharing a line with code are never replaced
+        write_group(dir.path(), "// Doc A\nint foo();\n", "int x; /* Doc B */\nint foo() {}\n");
+        let output = docwen(&["check", "--fix", toml]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Not fixed: "));
+    }
+
+    #[test]
+    fn parse_only_reports_files_with_syntax_errors()
+    {
+        let dir = tempdir().unwrap();
+        let toml = dir.path().join("docwen.toml");
+        let toml = toml.to_str().unwrap();
+        write_group(dir.path(), "// Doc A\nint foo();\n", "// Doc B\nint foo() {}\n");
+        let output = docwen(&["check", "--parse-only", toml]);
+        assert_eq!(output.status.code(), Some(0), "Docs are not compared: {}", String::from_utf8_lossy(&output.stdout));
+
+        write_group(dir.path(), "// Doc A\nint foo();\n", "// Doc A\nint foo() {\n    return (1 +;\n}\n");
+        let output = docwen(&["check", "--parse-only", toml]);
+        assert_eq!(output.status.code(), Some(1));
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.lines().count(), 1, "Only the malformed file is reported: {stdout}");
+        assert!(stdout.starts_with("a.c:2:") && stdout.trim_end().ends_with(": parse error"), "Output was: {stdout}");
+    }
+
+    #[test]
+    fn watch_with_clear_checks_again_after_a_change()
+    {
+        let dir = tempdir().unwrap();
+        let toml = dir.path().join("docwen.toml");
+        write_group(dir.path(), "// Doc A\nint foo();\n", "// Doc B\nint foo() {}\n");
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_docwen"))
+            .args(["check", "--watch", "--clear", toml.to_str().unwrap()])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || BufReader::new(stdout).lines().map_while(Result::ok).for_each(|l| { let _ = sender.send(l); }));
+
+        // Collects the lines of the next run (from its header up to the given last line)
+        let next_run = |last: &str| {
+            let mut lines = Vec::new();
+            while let Ok(line) = receiver.recv_timeout(Duration::from_secs(10))
+            {
+                let done = line.starts_with(last);
+                lines.push(line);
+                if done { break; }
+            }
+            lines
+        };
+
+        let first = next_run("-> [");
+        fs::write(dir.path().join("a.c"), "// Doc A\nint foo() { return 0; }\n").unwrap();
+        let second = next_run("Found no mismatches!");
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert!(first[0].starts_with("=== docwen check at ") && first[0].ends_with(" UTC ==="), "{first:?}");
+        assert!(first[1].starts_with("Mismatch in: \"// Doc"), "{first:?}");
+        assert_eq!(second.iter().filter(|l| l.starts_with("=== docwen check at ")).count(), 1, "{second:?}");
+        assert_eq!(second.last().map(String::as_str), Some("Found no mismatches!"));
+        assert!(first.iter().chain(&second).all(|l| !l.contains('\x1b')), "Output is no terminal, so nothing is cleared");
+    }
+}
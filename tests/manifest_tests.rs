@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod manifest_tests
+{
+    use std::path::PathBuf;
+    use docwen::manifest::PairingManifest;
+
+    #[test]
+    fn json_manifest_groups_put_canonical_file_first()
+    {
+        let json = r#"[
+            {"name": "vec", "files": ["bindings/vec_py.h", "include/vec.h"], "canonical": "include/vec.h"},
+            {"name": "mat", "files": ["include/mat.h", "bindings/mat_py.h"]}
+        ]"#;
+        let manifest = PairingManifest::parse_json(json).unwrap();
+        // FileGroups only compare their names
+        let groups: Vec<(String, Vec<PathBuf>)> = manifest.file_groups().into_iter().map(|g| (g.name, g.files)).collect();
+        assert_eq!(groups, vec![
+            ("vec".to_string(), vec![PathBuf::from("include/vec.h"), PathBuf::from("bindings/vec_py.h")]),
+            ("mat".to_string(), vec![PathBuf::from("include/mat.h"), PathBuf::from("bindings/mat_py.h")]),
+        ]);
+
+        let err = PairingManifest::parse_json(r#"[{"name": "a", "files": ["a.h"], "canonical": "b.h"}]"#).unwrap_err();
+        assert!(err.to_string().contains("not one of its files"), "Error was: {err}");
+        assert!(PairingManifest::parse_json(r#"[{"name": "a", "files": []}, {"name": "a", "files": []}]"#).is_err());
+    }
+
+    #[test]
+    fn csv_manifest_merges_rows_of_a_group()
+    {
+        let csv = "# group,file[,canonical]\nvec,bindings/vec_py.h\nvec,include/vec.h,canonical\n\nmat,include/mat.h\n";
+        let manifest = PairingManifest::parse_csv(csv).unwrap();
+        assert_eq!(manifest.groups.len(), 2);
+        assert_eq!(manifest.groups[0].canonical, Some(PathBuf::from("include/vec.h")));
+        assert_eq!(manifest.file_groups()[0].files[0], PathBuf::from("include/vec.h"));
+
+        let err = PairingManifest::parse_csv("vec\n").unwrap_err();
+        assert!(err.to_string().contains("Line 1"), "Error was: {err}");
+        assert!(PairingManifest::parse_csv("vec,a.h,primary\n").is_err());
+        assert!(PairingManifest::parse_csv("vec,a.h,canonical\nvec,b.h,canonical\n").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod output_tests
+{
+    use std::path::PathBuf;
+    use docwen::docwen_check::{FilePosition, FunctionID, Mismatch, MismatchKind};
+    use docwen::docfig::Settings;
+    use docwen::docwen_check::CheckReport;
+    use docwen::output::{ndjson_line, pre_commit_lines, report_lines, xml_document, Template};
+
+    /// Creates a mismatch of 'foo' at the given (path, row, column) positions.
+    fn mismatch(positions: &[(&str, usize, usize)]) -> Mismatch
+    {
+        Mismatch {
+            function: FunctionID { name: "foo".into(), params: "(int a)".into() },
+            kind: MismatchKind::Differs,
+            line: "// some doc".into(),
+            positions: positions.iter()
+                .map(|(p, row, column)| FilePosition { path: PathBuf::from(p), row: *row, column: *column, is_definition: false, needs_declaration: false })
+                .collect(),
+            doc_rows: positions.iter().map(|(_, row, _)| row.saturating_sub(1)).collect(),
+            doc_spans: positions.iter().map(|(_, row, _)| row.checked_sub(1).map(|r| (r, r))).collect(),
+        }
+    }
+
+    #[test]
+    fn custom_template_renders_one_line_per_position()
+    {
+        let template = Template::parse("{path}:{row}:{col}:{doc_row} [{kind}] {func}: {line}").unwrap();
+        let m = mismatch(&[("project/src/a.h", 3, 0), ("project/src/a.c", 10, 4)]);
+
+        let lines = template.render(&m, PathBuf::from("project"));
+        let sep = std::path::MAIN_SEPARATOR;
+        assert_eq!(lines, vec![
+            format!("src{sep}a.h:3:0:2 [differs] foo: // some doc"),
+            format!("src{sep}a.c:10:4:9 [differs] foo: // some doc"),
+        ]);
+    }
+
+    #[test]
+    fn template_supports_escaped_braces()
+    {
+        let template = Template::parse("{{{row}}}").unwrap();
+        let m = mismatch(&[("a.c", 7, 0)]);
+        assert_eq!(template.render(&m, PathBuf::from("")), vec!["{7}".to_string()]);
+    }
+
+    #[test]
+    fn unknown_placeholder_is_an_error()
+    {
+        let err = Template::parse("{path}:{bogus}").unwrap_err();
+        assert!(err.to_string().contains("{bogus}"), "Error was: {err}");
+    }
+
+    #[test]
+    fn unmatched_closing_brace_is_an_error()
+    {
+        assert!(Template::parse("{row}}").is_err());
+    }
+
+    #[test]
+    fn unterminated_placeholder_is_an_error()
+    {
+        let err = Template::parse("{path").unwrap_err();
+        assert!(err.to_string().contains("Unterminated '{' in format template"), "Error was: {err}");
+    }
+
+    #[test]
+    fn report_lines_use_configured_messages()
+    {
+        let report = CheckReport { target: PathBuf::from("project"), display_base: PathBuf::from("project"), mismatches: vec![mismatch(&[("project/a.c", 3, 0)])], warnings: Vec::new(), groups: Vec::new() };
+        let settings = Settings { msg_mismatch_prefix: "Abweichung: ".into(), ..Default::default() };
+        let lines = report_lines(&report, &settings);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("Abweichung: \"// some doc\""), "Line was: {}", lines[0]);
+
+        let empty = CheckReport { target: PathBuf::from("project"), display_base: PathBuf::from("project"), mismatches: Vec::new(), warnings: Vec::new(), groups: Vec::new() };
+        assert_eq!(report_lines(&empty, &Settings::default()), vec!["Found no mismatches!".to_string()]);
+        let silent = Settings { msg_no_mismatch: String::new(), ..Default::default() };
+        assert!(report_lines(&empty, &silent).is_empty());
+    }
+
+    #[test]
+    fn ndjson_lines_are_independently_parseable()
+    {
+        let mut multi_line = mismatch(&[("project/a.h", 1, 0), ("project/a.c", 5, 2)]);
+        multi_line.line = "// \"quoted\"\n and more".into();
+        let output = [mismatch(&[("project/b.c", 3, 0)]), multi_line]
+            .iter()
+            .map(|m| ndjson_line(m, PathBuf::from("project")) + "\n")
+            .collect::<String>();
+
+        let parsed: Vec<serde_json::Value> = output.lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0]["func"], "foo");
+        assert_eq!(parsed[0]["kind"], "differs");
+        assert_eq!(parsed[1]["line"], "// \"quoted\"\n and more");
+        assert_eq!(parsed[1]["positions"][1]["row"], 5);
+        assert_eq!(parsed[1]["positions"][1]["doc_row"], 4);
+        assert_eq!(parsed[1]["positions"][1]["doc_span"], serde_json::json!([4, 4]));
+    }
+
+    #[test]
+    fn only_missing_hides_differs_mismatches()
+    {
+        let mut missing = mismatch(&[("project/b.c", 7, 0)]);
+        missing.kind = MismatchKind::Missing;
+        missing.line = "// only in b".into();
+        let mut report = CheckReport { target: PathBuf::from("project"), display_base: PathBuf::from("project"), mismatches: vec![mismatch(&[("project/a.c", 3, 0)]), missing], warnings: Vec::new(), groups: Vec::new() };
+
+        report.retain_kinds(&["missing".parse().unwrap()]);
+        let lines = report_lines(&report, &Settings::default());
+        assert!(lines.iter().all(|l| !l.contains("some doc")), "Lines were: {lines:?}");
+        assert!(lines.iter().any(|l| l.contains("only in b")), "Lines were: {lines:?}");
+
+        let err = "missing-doc".parse::<MismatchKind>().unwrap_err();
+        assert!(err.to_string().contains("differs, missing"), "Error was: {err}");
+    }
+
+    #[test]
+    fn pre_commit_prints_nothing_when_clean_and_one_line_per_mismatch_otherwise()
+    {
+        let clean = CheckReport { target: PathBuf::from("project"), display_base: PathBuf::from("project"), mismatches: Vec::new(), warnings: Vec::new(), groups: Vec::new() };
+        assert!(pre_commit_lines(&clean).unwrap().is_empty());
+
+        let mismatches = vec![mismatch(&[("project/a.h", 3, 0), ("project/a.c", 10, 4)]), mismatch(&[("project/b.h", 5, 2), ("project/b.c", 8, 0)])];
+        let report = CheckReport { target: PathBuf::from("project"), display_base: PathBuf::from("project"), mismatches, warnings: Vec::new(), groups: Vec::new() };
+        assert_eq!(pre_commit_lines(&report).unwrap(), vec![
+            "a.h:3:0: differs: foo: // some doc".to_string(),
+            "b.h:5:2: differs: foo: // some doc".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn xml_document_is_well_formed_and_escaped()
+    {
+        let empty = CheckReport { target: PathBuf::from("project"), display_base: PathBuf::from("project"), mismatches: Vec::new(), warnings: Vec::new(), groups: Vec::new() };
+        let xml = xml_document(&empty, false);
+        let doc = roxmltree::Document::parse(&xml).unwrap();
+        assert_eq!(doc.root_element().tag_name().name(), "docwen");
+        assert_eq!(doc.root_element().children().filter(|n| n.is_element()).count(), 0);
+
+        let mut special = mismatch(&[("project/a.h", 3, 0), ("project/a.c", 10, 4)]);
+        special.line = "// Returns a < b && \"c\" > 'd'".into();
+        let mismatches = vec![special, mismatch(&[("project/b.c", 5, 2)])];
+        let report = CheckReport { target: PathBuf::from("project"), display_base: PathBuf::from("project"), mismatches, warnings: Vec::new(), groups: Vec::new() };
+        let xml = xml_document(&report, false);
+        let doc = roxmltree::Document::parse(&xml).unwrap();
+
+        let mismatches: Vec<_> = doc.descendants().filter(|n| n.has_tag_name("mismatch")).collect();
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].attribute("kind"), Some("differs"));
+        let line = mismatches[0].children().find(|n| n.has_tag_name("line")).unwrap();
+        assert_eq!(line.text(), Some("// Returns a < b && \"c\" > 'd'"));
+        let positions: Vec<_> = mismatches[0].children().filter(|n| n.has_tag_name("position")).collect();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[1].attribute("row"), Some("10"));
+    }
+}
@@ -20,6 +20,21 @@ mod toml_manager_tests
         assert_eq!(written, DEFAULT_TOML);
     }
 
+    #[test]
+    fn create_annotated_writes_commented_valid_default()
+    {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("docwen.toml");
+
+        create_annotated(&file_path).unwrap();
+
+        let written = fs::read_to_string(&file_path).unwrap();
+        assert!(written.lines().any(|l| l.trim_start().starts_with('#')));
+        let annotated = Docfig::from_file(&file_path).unwrap();
+        let plain: Docfig = toml::from_str(DEFAULT_TOML).unwrap();
+        assert_eq!(annotated, plain);
+    }
+
     #[test]
     fn create_default_fails_if_file_exists()
     {
@@ -34,6 +49,28 @@ mod toml_manager_tests
         );
     }
 
+    #[test]
+    fn create_from_contains_discovered_groups()
+    {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src").join("foo.h"), "").unwrap();
+        fs::write(dir.path().join("src").join("foo.c"), "").unwrap();
+        fs::write(dir.path().join("src").join("bar.c"), "").unwrap();
+
+        let toml_path = dir.path().join("docwen.toml");
+        create_from(&toml_path, dir.path().join("src")).unwrap();
+
+        let docfig = Docfig::from_file(&toml_path).unwrap();
+        assert_eq!(docfig.settings.target, PathBuf::from("src"));
+        assert_eq!(docfig.file_groups.len(), 1);
+        assert_eq!(docfig.file_groups[0].name, "foo");
+        assert_eq!(docfig.file_groups[0].files.len(), 2);
+
+        let err = create_from(&toml_path, dir.path().join("src")).unwrap_err();
+        assert!(err.to_string().contains("Failed to create new docwen.toml"));
+    }
+
     /// Helper to build Settings with arbitrary match/manual sets
     fn make_settings(match_extensions: &[&str], manual: &[&str]) -> Settings
     {
@@ -41,8 +78,9 @@ mod toml_manager_tests
         {
             target: ".".into(),
             match_extensions: match_extensions.iter().map(|s| s.to_string()).collect(),
-            mode: MatchFunctionDocs,
+            mode: MatchFunctionDocs.into(),
             manual: manual.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
         }
     }
 
@@ -57,7 +95,7 @@ mod toml_manager_tests
             PathBuf::from("bar.txt"),
         ];
 
-        let groups = group_by_stem(paths, &settings);
+        let groups = group_files(paths, &settings).unwrap();
 
         let mut counts = std::collections::HashMap::new();
         for g in &groups
@@ -75,7 +113,7 @@ mod toml_manager_tests
         let settings = make_settings(&["c"], &["skipme"]);
         let paths = vec![PathBuf::from("skipme.c"), PathBuf::from("keepme.c")];
 
-        let groups = group_by_stem(paths, &settings);
+        let groups = group_files(paths, &settings).unwrap();
         let names: std::collections::HashSet<_> =
             groups.into_iter().map(|g| g.name).collect();
 
@@ -83,15 +121,44 @@ mod toml_manager_tests
         assert!(names.contains("keepme"));
     }
 
+    #[test]
+    fn group_by_stem_matches_manual_globs()
+    {
+        let settings = make_settings(&["c"], &["test_*", "tmp?"]);
+        let paths = vec![PathBuf::from("test_foo.c"), PathBuf::from("Test_Bar.c"), PathBuf::from("tmp1.c"),
+                         PathBuf::from("foo.c"), PathBuf::from("tmp12.c")];
+
+        let names: std::collections::HashSet<_> =
+            group_files(paths, &settings).unwrap().into_iter().map(|g| g.name).collect();
+
+        assert_eq!(names, ["foo", "tmp12"].map(String::from).into_iter().collect());
+
+        // Invalid globs are reported
+        let settings = make_settings(&["c"], &["test_[a"]);
+        assert!(group_files(vec![PathBuf::from("foo.c")], &settings).is_err());
+    }
+
     #[test]
     fn group_by_stem_extension_case_insensitive()
     {
         let settings = make_settings(&["h", "c"], &[]);
         let paths = vec![PathBuf::from("FoO.H"), PathBuf::from("foo.c")];
 
-        let groups = group_by_stem(paths, &settings);
+        let groups = group_files(paths, &settings).unwrap();
         assert_eq!(groups[0].files.len(), 2);
-        assert_eq!(groups[0].name, "foo");
+        assert_eq!(groups[0].name, "FoO");
+    }
+
+    #[test]
+    fn group_by_stem_preserves_case_of_first_file()
+    {
+        let settings = make_settings(&["h", "c"], &[]);
+        let paths = vec![PathBuf::from("FooBar.c"), PathBuf::from("foobar.h"), PathBuf::from("baz.c")];
+
+        let groups = group_files(paths, &settings).unwrap();
+        let foo_bar = groups.iter().find(|g| g.name == "FooBar").expect("Missing 'FooBar' group");
+        assert_eq!(foo_bar.files, vec![PathBuf::from("FooBar.c"), PathBuf::from("foobar.h")]);
+        assert!(!groups.iter().any(|g| g.name == "foobar"));
     }
 
     #[test]
@@ -100,7 +167,7 @@ mod toml_manager_tests
         let settings = make_settings(&[], &[]);
         let paths = vec![PathBuf::from("foo.c"), PathBuf::from("bar.h")];
 
-        let groups = group_by_stem(paths, &settings);
+        let groups = group_files(paths, &settings).unwrap();
         assert!(groups.is_empty());
     }
 
@@ -111,7 +178,7 @@ mod toml_manager_tests
         let paths = vec![PathBuf::from("SkipMe.c"), PathBuf::from("keepme.c")];
 
         let names: std::collections::HashSet<_> =
-            group_by_stem(paths, &settings).into_iter().map(|g| g.name).collect();
+            group_files(paths, &settings).unwrap().into_iter().map(|g| g.name).collect();
 
         assert!(!names.contains("skipme"));
         assert!(names.contains("keepme"));
@@ -123,11 +190,30 @@ mod toml_manager_tests
         let settings = make_settings(&["c"], &[]);
         let paths = vec![PathBuf::from(".hidden.c")];
 
-        let groups = group_by_stem(paths, &settings);
+        let groups = group_files(paths, &settings).unwrap();
         assert_eq!(groups.len(), 1);
         assert_eq!(groups[0].name, ".hidden");
     }
 
+    #[test]
+    fn create_without_default_extensions_groups_nothing()
+    {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("src");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("foo.c"), "").unwrap();
+        fs::write(root.join("foo.h"), "").unwrap();
+
+        let toml_path = dir.path().join("docwen.toml");
+        create_from_with(&toml_path, &root, &CreateOptions{no_default_extensions: true}).unwrap();
+        let docfig = Docfig::from_file(&toml_path).unwrap();
+        assert!(docfig.settings.match_extensions.is_empty());
+        assert!(docfig.file_groups.is_empty());
+
+        update_toml(&toml_path).unwrap();
+        assert!(Docfig::from_file(&toml_path).unwrap().file_groups.is_empty());
+    }
+
     #[test]
     fn update_toml_creates_and_updates_groups()
     {
@@ -146,7 +232,7 @@ mod toml_manager_tests
 
         update_toml(&toml_path).unwrap();
         let docfig = Docfig::from_file(&toml_path).unwrap();
-        let files = &docfig.file_groups.get(0).unwrap().files;
+        let files = &docfig.file_groups.first().unwrap().files;
         assert!(files.contains(&PathBuf::from(c_path.strip_prefix(&root).unwrap()))
             && files.contains(&PathBuf::from(h_path.strip_prefix(&root).unwrap())));
     }
@@ -178,7 +264,7 @@ mod toml_manager_tests
         update_toml(&toml_path).unwrap();
         let docfig = Docfig::from_file(&toml_path).unwrap();
 
-        let files = &docfig.file_groups.get(0).unwrap().files;
+        let files = &docfig.file_groups.first().unwrap().files;
         assert_eq!(docfig.file_groups.len(), 1);
         assert_eq!(files.len(), 2);
         assert!(files.contains(&PathBuf::from("bar.c")));
@@ -242,7 +328,7 @@ mod toml_manager_tests
 
         update_toml(&toml_path).unwrap();
         let docfig = Docfig::from_file(&toml_path).unwrap();
-        let files = &docfig.file_groups.get(0).unwrap().files;
+        let files = &docfig.file_groups.first().unwrap().files;
         assert!(files.contains(&PathBuf::from(c_path.strip_prefix(&root).unwrap()))
             && files.contains(&PathBuf::from(h_path.strip_prefix(&root).unwrap())));
     }
@@ -254,6 +340,7 @@ mod toml_manager_tests
         let dir  = tempdir().unwrap();
         let toml_path = dir.path().join("docwen.toml");
         create_default(&toml_path).unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
 
         // append a group ` with only foo.c
         let mut contents = fs::read_to_string(&toml_path).unwrap();
@@ -327,4 +414,174 @@ mod toml_manager_tests
             "Unexpected error: {err}"
         );
     }
+
+    #[test]
+    fn normalize_path_removes_cur_dir_and_resolves_parent_dir()
+    {
+        assert_eq!(normalize_path("/project/./src/../lib/./a.c"), PathBuf::from("/project/lib/a.c"));
+        assert_eq!(normalize_path("./a/b/.."), PathBuf::from("a"));
+        assert_eq!(normalize_path("../a"), PathBuf::from("../a"));
+        assert_eq!(normalize_path("/.."), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn group_by_stem_joins_aliased_stem_into_canonical_group()
+    {
+        let mut settings = make_settings(&["h", "c"], &[]);
+        settings.aliases.insert("foo_new".into(), "foo".into());
+
+        let paths = vec![PathBuf::from("foo.h"), PathBuf::from("foo_new.c"), PathBuf::from("bar.c")];
+        let groups = group_files(paths, &settings).unwrap();
+
+        let foo = groups.iter().find(|g| g.name == "foo").expect("Missing 'foo' group");
+        assert_eq!(foo.files.len(), 2);
+        assert!(foo.files.contains(&PathBuf::from("foo_new.c")));
+        assert!(!groups.iter().any(|g| g.name == "foo_new"));
+    }
+
+    #[test]
+    fn update_toml_skips_linked_files()
+    {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("src");
+        fs::create_dir_all(root.join("include")).unwrap();
+        fs::write(root.join("include/ifoo.h"), "").unwrap();
+        fs::write(root.join("foo.c"), "").unwrap();
+        fs::write(root.join("foo.h"), "").unwrap();
+
+        let toml_path = dir.path().join("docwen.toml");
+        create_default(&toml_path).unwrap();
+        let mut contents = fs::read_to_string(&toml_path).unwrap();
+        contents.push_str("\n[[link]]\nheader = \"include/ifoo.h\"\nsource = \"foo.c\"\n");
+        fs::write(&toml_path, contents).unwrap();
+
+        update_toml(&toml_path).unwrap();
+        let docfig = Docfig::from_file(&toml_path).unwrap();
+
+        assert!(docfig.file_groups.is_empty(), "Linked foo.c must not be grouped by stem: {:?}", docfig.file_groups);
+        assert_eq!(docfig.links.len(), 1, "Links must survive the update");
+    }
+
+    #[test]
+    fn find_toml_walks_up_to_nearest_ancestor()
+    {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        create_default(dir.path().join("docwen.toml")).unwrap();
+
+        assert_eq!(find_toml(&nested).unwrap(), dir.path().join("docwen.toml"));
+
+        // The nearest one wins
+        create_default(dir.path().join("src").join("docwen.toml")).unwrap();
+        assert_eq!(find_toml(&nested).unwrap(), dir.path().join("src").join("docwen.toml"));
+    }
+
+    #[test]
+    fn find_toml_errors_without_ancestor_config()
+    {
+        let dir = tempdir().unwrap();
+        // A directory named docwen.toml is no config
+        fs::create_dir(dir.path().join("docwen.toml")).unwrap();
+        match find_toml(dir.path())
+        {
+            Err(e) => assert!(e.to_string().contains("No docwen.toml found"), "Error was: {e}"),
+            // Only possible if the temp directory itself is inside a tree with a docwen.toml
+            Ok(found) => assert!(!found.starts_with(dir.path()), "Found: {found:?}"),
+        }
+    }
+
+    #[test]
+    fn create_uses_user_template()
+    {
+        let dir = tempdir().unwrap();
+        let template = dir.path().join("default.toml");
+        let content = "# House defaults\n[settings]\ntarget = \"source\"\nmatch_extensions = [\"h\", \"cpp\"]\nmanual = [\"generated\"]\n";
+        fs::write(&template, content).unwrap();
+
+        let file_path = dir.path().join("docwen.toml");
+        create_from_template(&file_path, Some(&template)).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), content);
+
+        // Without a template file the built-in default is used
+        let fallback_path = dir.path().join("fallback.toml");
+        create_from_template(&fallback_path, Some(&dir.path().join("missing.toml"))).unwrap();
+        assert_eq!(fs::read_to_string(&fallback_path).unwrap(), DEFAULT_TOML);
+    }
+
+    #[test]
+    fn user_template_without_default_extensions()
+    {
+        let dir = tempdir().unwrap();
+        let template = dir.path().join("default.toml");
+        fs::write(&template, "# House defaults\n[settings]\ntarget = \"source\"\nmatch_extensions = [\n    \"h\", \"cpp\"\n] # Ours\n").unwrap();
+
+        let options = CreateOptions{no_default_extensions: true};
+        let file_path = dir.path().join("docwen.toml");
+        create_from_template_with(&file_path, Some(&template), &options).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "# House defaults\n[settings]\ntarget = \"source\"\nmatch_extensions = [] # Ours\n");
+        assert!(Docfig::from_file(&file_path).unwrap().settings.match_extensions.is_empty());
+    }
+
+    #[test]
+    fn malformed_user_template_errors()
+    {
+        let dir = tempdir().unwrap();
+        let template = dir.path().join("default.toml");
+        fs::write(&template, "[settings]\ntarget = \"src\"\nunknown_setting = 1\n").unwrap();
+
+        let file_path = dir.path().join("docwen.toml");
+        let err = create_from_template(&file_path, Some(&template)).unwrap_err();
+        assert!(format!("{err:#}").contains("Invalid template"), "Error was: {err:#}");
+        assert!(!file_path.exists());
+    }
+
+    #[test]
+    fn group_regex_pairs_files_by_captured_key()
+    {
+        let mut settings = make_settings(&["h", "c"], &[]);
+        settings.group_regex = Some(r"^module_(\w+?)_(api|impl)\.".into());
+
+        let paths = vec![
+            PathBuf::from("module_foo_api.h"),
+            PathBuf::from("src/module_foo_impl.c"),
+            PathBuf::from("module_bar_api.h"),
+            PathBuf::from("foo.c"),
+        ];
+        let groups = group_files(paths, &settings).unwrap();
+
+        let foo = groups.iter().find(|g| g.name == "foo").expect("Missing 'foo' group");
+        assert_eq!(foo.files, vec![PathBuf::from("module_foo_api.h"), PathBuf::from("src/module_foo_impl.c")]);
+        assert_eq!(groups.len(), 2, "'foo.c' does not match and stays ungrouped: {groups:?}");
+    }
+
+    #[test]
+    fn invalid_group_regex_errors()
+    {
+        let mut settings = make_settings(&["h", "c"], &[]);
+        settings.group_regex = Some("module_(".into());
+        assert!(group_files(vec![PathBuf::from("module_foo.h")], &settings).is_err());
+
+        let toml = NamedTempFile::new().unwrap();
+        fs::write(toml.path(), "[settings]\ntarget = \".\"\ngroup_regex = \"module_.*\"\n").unwrap();
+        let err = Docfig::from_file(toml.path()).unwrap_err();
+        assert!(err.to_string().contains("no capture group"), "Error was: {err}");
+    }
+
+    #[test]
+    fn missing_target_root_is_reported_clearly()
+    {
+        let dir = tempdir().unwrap();
+        let toml_path = dir.path().join("docwen.toml");
+        fs::write(&toml_path, "[settings]\ntarget = \"missing\"\n").unwrap();
+
+        for err in [update_toml(&toml_path).unwrap_err(), docwen::docwen_check::check(&toml_path).unwrap_err()]
+        {
+            assert!(err.to_string().contains(&format!("Target root {} does not exist", dir.path().join("missing").display())),
+                    "Error was: {err}");
+        }
+
+        // Pure resolution does not access the file system
+        assert_eq!(get_absolute_root(&toml_path, "missing").unwrap(), dir.path().join("missing"));
+    }
 }
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tui_tests
+{
+    use std::fs;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyCode;
+    use ratatui::Terminal;
+    use tempfile::tempdir;
+    use docwen::docwen_check::{self, CheckOptions};
+    use docwen::tui::{App, DocPane};
+
+    /// Checks a workspace with the groups 'a' (one mismatch), 'b' (clean) and 'c' (two mismatches).
+    fn app() -> (tempfile::TempDir, App)
+    {
+        let dir = tempdir().unwrap();
+        let files = [("a.h", "// Doc A\nint foo();\n"), ("a.c", "// Doc B\nint foo() {}\n"),
+                     ("b.h", "// Doc\nint bar();\n"), ("b.c", "// Doc\nint bar() {}\n"),
+                     ("c.h", "// X\nint x();\n// Y\nint y();\n"), ("c.c", "// X2\nint x() {}\nint y() {}\n")];
+        files.iter().for_each(|(f, content)| fs::write(dir.path().join(f), content).unwrap());
+        let groups: String = ["a", "b", "c"].iter()
+            .map(|g| format!("[[filegroup]]\nname = \"{g}\"\nfiles = [\"{g}.h\", \"{g}.c\"]\n\n"))
+            .collect();
+        fs::write(dir.path().join("docwen.toml"), format!("[settings]\ntarget = \".\"\n\n{groups}")).unwrap();
+
+        let report = docwen_check::check_with_options(dir.path().join("docwen.toml"), &CheckOptions::default()).unwrap();
+        (dir, App::new(report))
+    }
+
+    #[test]
+    fn keys_navigate_mismatches_and_groups()
+    {
+        let (_dir, mut app) = app();
+        assert_eq!(app.mismatches.len(), 3);
+        assert_eq!(app.selected_group(), Some(0));
+
+        assert!(app.handle_key(KeyCode::Char('n')));
+        assert_eq!((app.selected, app.selected_group()), (1, Some(2)), "Clean groups are skipped");
+        app.handle_key(KeyCode::Char('G'));
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.selected, 2, "Stays at the last mismatch");
+        app.handle_key(KeyCode::Char('['));
+        assert_eq!(app.selected, 0);
+        app.handle_key(KeyCode::Char(']'));
+        assert_eq!(app.selected, 1);
+        app.handle_key(KeyCode::Char('k'));
+        assert_eq!(app.selected, 0);
+        assert!(!app.handle_key(KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn selected_mismatch_shows_doc_blocks_side_by_side()
+    {
+        let (_dir, app) = app();
+        assert_eq!(app.selected_panes(), [
+            DocPane { title: "a.h:2".into(), lines: vec!["// Doc A".into(), "int foo();".into()], highlight: Some(0) },
+            DocPane { title: "a.c:2".into(), lines: vec!["// Doc B".into(), "int foo() {}".into()], highlight: Some(0) },
+        ]);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|c| c.symbol()).collect();
+        for text in ["Groups", "Mismatches (3)", "[differs] foo()", "a.h:2", "a.c:2", "// Doc A", "// Doc B"]
+        {
+            assert!(screen.contains(text), "Missing '{text}' in:\n{screen}");
+        }
+    }
+}